@@ -0,0 +1,669 @@
+use crate::duperror::DupError;
+use crate::extents::shares_all_storage;
+use crate::hardlink::hardlink;
+use crate::keep::KeepPolicy;
+use crate::normalize::{normalized_hash, Normalization};
+use crate::reflink::reflink;
+use crate::trash::trash;
+use crate::utilities::PathUtilities;
+use crate::utilities::{absolute, file_id, move_file, quarantine_path, relative_path, temp_path};
+use crate::{DupGroup, DupResults};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// What is done with the duplicates of the file kept from a group.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Action {
+	/// Deletes the duplicates.
+	Delete,
+
+	/// Replaces the duplicates with hard links to the kept file.
+	///
+	/// Each hard link is made under a temporary name and renamed over the
+	/// duplicate, so the duplicate is either replaced or left unchanged.
+	Hardlink,
+
+	/// Replaces the duplicates with symbolic links to the kept file, using
+	/// its absolute path.
+	Symlink,
+
+	/// Replaces the duplicates with symbolic links to the kept file, using
+	/// its path relative to each link.
+	RelativeSymlink,
+
+	/// Replaces the duplicates with copy-on-write clones of the kept file,
+	/// on filesystems that support it.
+	Reflink,
+
+	/// Moves the duplicates into the given quarantine directory, under the
+	/// same directory structure as their absolute paths, rather than deleting
+	/// them.
+	MoveTo(PathBuf),
+
+	/// Moves the duplicates to the system's trash or recycle bin, so they
+	/// can be restored if needed.
+	Trash,
+}
+
+impl FromStr for Action {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Action, String> {
+		match s {
+			"delete" => Ok(Action::Delete),
+			"hardlink" => Ok(Action::Hardlink),
+			"symlink" => Ok(Action::Symlink),
+			"relative-symlink" => Ok(Action::RelativeSymlink),
+			"reflink" => Ok(Action::Reflink),
+			"trash" => Ok(Action::Trash),
+			_ => Err(format!("unknown action {}", s)),
+		}
+	}
+}
+
+/// Whether to go ahead with acting on a group of duplicate files, returned
+/// by the confirmation callback given to `DupResults::apply_actions_with()`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Decision {
+	/// Acts on the group.
+	Proceed,
+
+	/// Leaves the group unchanged and moves on to the next group.
+	Skip,
+
+	/// Leaves the group and all remaining groups unchanged.
+	Abort,
+}
+
+/// A plan for acting on a group of duplicate files, giving the file to keep
+/// and the files to remove or replace.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ActionPlan {
+	/// The hash of the group this plan is for.
+	hash: String,
+
+	/// The action to take on the files to remove.
+	action: Action,
+
+	/// The path to the file to keep.
+	keep: PathBuf,
+
+	/// The paths to the files to remove or replace.
+	remove: Vec<PathBuf>,
+
+	/// The normalizations applied to the files' contents when they were
+	/// compared.
+	normalizations: Vec<Normalization>,
+}
+
+impl ActionPlan {
+	/// Returns the hash of the group this plan is for.
+	pub fn get_hash(&self) -> &str {
+		&self.hash
+	}
+
+	/// Returns a reference to the action to take on the files to remove.
+	pub fn action(&self) -> &Action {
+		&self.action
+	}
+
+	/// Returns the path to the file to keep.
+	pub fn keep(&self) -> &Path {
+		&self.keep
+	}
+
+	/// Returns a reference to the paths to the files to remove or replace.
+	pub fn remove(&self) -> &[PathBuf] {
+		&self.remove
+	}
+
+	/// Returns the path that a symbolic link replacing the given file should
+	/// point to, which is either the absolute path to the file to keep or,
+	/// for `Action::RelativeSymlink`, its path relative to the link.
+	pub fn link_target(&self, file: &Path) -> PathBuf {
+		let keep = absolute(&self.keep);
+
+		match (&self.action, absolute(file).parent()) {
+			(Action::RelativeSymlink, Some(link_dir)) => relative_path(link_dir, &keep),
+			_ => keep,
+		}
+	}
+
+	/// Carries out the plan, adding the files acted on, the bytes reclaimed
+	/// and any errors to the given `ActionResults`.
+	///
+	/// Before any files are changed, the file to keep and the files to remove
+	/// are hashed again.  If any of them have changed since they were checked,
+	/// so they're no longer duplicates of each other or no longer have the
+	/// group's hash, no files are changed and a conflict is recorded.
+	///
+	/// Before a hard link action is carried out, all files are checked again
+	/// to be on the same filesystem as the file to keep, as when the plan was
+	/// made; if any aren't, no files are changed and an error is recorded.
+	/// Files that are already hard links to the file to keep are skipped, as
+	/// are files that already share all of their storage with it for a
+	/// reflink action.  Each file is replaced by renaming a new hard link over
+	/// it, so if replacing a file fails it's left unchanged, although a
+	/// temporary link may be left behind if it can't be cleaned up, which is
+	/// given in the error.
+	///
+	/// Files moved to a quarantine directory or the trash aren't counted
+	/// towards the bytes reclaimed, since they're still stored.
+	///
+	/// If a reflink action fails because the filesystem doesn't support
+	/// cloning files, the file is left unchanged and a warning is recorded
+	/// rather than an error.
+	pub fn execute(&self, results: &mut ActionResults) {
+		let keep_metadata = match fs::metadata(&self.keep) {
+			Ok(m) => m,
+			Err(e) => {
+				results.errors.push(DupError::new(self.keep.clone(), e));
+				return;
+			}
+		};
+		let keep_id = file_id(&keep_metadata);
+
+		if !self.check_unchanged(results) {
+			return;
+		}
+
+		if self.action == Action::Hardlink {
+			if let Err(e) = self.check_same_device() {
+				results.errors.push(DupError::new(self.keep.clone(), e));
+				return;
+			}
+		}
+
+		for file in &self.remove {
+			let metadata = match fs::symlink_metadata(file) {
+				Ok(m) => m,
+				Err(e) => {
+					results.errors.push(DupError::new(file.clone(), e));
+					continue;
+				}
+			};
+
+			if keep_id.is_some() && file_id(&metadata) == keep_id {
+				continue;
+			}
+
+			if self.action == Action::Reflink && shares_all_storage(&self.keep, file) {
+				continue;
+			}
+
+			let outcome = match &self.action {
+				Action::Delete => fs::remove_file(file),
+				Action::Hardlink => hardlink(&self.keep, file),
+				Action::Symlink | Action::RelativeSymlink => symlink(&self.link_target(file), file),
+				Action::Reflink => reflink(&self.keep, file),
+				Action::MoveTo(dir) => move_file(file, &quarantine_path(dir, file)),
+				Action::Trash => trash(file),
+			};
+
+			match outcome {
+				Ok(()) => {
+					results
+						.completed
+						.push((self.action.clone(), file.clone(), self.keep.clone()));
+
+					if !matches!(self.action, Action::MoveTo(_) | Action::Trash) {
+						results.bytes_reclaimed += metadata.len();
+					}
+				}
+				Err(e) if e.kind() == io::ErrorKind::Unsupported => {
+					results.warnings.push(DupError::new(file.clone(), e))
+				}
+				Err(e) => results.errors.push(DupError::new(file.clone(), e)),
+			};
+		}
+	}
+
+	/// Returns whether the file to keep and the files to remove still have the
+	/// same contents as each other, and the group's hash if it's a BLAKE3
+	/// hash, recording a conflict or error in the given `ActionResults` if
+	/// not.  Contents are normalized in the same way as when the files were
+	/// compared.
+	fn check_unchanged(&self, results: &mut ActionResults) -> bool {
+		let hash = |file: &PathBuf| match self.normalizations.is_empty() {
+			true => file.blake3(),
+			false => normalized_hash(file, &self.normalizations),
+		};
+		let keep_hash = match hash(&self.keep) {
+			Ok(h) => h,
+			Err(e) => {
+				results.errors.push(DupError::new(self.keep.clone(), e));
+				return false;
+			}
+		};
+		let is_blake3 = self.hash.len() == 64 && self.hash.chars().all(|c| c.is_ascii_hexdigit());
+
+		if is_blake3 && keep_hash != self.hash {
+			results
+				.conflicts
+				.push(DupError::new(self.keep.clone(), changed_error()));
+			return false;
+		}
+
+		for file in &self.remove {
+			match hash(file) {
+				Ok(h) if h == keep_hash => {}
+				Ok(_) => {
+					results
+						.conflicts
+						.push(DupError::new(file.clone(), changed_error()));
+					return false;
+				}
+				Err(e) => {
+					results.errors.push(DupError::new(file.clone(), e));
+					return false;
+				}
+			};
+		}
+
+		true
+	}
+
+	/// Returns an error if any file to remove is on a different filesystem to
+	/// the file to keep, so can't be hard linked to it, suggesting actions
+	/// that can be used instead.
+	fn check_same_device(&self) -> io::Result<()> {
+		let keep_device = match file_id(&fs::metadata(&self.keep)?) {
+			Some((device, _)) => device,
+			None => return Ok(()),
+		};
+
+		for file in &self.remove {
+			let metadata = fs::metadata(file)
+				.map_err(|e| io::Error::new(e.kind(), format!("{}: {}", file.display(), e)))?;
+
+			if let Some((device, _)) = file_id(&metadata) {
+				if device != keep_device {
+					return Err(io::Error::new(
+						io::ErrorKind::Unsupported,
+						format!(
+							"{} is on a different filesystem to {}, so can't be hard linked to it; use symbolic links or reflinks instead",
+							file.display(),
+							self.keep.display()
+						),
+					));
+				}
+			}
+		}
+
+		Ok(())
+	}
+}
+
+/// Results of carrying out action plans.
+#[derive(Debug, Default)]
+pub struct ActionResults {
+	/// The actions completed, with the paths acted on and the paths of the
+	/// files kept in their place.
+	completed: Vec<(Action, PathBuf, PathBuf)>,
+
+	/// The number of bytes reclaimed by the completed actions.
+	bytes_reclaimed: u64,
+
+	/// Errors encountered while carrying out actions.
+	errors: Vec<DupError>,
+
+	/// Actions that weren't carried out because they aren't supported, where
+	/// the files were left unchanged.
+	warnings: Vec<DupError>,
+
+	/// Files that had changed since they were checked, causing their groups
+	/// to be left unchanged.
+	conflicts: Vec<DupError>,
+
+	/// Plans for groups that were skipped when confirming actions.
+	skipped: Vec<ActionPlan>,
+
+	/// Whether carrying out actions was aborted when confirming actions.
+	aborted: bool,
+}
+
+impl ActionResults {
+	/// Creates a new, empty `ActionResults`.
+	pub fn new() -> ActionResults {
+		ActionResults::default()
+	}
+
+	/// Returns a reference to the completed actions, with the paths acted on
+	/// and the paths of the files kept in their place.
+	pub fn completed(&self) -> &[(Action, PathBuf, PathBuf)] {
+		&self.completed
+	}
+
+	/// Returns the number of bytes reclaimed by the completed actions.
+	pub fn bytes_reclaimed(&self) -> u64 {
+		self.bytes_reclaimed
+	}
+
+	/// Returns a reference to the errors.
+	pub fn errors(&self) -> &[DupError] {
+		&self.errors
+	}
+
+	/// Returns a reference to the warnings for actions that weren't carried
+	/// out because they aren't supported, where the files were left
+	/// unchanged.
+	pub fn warnings(&self) -> &[DupError] {
+		&self.warnings
+	}
+
+	/// Returns a reference to the conflicts for files that had changed since
+	/// they were checked, causing their groups to be left unchanged.
+	pub fn conflicts(&self) -> &[DupError] {
+		&self.conflicts
+	}
+
+	/// Returns a reference to the plans for groups that were skipped when
+	/// confirming actions.
+	pub fn skipped(&self) -> &[ActionPlan] {
+		&self.skipped
+	}
+
+	/// Returns whether carrying out actions was aborted when confirming
+	/// actions, leaving any remaining groups unchanged.
+	pub fn aborted(&self) -> bool {
+		self.aborted
+	}
+}
+
+/// Returns the error recorded for a file that changed since it was checked.
+fn changed_error() -> io::Error {
+	io::Error::other("file has changed since it was checked; group skipped")
+}
+
+/// Replaces `link` with a symbolic link to `original`.
+///
+/// The symbolic link is made under a temporary name in the same directory as
+/// `link` and then renamed over it, so `link` is never missing, even if the
+/// process is interrupted part way through.
+///
+/// # Errors
+///
+/// Returns an error if creating the temporary link fails, in which case
+/// `link` is left unchanged.  Returns an error if renaming the temporary link
+/// over `link` fails, in which case `link` is left unchanged and the
+/// temporary link is removed; if it can't be removed, the error gives its
+/// path so it can be removed by hand.
+fn symlink(original: &Path, link: &Path) -> io::Result<()> {
+	let temp = temp_path(link);
+	symlink_file(original, &temp)?;
+
+	if let Err(e) = fs::rename(&temp, link) {
+		if fs::remove_file(&temp).is_err() {
+			return Err(io::Error::new(
+				e.kind(),
+				format!("{}; temporary link {} left behind", e, temp.display()),
+			));
+		}
+
+		return Err(e);
+	}
+
+	Ok(())
+}
+
+/// Creates a symbolic link to a file.
+#[cfg(unix)]
+fn symlink_file(original: &Path, link: &Path) -> io::Result<()> {
+	std::os::unix::fs::symlink(original, link)
+}
+
+/// Creates a symbolic link to a file.
+#[cfg(windows)]
+fn symlink_file(original: &Path, link: &Path) -> io::Result<()> {
+	std::os::windows::fs::symlink_file(original, link)
+}
+
+/// Creates a symbolic link to a file, which isn't supported on this
+/// platform.
+#[cfg(not(any(unix, windows)))]
+fn symlink_file(_original: &Path, _link: &Path) -> io::Result<()> {
+	Err(io::Error::new(
+		io::ErrorKind::Unsupported,
+		"symbolic links aren't supported on this platform",
+	))
+}
+
+impl DupGroup {
+	/// Returns a plan for acting on this group, keeping the group's
+	/// canonical copy if one has been designated, or otherwise the file
+	/// selected by the keep policy.
+	///
+	/// # Errors
+	///
+	/// Returns an error if the keep policy depends on file metadata that
+	/// can't be read.  For `Action::Hardlink`, also returns an error if any
+	/// of the files are on a different filesystem to the file to keep, since
+	/// hard links can't be made to them, or if their metadata can't be read.
+	///
+	/// Also returns an error for hard links, symbolic links and reflinks if
+	/// the group's files were compared after normalizing their contents, as
+	/// given by `normalizations()`, since their bytes may differ and
+	/// replacing them would change their contents.
+	pub fn plan(&self, policy: &KeepPolicy, action: Action) -> io::Result<ActionPlan> {
+		let replaces = matches!(
+			action,
+			Action::Hardlink | Action::Symlink | Action::RelativeSymlink | Action::Reflink
+		);
+
+		if replaces && !self.normalizations.is_empty() {
+			return Err(io::Error::new(
+				io::ErrorKind::Unsupported,
+				"files matched after normalizing their contents can't be linked to each other, as their bytes may differ; delete, move or trash them instead",
+			));
+		}
+
+		let keep_index = match self.canonical {
+			Some(i) => i,
+			None => policy.select(self)?,
+		};
+		let mut remove: Vec<PathBuf> = self.files.iter().map(|f| f.to_path_buf()).collect();
+		let keep = remove.remove(keep_index);
+		let plan = ActionPlan {
+			hash: self.hash.clone(),
+			action,
+			keep,
+			remove,
+			normalizations: self.normalizations.clone(),
+		};
+
+		if plan.action == Action::Hardlink {
+			plan.check_same_device()?;
+		}
+
+		Ok(plan)
+	}
+}
+
+impl DupResults {
+	/// Returns plans for acting on all duplicate file groups, keeping each
+	/// group's canonical copy or otherwise the file selected by the keep
+	/// policy; and also returns errors for any groups that couldn't be
+	/// planned.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use dupcheck::{Action, KeepPolicy};
+	/// use std::path::PathBuf;
+	///
+	/// let dirs = vec![PathBuf::from("foo")];
+	/// let mut dup_result = dupcheck::DupResults::new();
+	///
+	/// if let Err(dup_error) = dup_result.within(&dirs) {
+	///     // Error handling
+	/// }
+	///
+	/// let (plans, errors) = dup_result.plan_actions(&KeepPolicy::Oldest, Action::Delete);
+	///
+	/// for plan in &plans {
+	///     println!("Keeping {}", plan.keep().display());
+	/// }
+	/// ```
+	pub fn plan_actions(
+		&self,
+		policy: &KeepPolicy,
+		action: Action,
+	) -> (Vec<ActionPlan>, Vec<DupError>) {
+		let mut plans = vec![];
+		let mut errors = vec![];
+
+		for group in &self.duplicates {
+			match group.plan(policy, action.clone()) {
+				Ok(plan) => plans.push(plan),
+				Err(e) => errors.push(DupError::new(group.files[0].to_path_buf(), e)),
+			};
+		}
+
+		(plans, errors)
+	}
+
+	/// Carries out the given action on all duplicate file groups, keeping
+	/// each group's canonical copy or otherwise the file selected by the keep
+	/// policy, and returns the results.
+	///
+	/// See `ActionPlan::execute()` for details of how plans are carried out.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use dupcheck::{Action, KeepPolicy};
+	/// use std::path::PathBuf;
+	///
+	/// let dirs = vec![PathBuf::from("foo")];
+	/// let mut dup_result = dupcheck::DupResults::new();
+	///
+	/// if let Err(dup_error) = dup_result.within(&dirs) {
+	///     // Error handling
+	/// }
+	///
+	/// let results = dup_result.apply_actions(&KeepPolicy::Oldest, Action::Hardlink);
+	/// println!("{} bytes reclaimed", results.bytes_reclaimed());
+	/// ```
+	pub fn apply_actions(&self, policy: &KeepPolicy, action: Action) -> ActionResults {
+		self.apply_actions_with(policy, action, |_, _| Decision::Proceed)
+	}
+
+	/// Carries out the given action on all duplicate file groups, as with
+	/// `apply_actions()`, but calls `confirm` with each group and its plan
+	/// before the group is changed, to decide whether to proceed with the
+	/// group, skip it or abort all remaining groups.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use dupcheck::{Action, Decision, KeepPolicy};
+	/// use std::path::PathBuf;
+	///
+	/// let dirs = vec![PathBuf::from("foo")];
+	/// let mut dup_result = dupcheck::DupResults::new();
+	///
+	/// if let Err(dup_error) = dup_result.within(&dirs) {
+	///     // Error handling
+	/// }
+	///
+	/// let results = dup_result.apply_actions_with(&KeepPolicy::First, Action::Delete, |group, plan| {
+	///     if plan.remove().len() > 10 {
+	///         Decision::Skip
+	///     } else {
+	///         Decision::Proceed
+	///     }
+	/// });
+	/// ```
+	pub fn apply_actions_with<F>(
+		&self,
+		policy: &KeepPolicy,
+		action: Action,
+		mut confirm: F,
+	) -> ActionResults
+	where
+		F: FnMut(&DupGroup, &ActionPlan) -> Decision,
+	{
+		let mut results = ActionResults::new();
+
+		for group in &self.duplicates {
+			let plan = match group.plan(policy, action.clone()) {
+				Ok(p) => p,
+				Err(e) => {
+					results
+						.errors
+						.push(DupError::new(group.files[0].to_path_buf(), e));
+					continue;
+				}
+			};
+
+			match confirm(group, &plan) {
+				Decision::Proceed => plan.execute(&mut results),
+				Decision::Skip => results.skipped.push(plan),
+				Decision::Abort => {
+					results.aborted = true;
+					break;
+				}
+			};
+		}
+
+		results
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::script::ScriptFormat;
+	use std::sync::Arc;
+
+	/// Returns results with one group of files matched after decompressing
+	/// them.
+	fn gzip_results() -> DupResults {
+		let files = vec![
+			Arc::from(Path::new("a.txt")),
+			Arc::from(Path::new("b.txt.gz")),
+		];
+		let mut group = DupGroup::new(String::from("0"), files);
+		group.normalizations = vec![Normalization::Gzip];
+		let mut results = DupResults::new();
+		results.duplicates.push(group);
+		results
+	}
+
+	#[test]
+	fn normalized_groups_are_not_replaced() {
+		let results = gzip_results();
+		let group = &results.duplicates[0];
+
+		for action in [
+			Action::Hardlink,
+			Action::Symlink,
+			Action::RelativeSymlink,
+			Action::Reflink,
+		] {
+			let error = group.plan(&KeepPolicy::First, action).unwrap_err();
+			assert_eq!(error.kind(), io::ErrorKind::Unsupported);
+		}
+
+		assert!(group.plan(&KeepPolicy::First, Action::Delete).is_ok());
+		assert!(group.plan(&KeepPolicy::First, Action::Trash).is_ok());
+	}
+
+	#[test]
+	fn normalized_groups_are_not_replaced_by_scripts() {
+		let results = gzip_results();
+
+		for format in [ScriptFormat::Sh, ScriptFormat::PowerShell] {
+			let mut script = vec![];
+			results
+				.write_script(&mut script, format, &KeepPolicy::First, Action::Hardlink)
+				.unwrap();
+			let script = String::from_utf8(script).unwrap();
+
+			assert!(script.contains("# Skipped, as the group couldn't be planned"));
+			assert!(!script.contains("b.txt.gz'"));
+		}
+	}
+}
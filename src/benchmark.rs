@@ -0,0 +1,216 @@
+use crate::hash::{hash_file, hash_file_chunked, DEFAULT_BUFFER_SIZE};
+use crate::mmap::blake3_mapped;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+/// The most bytes of files read by each round of a `Benchmark`.
+const SAMPLE_BYTES: u64 = 64 * 1024 * 1024;
+
+/// The most files read by each round of a `Benchmark`.
+const SAMPLE_FILES: usize = 64;
+
+/// The number of times each read method is timed by a `Benchmark`, with the
+/// fastest time kept.
+const ROUNDS: usize = 3;
+
+/// A way of reading files to hash them.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ReadMethod {
+	/// Reads files in chunks into a buffer.  This is the default.
+	#[default]
+	Buffered,
+
+	/// Maps files into memory, on Unix, which avoids copying their data and
+	/// can be faster for large files.  If another process truncates a file
+	/// while it's being hashed, the process is ended by `SIGBUS`, so this
+	/// shouldn't be used on files that may be changing.  Files are read into
+	/// a buffer on other platforms.
+	Mmap,
+}
+
+impl FromStr for ReadMethod {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<ReadMethod, String> {
+		match s {
+			"buffered" => Ok(ReadMethod::Buffered),
+			"mmap" => Ok(ReadMethod::Mmap),
+			_ => Err(format!("unknown read method {}", s)),
+		}
+	}
+}
+
+impl ReadMethod {
+	/// Returns the BLAKE3 hash of a file, read with this method and, if it's
+	/// read into a buffer, the given buffer.
+	pub(crate) fn hash(&self, path: &Path, buffer: &mut [u8]) -> io::Result<String> {
+		match self {
+			ReadMethod::Buffered => hash_file_chunked(path, buffer),
+			ReadMethod::Mmap => blake3_mapped(path),
+		}
+	}
+}
+
+/// How fast files on some storage are hashed with each `ReadMethod`, for
+/// choosing the fastest way to hash them.
+///
+/// Files are always hashed with BLAKE3, so the results of checks don't
+/// depend on the read method chosen.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Benchmark {
+	/// The number of bytes hashed by each round.
+	bytes: u64,
+
+	/// The fastest time taken to hash the sample with each read method.
+	times: Vec<(ReadMethod, Duration)>,
+}
+
+impl Benchmark {
+	/// Measures how fast files within the given directories are hashed with
+	/// each read method.
+	///
+	/// A sample of up to 64 files and 64 MiB, or a single larger file, is
+	/// taken from the directories and read once so that every method reads
+	/// them from the same place, then hashed three times with each method,
+	/// keeping the fastest time.
+	/// Files that can't be read are left out of the sample, and files read
+	/// into a buffer use a buffer of `DEFAULT_BUFFER_SIZE`.  The sample is
+	/// usually in the page cache after the first read, so this measures the
+	/// cost of reading and hashing rather than the speed of the storage.
+	///
+	/// # Errors
+	///
+	/// Returns an error if any `dirs` are not directories.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// match dupcheck::Benchmark::run(&["foo"]) {
+	///     Ok(benchmark) => {
+	///         let mut options = dupcheck::DupOptions::new();
+	///         options.set_read_method(benchmark.fastest());
+	///     }
+	///     Err(benchmark_error) => {
+	///         // Error handling
+	///     }
+	/// }
+	/// ```
+	pub fn run<T: AsRef<Path>>(dirs: &[T]) -> io::Result<Benchmark> {
+		if let Some(dir) = dirs.iter().map(|d| d.as_ref()).find(|d| !d.is_dir()) {
+			return Err(io::Error::new(
+				io::ErrorKind::InvalidInput,
+				format!("{} is not a directory", dir.display()),
+			));
+		}
+
+		let mut samples = sample_files(dirs);
+		samples.retain(|f| hash_file(f).is_ok());
+		let bytes = samples
+			.iter()
+			.filter_map(|f| f.metadata().ok())
+			.map(|m| m.len())
+			.sum();
+
+		let methods = [ReadMethod::Buffered, ReadMethod::Mmap];
+		let mut times: Vec<(ReadMethod, Duration)> =
+			methods.iter().map(|m| (*m, Duration::MAX)).collect();
+		let mut buffer = vec![0; DEFAULT_BUFFER_SIZE];
+
+		// The methods take turns, so any change in the system's load affects
+		// them all alike.
+		for _ in 0..ROUNDS {
+			for (method, time) in times.iter_mut() {
+				let start = Instant::now();
+
+				for file in &samples {
+					let _ = method.hash(file, &mut buffer);
+				}
+
+				*time = (*time).min(start.elapsed());
+			}
+		}
+
+		Ok(Benchmark { bytes, times })
+	}
+
+	/// Returns the number of bytes hashed by each round of the benchmark.
+	pub fn bytes(&self) -> u64 {
+		self.bytes
+	}
+
+	/// Returns how many bytes per second were hashed with the given read
+	/// method, or `None` if nothing was hashed.
+	pub fn throughput(&self, method: ReadMethod) -> Option<u64> {
+		let (_, time) = self.times.iter().find(|(m, _)| *m == method)?;
+
+		match self.bytes {
+			0 => None,
+			bytes => Some((bytes as f64 / time.as_secs_f64().max(f64::EPSILON)) as u64),
+		}
+	}
+
+	/// Returns the read method that hashed the sample fastest, out of those
+	/// that are safe with files that may be changing.  Memory mapping is
+	/// never chosen, as a file truncated while it's mapped ends the process,
+	/// so it has to be set explicitly.
+	pub fn fastest(&self) -> ReadMethod {
+		self
+			.times
+			.iter()
+			.map(|(method, _)| *method)
+			.filter(|method| *method != ReadMethod::Mmap)
+			.max_by_key(|method| self.throughput(*method).unwrap_or(0))
+			.unwrap_or_default()
+	}
+}
+
+/// Returns up to `SAMPLE_FILES` non-empty files within the given
+/// directories, totalling at most `SAMPLE_BYTES` apart from the first file,
+/// searching the directories breadth first so the sample is found quickly.
+fn sample_files<T: AsRef<Path>>(dirs: &[T]) -> Vec<PathBuf> {
+	let mut dirs: Vec<PathBuf> = dirs.iter().map(|d| d.as_ref().to_path_buf()).collect();
+	let mut files = vec![];
+	let mut bytes = 0;
+
+	while !dirs.is_empty() {
+		for dir in dirs.split_off(0) {
+			let entries = match dir.read_dir() {
+				Ok(entries) => entries,
+				Err(_) => continue,
+			};
+
+			for entry in entries.filter_map(|e| e.ok()) {
+				let path = entry.path();
+				let file_type = match entry.file_type() {
+					Ok(t) => t,
+					Err(_) => continue,
+				};
+
+				if file_type.is_dir() {
+					dirs.push(path);
+					continue;
+				}
+
+				let len = match entry.metadata() {
+					Ok(m) if file_type.is_file() => m.len(),
+					_ => continue,
+				};
+
+				if len == 0 || (!files.is_empty() && bytes + len > SAMPLE_BYTES) {
+					continue;
+				}
+
+				files.push(path);
+				bytes += len;
+
+				if files.len() == SAMPLE_FILES || bytes >= SAMPLE_BYTES {
+					return files;
+				}
+			}
+		}
+	}
+
+	files
+}
@@ -0,0 +1,151 @@
+use crate::events::ScanEvent;
+use crate::pipeline::Stage;
+use crate::{DupGroup, DupResults};
+use std::collections::{HashMap, HashSet};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+impl DupResults {
+	/// Checks for files in `files_a` that are duplicates of files in
+	/// `files_b`, such as files being imported that are already in a
+	/// library, and returns the results.
+	///
+	/// Only groups with files from both lists are kept, so duplicates within
+	/// just one of the lists aren't reported.  A kept group contains all of
+	/// the files from either list with the same contents.  Files in both
+	/// lists are treated as being in `files_a`.  When the comparison pipeline
+	/// compares sizes, files with no file of the same size in the other list
+	/// aren't compared further.
+	///
+	/// Groups are sent to `DupOptions::group_sender()`, and as events, once
+	/// all of the files have been compared, since groups within one list
+	/// aren't known to be left out until then.
+	///
+	/// # Errors
+	///
+	/// The returned `DupResults` will contain errors if any paths within
+	/// either list are not files or if I/O errors occur while trying to read
+	/// files.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use std::path::PathBuf;
+	///
+	/// let imports = vec![PathBuf::from("import/a.jpg"), PathBuf::from("import/b.jpg")];
+	/// let library = vec![PathBuf::from("photos/c.jpg"), PathBuf::from("photos/d.jpg")];
+	/// let mut dup_result = dupcheck::DupResults::new();
+	///
+	/// if let Err(dup_error) = dup_result.between(&imports, &library) {
+	///     // Error handling
+	/// }
+	///
+	/// for group in dup_result.duplicates() {
+	///     println!("{:?} is already in the library", group.get_files());
+	/// }
+	/// ```
+	pub fn between<T: AsRef<Path>>(&mut self, files_a: &[T], files_b: &[T]) -> io::Result<()> {
+		self.check_valid_paths(Some(files_a), None)?;
+		self.check_valid_paths(Some(files_b), None)?;
+
+		let mut files_a = self.convert_to_path_buf(files_a);
+		let in_a: HashSet<PathBuf> = files_a.iter().cloned().collect();
+		let mut files_b = self.convert_to_path_buf(files_b);
+		files_b.retain(|f| !in_a.contains(f));
+
+		// Files whose sizes can't be read are kept so their errors are found
+		// when they're compared.
+		if self.options.pipeline().contains(Stage::Size) {
+			let sizes_a = self.sizes(&files_a);
+			let sizes_b = self.sizes(&files_b);
+			let lens_a: HashSet<u64> = sizes_a.values().copied().collect();
+			let lens_b: HashSet<u64> = sizes_b.values().copied().collect();
+			files_a.retain(|f| sizes_a.get(f).is_none_or(|s| lens_b.contains(s)));
+			files_b.retain(|f| sizes_b.get(f).is_none_or(|s| lens_a.contains(s)));
+		}
+
+		// Groups are only sent from the check once they're known to span
+		// both lists.
+		let mut options = self.options.clone();
+		options.set_group_sender(None);
+		options.set_event_sender(None);
+
+		let mut check = DupResults::with_options(options);
+		let files: Vec<PathBuf> = files_a.iter().chain(&files_b).cloned().collect();
+		check.files(&files)?;
+
+		for dup_error in check.errors {
+			self
+				.options
+				.emit(|| ScanEvent::Error(dup_error.to_string()));
+			self.errors.push(dup_error);
+		}
+
+		self.warnings.append(&mut check.warnings);
+		self.skipped.append(&mut check.skipped);
+		self.unique.append(&mut check.unique);
+
+		for group in check.duplicates {
+			let from_a = group.files.iter().filter(|f| in_a.contains(&***f)).count();
+
+			if from_a > 0 && from_a < group.files.len() {
+				self.merge_group(group);
+			}
+		}
+
+		self.finished(files.len());
+
+		Ok(())
+	}
+
+	/// Returns the sizes of the given files that can be read, keyed by their
+	/// paths.
+	fn sizes(&self, files: &[PathBuf]) -> HashMap<PathBuf, u64> {
+		let retry = self.options.retry_policy();
+
+		files
+			.iter()
+			.filter_map(|f| {
+				retry
+					.run(|| f.metadata())
+					.ok()
+					.map(|m| (f.clone(), m.len()))
+			})
+			.collect()
+	}
+
+	/// Adds a group to the results, adding its files to any existing group
+	/// with the same hash, and sends it to the group sender and as an event.
+	fn merge_group(&mut self, group: DupGroup) {
+		let i = match self.duplicates.iter().position(|g| g.hash == group.hash) {
+			Some(i) => {
+				let existing = &mut self.duplicates[i];
+
+				for (j, file) in group.files.iter().enumerate() {
+					if !existing.contains(file) {
+						existing.push_file(
+							Arc::clone(file),
+							group.roots.get(j).cloned().flatten(),
+							group.metadata_at(j).copied(),
+						);
+					}
+				}
+
+				i
+			}
+			None => {
+				self.duplicates.push(group);
+				self.duplicates.len() - 1
+			}
+		};
+
+		if let Some(sender) = self.options.group_sender() {
+			let _ = sender.send(self.duplicates[i].clone());
+		}
+
+		self
+			.options
+			.emit(|| ScanEvent::GroupUpdated(Box::new(self.duplicates[i].clone())));
+	}
+}
@@ -0,0 +1,837 @@
+use crate::events::SkipReason;
+use crate::metadata::FileMetadata;
+use crate::normalize::Normalization;
+use crate::pipeline::Stage;
+use crate::{DupGroup, DupResults};
+use std::collections::HashMap;
+use std::io;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+/// The start of a binary results file.
+const RESULTS_MAGIC: &[u8; 4] = b"DUPR";
+
+/// The version of the binary format written.
+pub(crate) const VERSION: u8 = 1;
+
+/// Writes values in dupcheck's binary format.
+///
+/// Numbers are written as LEB128 variable-length integers, strings and
+/// paths with their length first, and BLAKE3 hashes as their 32 raw bytes.
+/// Each path is written as the number of leading bytes it shares with the
+/// previous path written and the bytes that follow, so the many paths
+/// sharing directories take little space.
+pub(crate) struct Encoder<W: Write> {
+	/// The writer values are written to.
+	writer: W,
+
+	/// The bytes of the last path written.
+	last_path: Vec<u8>,
+}
+
+impl<W: Write> Encoder<W> {
+	/// Creates a new `Encoder`, writing the given magic bytes and version.
+	pub(crate) fn new(mut writer: W, magic: &[u8; 4]) -> io::Result<Encoder<W>> {
+		writer.write_all(magic)?;
+		writer.write_all(&[VERSION])?;
+
+		Ok(Encoder {
+			writer,
+			last_path: vec![],
+		})
+	}
+
+	/// Writes a number.
+	pub(crate) fn number(&mut self, mut n: u64) -> io::Result<()> {
+		loop {
+			let byte = (n & 0x7f) as u8;
+			n >>= 7;
+
+			if n == 0 {
+				return self.writer.write_all(&[byte]);
+			}
+
+			self.writer.write_all(&[byte | 0x80])?;
+		}
+	}
+
+	/// Writes a number, or zero for `None`.
+	pub(crate) fn optional_number(&mut self, n: Option<u64>) -> io::Result<()> {
+		self.number(n.map_or(0, |n| n + 1))
+	}
+
+	/// Writes bytes, with their length first.
+	pub(crate) fn bytes(&mut self, bytes: &[u8]) -> io::Result<()> {
+		self.number(bytes.len() as u64)?;
+		self.writer.write_all(bytes)
+	}
+
+	/// Writes a string.
+	pub(crate) fn string(&mut self, s: &str) -> io::Result<()> {
+		self.bytes(s.as_bytes())
+	}
+
+	/// Writes a hash, as its raw bytes if it's a BLAKE3 hash and otherwise
+	/// as a string.
+	pub(crate) fn hash(&mut self, hash: &str) -> io::Result<()> {
+		match blake3::Hash::from_hex(hash) {
+			Ok(h) if h.to_hex().as_str() == hash => {
+				self.writer.write_all(&[0])?;
+				self.writer.write_all(h.as_bytes())
+			}
+			_ => {
+				self.writer.write_all(&[1])?;
+				self.string(hash)
+			}
+		}
+	}
+
+	/// Writes a path.
+	pub(crate) fn path(&mut self, path: &Path) -> io::Result<()> {
+		let bytes = path_bytes(path);
+		let shared = bytes
+			.iter()
+			.zip(&self.last_path)
+			.take_while(|(a, b)| a == b)
+			.count();
+
+		self.number(shared as u64)?;
+		self.bytes(&bytes[shared..])?;
+		self.last_path = bytes;
+
+		Ok(())
+	}
+
+	/// Writes a list of paths.
+	pub(crate) fn paths(&mut self, paths: &[Arc<Path>]) -> io::Result<()> {
+		self.number(paths.len() as u64)?;
+
+		for path in paths {
+			self.path(path)?;
+		}
+
+		Ok(())
+	}
+
+	/// Writes a path, or a zero byte for `None`.
+	pub(crate) fn optional_path(&mut self, path: Option<&Path>) -> io::Result<()> {
+		match path {
+			Some(p) => {
+				self.writer.write_all(&[1])?;
+				self.path(p)
+			}
+			None => self.writer.write_all(&[0]),
+		}
+	}
+
+	/// Writes a time, or a zero byte for `None`.
+	pub(crate) fn time(&mut self, time: Option<SystemTime>) -> io::Result<()> {
+		let (after, duration) = match time {
+			Some(t) => match t.duration_since(SystemTime::UNIX_EPOCH) {
+				Ok(d) => (true, d),
+				Err(e) => (false, e.duration()),
+			},
+			None => return self.writer.write_all(&[0]),
+		};
+
+		self.writer.write_all(&[if after { 1 } else { 2 }])?;
+		self.number(duration.as_secs())?;
+		self.number(duration.subsec_nanos() as u64)
+	}
+
+	/// Flushes the writer.
+	pub(crate) fn flush(&mut self) -> io::Result<()> {
+		self.writer.flush()
+	}
+}
+
+/// Reads values written by an `Encoder`.
+pub(crate) struct Decoder<R: Read> {
+	/// The reader values are read from.
+	reader: R,
+
+	/// The bytes of the last path read.
+	last_path: Vec<u8>,
+}
+
+impl<R: Read> Decoder<R> {
+	/// Creates a new `Decoder`, checking the given magic bytes and the
+	/// version.
+	///
+	/// # Errors
+	///
+	/// Returns an error if reading fails, or if the magic bytes or version
+	/// don't match.
+	pub(crate) fn new(mut reader: R, magic: &[u8; 4]) -> io::Result<Decoder<R>> {
+		let mut header = [0; 5];
+		reader.read_exact(&mut header)?;
+
+		if &header[..4] != magic {
+			return Err(invalid("not a dupcheck binary file of the expected kind"));
+		}
+
+		if header[4] != VERSION {
+			return Err(invalid(&format!(
+				"unsupported binary format version {}",
+				header[4]
+			)));
+		}
+
+		Ok(Decoder {
+			reader,
+			last_path: vec![],
+		})
+	}
+
+	/// Reads a byte.
+	pub(crate) fn byte(&mut self) -> io::Result<u8> {
+		let mut byte = [0];
+		self.reader.read_exact(&mut byte)?;
+
+		Ok(byte[0])
+	}
+
+	/// Reads a number.
+	pub(crate) fn number(&mut self) -> io::Result<u64> {
+		let mut n = 0;
+
+		for shift in (0..64).step_by(7) {
+			let byte = self.byte()?;
+			n |= ((byte & 0x7f) as u64) << shift;
+
+			if byte & 0x80 == 0 {
+				return Ok(n);
+			}
+		}
+
+		Err(invalid("number too large"))
+	}
+
+	/// Reads a number that may be `None`.
+	pub(crate) fn optional_number(&mut self) -> io::Result<Option<u64>> {
+		Ok(self.number()?.checked_sub(1))
+	}
+
+	/// Reads a number that's used as a count or index.
+	pub(crate) fn size(&mut self) -> io::Result<usize> {
+		usize::try_from(self.number()?).map_err(|_| invalid("length too large"))
+	}
+
+	/// Reads bytes written with their length.
+	pub(crate) fn bytes(&mut self) -> io::Result<Vec<u8>> {
+		let len = self.number()?;
+		let mut bytes = vec![];
+		(&mut self.reader).take(len).read_to_end(&mut bytes)?;
+
+		if (bytes.len() as u64) < len {
+			return Err(io::ErrorKind::UnexpectedEof.into());
+		}
+
+		Ok(bytes)
+	}
+
+	/// Reads a string.
+	pub(crate) fn string(&mut self) -> io::Result<String> {
+		String::from_utf8(self.bytes()?).map_err(|_| invalid("invalid UTF-8 in string"))
+	}
+
+	/// Reads a hash.
+	pub(crate) fn hash(&mut self) -> io::Result<String> {
+		match self.byte()? {
+			0 => {
+				let mut bytes = [0; 32];
+				self.reader.read_exact(&mut bytes)?;
+				Ok(blake3::Hash::from(bytes).to_hex().to_string())
+			}
+			1 => self.string(),
+			_ => Err(invalid("invalid hash")),
+		}
+	}
+
+	/// Reads a path.
+	pub(crate) fn path(&mut self) -> io::Result<PathBuf> {
+		let shared = self.size()?;
+
+		if shared > self.last_path.len() {
+			return Err(invalid("invalid path"));
+		}
+
+		self.last_path.truncate(shared);
+		let mut rest = self.bytes()?;
+		self.last_path.append(&mut rest);
+
+		bytes_path(&self.last_path)
+	}
+
+	/// Reads a list of paths.
+	pub(crate) fn paths(&mut self) -> io::Result<Vec<Arc<Path>>> {
+		let len = self.size()?;
+		let mut paths = vec![];
+
+		for _ in 0..len {
+			paths.push(Arc::from(self.path()?));
+		}
+
+		Ok(paths)
+	}
+
+	/// Reads a path that may be `None`.
+	pub(crate) fn optional_path(&mut self) -> io::Result<Option<PathBuf>> {
+		match self.byte()? {
+			0 => Ok(None),
+			1 => self.path().map(Some),
+			_ => Err(invalid("invalid path")),
+		}
+	}
+
+	/// Reads a time that may be `None`.
+	pub(crate) fn time(&mut self) -> io::Result<Option<SystemTime>> {
+		let after = match self.byte()? {
+			0 => return Ok(None),
+			1 => true,
+			2 => false,
+			_ => return Err(invalid("invalid time")),
+		};
+		let secs = self.number()?;
+		let nanos = u32::try_from(self.number()?).map_err(|_| invalid("invalid time"))?;
+		let duration = Duration::new(secs, nanos);
+		let time = match after {
+			true => SystemTime::UNIX_EPOCH.checked_add(duration),
+			false => SystemTime::UNIX_EPOCH.checked_sub(duration),
+		};
+
+		time.map(Some).ok_or_else(|| invalid("invalid time"))
+	}
+}
+
+/// Writes duplicate file groups in dupcheck's compact binary format, to be
+/// read back with `DupResults::read_binary()`.
+///
+/// Each group's hash, files, baseline and queried files, designated original
+/// and canonical copies, normalizations, roots, tags and file metadata are
+/// written.  The files a check skipped or found to be unique can also be
+/// written, so `DupResults::explain()` can explain them once the results
+/// are read back.  Hashes are written as raw bytes, and paths sharing directories
+/// with the path before them only take the space of their differences, so
+/// results take far less space than as JSON and can be read far faster.
+///
+/// Groups can be written as they're found, such as from the receiver of
+/// `DupOptions::group_sender()`, so the results found so far can be read
+/// back if the check is interrupted.  A group written more than once, such
+/// as when files are added to it, is merged when it's read.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::fs::File;
+/// use std::io::BufWriter;
+/// use std::path::PathBuf;
+/// use std::sync::mpsc;
+/// use std::thread;
+///
+/// let (sender, receiver) = mpsc::channel();
+/// let mut options = dupcheck::DupOptions::new();
+/// options.set_group_sender(Some(sender));
+///
+/// let saving = thread::spawn(move || -> std::io::Result<()> {
+///     let file = BufWriter::new(File::create("results.dupr")?);
+///     let mut writer = dupcheck::ResultsWriter::new(file)?;
+///
+///     for group in receiver {
+///         writer.write_group(&group)?;
+///     }
+///
+///     writer.finish()
+/// });
+///
+/// let dirs = vec![PathBuf::from("foo")];
+/// let mut dup_result = dupcheck::DupResults::with_options(options);
+///
+/// if let Err(dup_error) = dup_result.within(&dirs) {
+///     // Error handling
+/// }
+///
+/// drop(dup_result);
+///
+/// if let Ok(Err(write_error)) = saving.join() {
+///     // Error handling
+/// }
+/// ```
+pub struct ResultsWriter<W: Write> {
+	/// The encoder the groups are written with.
+	encoder: Encoder<W>,
+}
+
+impl<W: Write> ResultsWriter<W> {
+	/// Creates a new `ResultsWriter`, writing the start of the results.
+	///
+	/// # Errors
+	///
+	/// Returns an error if writing fails.
+	pub fn new(writer: W) -> io::Result<ResultsWriter<W>> {
+		let mut encoder = Encoder::new(writer, RESULTS_MAGIC)?;
+		encoder.flush()?;
+
+		Ok(ResultsWriter { encoder })
+	}
+
+	/// Writes a group and flushes the writer, so the group can be read back
+	/// even if the results are never finished.
+	///
+	/// # Errors
+	///
+	/// Returns an error if writing fails.
+	pub fn write_group(&mut self, group: &DupGroup) -> io::Result<()> {
+		let encoder = &mut self.encoder;
+		encoder.number(1)?;
+		encoder.hash(&group.hash)?;
+		encoder.paths(&group.files)?;
+		encoder.paths(&group.baseline)?;
+		encoder.paths(&group.queried)?;
+		encoder.optional_number(group.original.map(|i| i as u64))?;
+		encoder.optional_number(group.canonical.map(|i| i as u64))?;
+		encoder.number(group.normalizations.len() as u64)?;
+
+		for normalization in &group.normalizations {
+			encoder.number(normalization_code(normalization))?;
+		}
+
+		for i in 0..group.files.len() {
+			encoder.optional_path(group.root_at(i))?;
+		}
+
+		encoder.number(group.tags.len() as u64)?;
+
+		for (key, value) in &group.tags {
+			encoder.string(key)?;
+			encoder.string(value)?;
+		}
+
+		for i in 0..group.files.len() {
+			match group.metadata_at(i) {
+				Some(metadata) => {
+					encoder.number(1)?;
+					encoder.number(metadata.size())?;
+					encoder.time(metadata.modified())?;
+					encoder.time(metadata.changed())?;
+					encoder.optional_number(metadata.uid().map(u64::from))?;
+					encoder.optional_number(metadata.gid().map(u64::from))?;
+					encoder.optional_number(metadata.mode().map(u64::from))?;
+				}
+				None => encoder.number(0)?,
+			};
+		}
+
+		encoder.flush()
+	}
+
+	/// Writes the files and directories skipped by a check, with the reason
+	/// each was skipped, as given by `DupResults::skipped()`.
+	///
+	/// # Errors
+	///
+	/// Returns an error if writing fails.
+	pub fn write_skipped(&mut self, skipped: &[(PathBuf, SkipReason)]) -> io::Result<()> {
+		for (path, reason) in skipped {
+			self.encoder.number(2)?;
+			self.encoder.path(path)?;
+			self.encoder.number(skip_reason_code(reason))?;
+		}
+
+		self.encoder.flush()
+	}
+
+	/// Writes the files found to have no duplicates by a check with
+	/// `DupOptions::explainable()` enabled, with the comparison stage at
+	/// which each was found to be unique.
+	///
+	/// # Errors
+	///
+	/// Returns an error if writing fails.
+	pub fn write_unique(&mut self, unique: &[(PathBuf, Stage)]) -> io::Result<()> {
+		for (path, stage) in unique {
+			self.encoder.number(3)?;
+			self.encoder.path(path)?;
+			self.encoder.number(stage_code(stage))?;
+		}
+
+		self.encoder.flush()
+	}
+
+	/// Marks the results as complete and flushes the writer.
+	///
+	/// # Errors
+	///
+	/// Returns an error if writing fails.
+	pub fn finish(mut self) -> io::Result<()> {
+		self.encoder.number(0)?;
+		self.encoder.flush()
+	}
+}
+
+impl DupResults {
+	/// Writes the duplicate file groups in dupcheck's compact binary format,
+	/// as described for `ResultsWriter`, to be read back with
+	/// `read_binary()`.  Any skipped and unique files recorded by the checks
+	/// are written too.
+	///
+	/// # Errors
+	///
+	/// Returns an error if writing fails.
+	///
+	/// # Examples
+	///
+	/// ```no_run
+	/// use std::fs::File;
+	/// use std::io::BufWriter;
+	/// use std::path::PathBuf;
+	///
+	/// let dirs = vec![PathBuf::from("foo")];
+	/// let mut dup_result = dupcheck::DupResults::new();
+	///
+	/// if let Err(dup_error) = dup_result.within(&dirs) {
+	///     // Error handling
+	/// }
+	///
+	/// if let Ok(file) = File::create("results.dupr") {
+	///     if let Err(write_error) = dup_result.write_binary(BufWriter::new(file)) {
+	///         // Error handling
+	///     }
+	/// }
+	/// ```
+	pub fn write_binary<W: Write>(&self, writer: W) -> io::Result<()> {
+		let mut results_writer = ResultsWriter::new(writer)?;
+
+		for group in &self.duplicates {
+			results_writer.write_group(group)?;
+		}
+
+		results_writer.write_skipped(&self.skipped)?;
+		results_writer.write_unique(&self.unique)?;
+		results_writer.finish()
+	}
+
+	/// Reads duplicate file groups written by `write_binary()` or a
+	/// `ResultsWriter`, adding them to these results, so they can be reported
+	/// or acted on later, such as on another computer with the same storage
+	/// mounted in the same place.  Groups with the same hash as a group
+	/// already in the results have their files added to it, and files
+	/// already in another group are skipped.  Any skipped and unique files
+	/// that were written are added too, for `explain()`.
+	///
+	/// The files aren't read, so the groups are as they were when they were
+	/// written.  Returns whether the results were complete.  Results whose
+	/// writing was interrupted, such as by the end of a check that was
+	/// saving its groups as they were found, are incomplete, and every group
+	/// that was completely written is added.
+	///
+	/// # Errors
+	///
+	/// Returns an error if reading fails or the data isn't in dupcheck's
+	/// binary format.  No groups are added if an error is returned.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use dupcheck::{Action, KeepPolicy};
+	/// use std::fs::File;
+	/// use std::io::BufReader;
+	///
+	/// let mut dup_result = dupcheck::DupResults::new();
+	///
+	/// if let Ok(file) = File::open("results.dupr") {
+	///     match dup_result.read_binary(BufReader::new(file)) {
+	///         Ok(true) => {}
+	///         Ok(false) => println!("The check that saved these results didn't finish."),
+	///         Err(read_error) => {
+	///             // Error handling
+	///         }
+	///     }
+	/// }
+	///
+	/// let (plans, errors) = dup_result.plan_actions(&KeepPolicy::First, Action::Delete);
+	/// ```
+	pub fn read_binary<R: Read>(&mut self, reader: R) -> io::Result<bool> {
+		let mut decoder = Decoder::new(reader, RESULTS_MAGIC)?;
+		let mut groups = vec![];
+		let mut skipped = vec![];
+		let mut unique = vec![];
+
+		// Running out of data is only an error in the header, since results
+		// can be read while they're still being written.
+		let complete = loop {
+			let read = match decoder.byte() {
+				Ok(0) => break true,
+				Ok(1) => read_group(&mut decoder).map(|g| groups.push(g)),
+				Ok(2) => decoder
+					.path()
+					.and_then(|p| Ok((p, skip_reason(decoder.number()?)?)))
+					.map(|s| skipped.push(s)),
+				Ok(3) => decoder
+					.path()
+					.and_then(|p| Ok((p, stage(decoder.number()?)?)))
+					.map(|u| unique.push(u)),
+				Ok(_) => Err(invalid("invalid record")),
+				Err(e) => Err(e),
+			};
+
+			match read {
+				Ok(()) => {}
+				Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => break false,
+				Err(e) => return Err(e),
+			};
+		};
+
+		self.skipped.append(&mut skipped);
+		self.unique.append(&mut unique);
+
+		// Groups and their files are indexed once, rather than every group
+		// being searched for each loaded file.
+		let mut index = LoadedIndex {
+			groups: HashMap::new(),
+			members: HashMap::new(),
+		};
+
+		for (i, group) in self.duplicates.iter().enumerate() {
+			index.groups.insert(group.hash.clone(), i);
+
+			for file in &group.files {
+				index.members.insert(Arc::clone(file), i);
+			}
+		}
+
+		for group in groups {
+			self.add_loaded_group(group, &mut index);
+		}
+
+		Ok(complete)
+	}
+
+	/// Adds a group read from saved results, merging it into any group with
+	/// the same hash and leaving out files already in another group.  A new
+	/// group is left out if it has fewer than two files left.
+	fn add_loaded_group(&mut self, mut group: DupGroup, index: &mut LoadedIndex) {
+		match index.groups.get(&group.hash).copied() {
+			Some(i) => {
+				// Files already in this group or in another are skipped.
+				for (j, file) in group.files.iter().enumerate() {
+					if index.members.contains_key(&**file) {
+						continue;
+					}
+
+					self.duplicates[i].push_file(
+						Arc::clone(file),
+						group.roots.get(j).cloned().flatten(),
+						group.metadata_at(j).copied(),
+					);
+					index.members.insert(Arc::clone(file), i);
+				}
+			}
+			None => {
+				group.remove_files(|f| !index.members.contains_key(f));
+
+				if group.files.len() + group.baseline.len() + group.queried.len() < 2 {
+					return;
+				}
+
+				let i = self.duplicates.len();
+				index.groups.insert(group.hash.clone(), i);
+
+				for file in &group.files {
+					index.members.insert(Arc::clone(file), i);
+				}
+
+				self.duplicates.push(group);
+			}
+		};
+	}
+}
+
+/// The groups in a `DupResults` indexed by hash, and the group that each of
+/// their files is in, for merging in groups read from saved results.
+struct LoadedIndex {
+	groups: HashMap<String, usize>,
+	members: HashMap<Arc<Path>, usize>,
+}
+
+/// Reads a group written by `ResultsWriter::write_group()`, after the byte
+/// marking that a group follows.
+fn read_group<R: Read>(decoder: &mut Decoder<R>) -> io::Result<DupGroup> {
+	let mut group = DupGroup::new(decoder.hash()?, decoder.paths()?);
+	group.baseline = decoder.paths()?;
+	group.queried = decoder.paths()?;
+	group.original = decoder.optional_number()?.map(|i| i as usize);
+	group.canonical = decoder.optional_number()?.map(|i| i as usize);
+
+	let file_count = group.files.len();
+
+	if group.original.is_some_and(|i| i >= file_count)
+		|| group.canonical.is_some_and(|i| i >= file_count)
+	{
+		return Err(invalid("invalid file index"));
+	}
+
+	for _ in 0..decoder.size()? {
+		group.normalizations.push(normalization(decoder.number()?)?);
+	}
+
+	let mut last_root: Option<Arc<Path>> = None;
+
+	for i in 0..group.files.len() {
+		if let Some(root) = decoder.optional_path()? {
+			// Files in a group are usually found in the same directory given
+			// to `DupResults::within()`, so they can share its path.
+			let root = match last_root {
+				Some(r) if *r == *root => r,
+				_ => Arc::from(root),
+			};
+			group.set_root(i, Arc::clone(&root));
+			last_root = Some(root);
+		}
+	}
+
+	for _ in 0..decoder.size()? {
+		group.tags.insert(decoder.string()?, decoder.string()?);
+	}
+
+	for i in 0..group.files.len() {
+		if decoder.number()? == 0 {
+			continue;
+		}
+
+		let size = decoder.number()?;
+		let modified = decoder.time()?;
+		let changed = decoder.time()?;
+		let ids = (
+			decoder.optional_number()?,
+			decoder.optional_number()?,
+			decoder.optional_number()?,
+		);
+		let ownership = match ids {
+			(Some(uid), Some(gid), Some(mode)) => Some((uid as u32, gid as u32, mode as u32)),
+			_ => None,
+		};
+		group.set_metadata_at(
+			i,
+			FileMetadata::from_parts(size, modified, changed, ownership),
+		);
+	}
+
+	Ok(group)
+}
+
+/// Returns the code a normalization is written as.
+fn normalization_code(normalization: &Normalization) -> u64 {
+	match normalization {
+		Normalization::ImageMetadata => 0,
+		Normalization::ArchiveMembers => 1,
+		Normalization::Gzip => 2,
+		Normalization::LineEndings => 3,
+		Normalization::TextEncoding => 4,
+	}
+}
+
+/// Returns the normalization written as the given code.
+fn normalization(code: u64) -> io::Result<Normalization> {
+	match code {
+		0 => Ok(Normalization::ImageMetadata),
+		1 => Ok(Normalization::ArchiveMembers),
+		2 => Ok(Normalization::Gzip),
+		3 => Ok(Normalization::LineEndings),
+		4 => Ok(Normalization::TextEncoding),
+		_ => Err(invalid("unknown normalization")),
+	}
+}
+
+/// Returns the code a skip reason is written as.
+fn skip_reason_code(reason: &SkipReason) -> u64 {
+	match reason {
+		SkipReason::Junk => 0,
+		SkipReason::Placeholder => 1,
+		SkipReason::Signature => 2,
+		SkipReason::Size => 3,
+		SkipReason::Filtered => 4,
+		SkipReason::ExcludedDir => 5,
+		SkipReason::AlreadySearched => 6,
+		SkipReason::AlreadyChecked => 7,
+	}
+}
+
+/// Returns the skip reason written as the given code.
+fn skip_reason(code: u64) -> io::Result<SkipReason> {
+	match code {
+		0 => Ok(SkipReason::Junk),
+		1 => Ok(SkipReason::Placeholder),
+		2 => Ok(SkipReason::Signature),
+		3 => Ok(SkipReason::Size),
+		4 => Ok(SkipReason::Filtered),
+		5 => Ok(SkipReason::ExcludedDir),
+		6 => Ok(SkipReason::AlreadySearched),
+		7 => Ok(SkipReason::AlreadyChecked),
+		_ => Err(invalid("unknown skip reason")),
+	}
+}
+
+/// Returns the code a comparison stage is written as.
+fn stage_code(stage: &Stage) -> u64 {
+	match stage {
+		Stage::Size => 0,
+		Stage::PrefixHash => 1,
+		Stage::FullHash => 2,
+		Stage::ByteCompare => 3,
+		Stage::NormalizedHash => 4,
+	}
+}
+
+/// Returns the comparison stage written as the given code.
+fn stage(code: u64) -> io::Result<Stage> {
+	match code {
+		0 => Ok(Stage::Size),
+		1 => Ok(Stage::PrefixHash),
+		2 => Ok(Stage::FullHash),
+		3 => Ok(Stage::ByteCompare),
+		4 => Ok(Stage::NormalizedHash),
+		_ => Err(invalid("unknown comparison stage")),
+	}
+}
+
+/// Returns the bytes of a path, which are its raw bytes on Unix and UTF-8
+/// elsewhere.
+#[cfg(unix)]
+fn path_bytes(path: &Path) -> Vec<u8> {
+	use std::os::unix::ffi::OsStrExt;
+
+	path.as_os_str().as_bytes().to_vec()
+}
+
+/// Returns the bytes of a path, which are its raw bytes on Unix and UTF-8
+/// elsewhere.
+#[cfg(not(unix))]
+fn path_bytes(path: &Path) -> Vec<u8> {
+	path.to_string_lossy().as_bytes().to_vec()
+}
+
+/// Returns the path with the given bytes.
+#[cfg(unix)]
+fn bytes_path(bytes: &[u8]) -> io::Result<PathBuf> {
+	use std::ffi::OsStr;
+	use std::os::unix::ffi::OsStrExt;
+
+	Ok(PathBuf::from(OsStr::from_bytes(bytes)))
+}
+
+/// Returns the path with the given bytes.
+#[cfg(not(unix))]
+fn bytes_path(bytes: &[u8]) -> io::Result<PathBuf> {
+	match std::str::from_utf8(bytes) {
+		Ok(s) => Ok(PathBuf::from(s)),
+		Err(_) => Err(invalid("invalid UTF-8 in path")),
+	}
+}
+
+/// Returns an error for invalid binary data.
+pub(crate) fn invalid(message: &str) -> io::Error {
+	io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
@@ -0,0 +1,76 @@
+use crate::duperror::DupError;
+use crate::DupResults;
+use std::collections::HashSet;
+use std::io;
+use std::path::{Path, PathBuf};
+
+impl DupResults {
+	/// Checks all files within the given directories against a list of known
+	/// BLAKE3 hashes, such as hashes of content that mustn't be stored.  Any
+	/// matching files can then be retrieved with `blocked()`.
+	///
+	/// # Errors
+	///
+	/// Returns an error if any `dirs` are not directories.  The `DupResults`
+	/// will contain errors for any files that couldn't be read.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use std::path::PathBuf;
+	///
+	/// let hashes = vec!["af1349b9f5f9a1a6a0404dea36dcc9499bcb25c9adc112b7cc9a93cae41f3262"];
+	/// let dirs = vec![PathBuf::from("foo")];
+	/// let mut dup_result = dupcheck::DupResults::new();
+	///
+	/// if let Err(dup_error) = dup_result.check_blocklist(&hashes, &dirs) {
+	///     // Error handling
+	/// }
+	///
+	/// for (hash, file) in dup_result.blocked() {
+	///     println!("{} matches {}", file.display(), hash);
+	/// }
+	/// ```
+	pub fn check_blocklist<S: AsRef<str>, T: AsRef<Path>>(
+		&mut self,
+		hashes: &[S],
+		dirs: &[T],
+	) -> io::Result<()> {
+		self.check_valid_paths(None, Some(dirs))?;
+
+		let hashes: HashSet<String> = hashes
+			.iter()
+			.map(|h| h.as_ref().to_ascii_lowercase())
+			.collect();
+		let (files, mut errors) = self.files_within(&self.convert_to_path_buf(dirs), None);
+		self.errors.append(&mut errors);
+		let retry = self.options.retry_policy();
+
+		for file in files {
+			self.options.checkpoint();
+			let hash = match retry.run(|| self.hash(&file)) {
+				Ok(h) => h,
+				Err(e) => {
+					self.push_error(DupError::new(file, e));
+					continue;
+				}
+			};
+
+			if self.options.hash_all() {
+				self.scanned.add(hash.clone(), file.clone());
+			}
+
+			if hashes.contains(&hash) && !self.blocked.iter().any(|(_, f)| *f == file) {
+				self.blocked.push((hash, file));
+			}
+		}
+
+		Ok(())
+	}
+
+	/// Returns a reference to the hashes and paths of files found by
+	/// `check_blocklist()` to match a blocked hash.
+	pub fn blocked(&self) -> &[(String, PathBuf)] {
+		&self.blocked
+	}
+}
@@ -0,0 +1,308 @@
+use crate::binary::{invalid, Decoder, Encoder};
+use crate::utilities::file_id;
+use std::collections::HashMap;
+use std::fs::{File, Metadata, OpenOptions, TryLockError};
+use std::io;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::SystemTime;
+
+/// The start of a binary hash cache file.
+const CACHE_MAGIC: &[u8; 4] = b"DUPH";
+
+/// Cached hashes, with each file's size and modification time when it was
+/// hashed, by file.
+type Entries = HashMap<CachedFile, (u64, SystemTime, String)>;
+
+/// A file whose hash is cached.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+enum CachedFile {
+	/// The file with the given device and inode numbers, wherever it is.
+	Inode(u64, u64),
+
+	/// The file at the given path, on platforms where files can't be
+	/// identified otherwise.
+	Path(PathBuf),
+}
+
+/// An in-memory cache of files' BLAKE3 hashes, which can be shared between
+/// checks so unchanged files aren't read again.
+///
+/// Files are identified by their device and inode numbers where the platform
+/// provides them, or otherwise by their paths, so files that are renamed or
+/// moved within a device still use their cached hashes.  Cached hashes are
+/// only used if a file's size and modification time are the same as when it
+/// was hashed.  Clones of a `HashCache` share the same
+/// cached hashes, so a cache can be given to the options of several checks,
+/// including checks running on other threads.
+#[derive(Clone, Debug, Default)]
+pub struct HashCache {
+	/// Each file's size, modification time and hash.
+	entries: Arc<Mutex<Entries>>,
+}
+
+impl HashCache {
+	/// Creates a new, empty `HashCache`.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use std::path::PathBuf;
+	///
+	/// let mut options = dupcheck::DupOptions::new();
+	/// options.set_hash_cache(Some(dupcheck::HashCache::new()));
+	///
+	/// let dirs = vec![PathBuf::from("foo")];
+	/// let mut dup_result = dupcheck::DupResults::with_options(options.clone());
+	///
+	/// if let Err(dup_error) = dup_result.within(&dirs) {
+	///     // Error handling
+	/// }
+	///
+	/// // Files that haven't changed aren't read again.
+	/// let mut dup_result = dupcheck::DupResults::with_options(options);
+	///
+	/// if let Err(dup_error) = dup_result.within(&dirs) {
+	///     // Error handling
+	/// }
+	/// ```
+	pub fn new() -> HashCache {
+		HashCache::default()
+	}
+
+	/// Returns the number of cached hashes.
+	pub fn len(&self) -> usize {
+		self.lock().len()
+	}
+
+	/// Returns whether no hashes are cached.
+	pub fn is_empty(&self) -> bool {
+		self.lock().is_empty()
+	}
+
+	/// Removes all cached hashes.
+	pub fn clear(&self) {
+		self.lock().clear();
+	}
+
+	/// Takes an exclusive lock for the hash cache file at the given path,
+	/// which is held until the returned `File` is closed, so that programs
+	/// reading and later replacing the same cache file don't overwrite each
+	/// other's hashes or read a partly written file.
+	///
+	/// The lock is taken on a file alongside the cache file, with `.lock`
+	/// added to its name, which is created if it doesn't exist.  If `wait`
+	/// is `true`, this waits for any other lock to be released.  Otherwise,
+	/// an error of kind `WouldBlock` is returned if the cache is locked.
+	/// The lock is advisory, so it only keeps out programs that also lock
+	/// the cache.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use std::io;
+	///
+	/// match dupcheck::HashCache::lock_file("foo/hashes.duph", false) {
+	///     Ok(lock) => {
+	///         // Read, use and save the cache before the lock is closed.
+	///     }
+	///     Err(lock_error) if lock_error.kind() == io::ErrorKind::WouldBlock => {
+	///         // The cache is in use
+	///     }
+	///     Err(lock_error) => {
+	///         // Error handling
+	///     }
+	/// }
+	/// ```
+	pub fn lock_file<P: AsRef<Path>>(path: P, wait: bool) -> io::Result<File> {
+		let mut lock_path = path.as_ref().as_os_str().to_owned();
+		lock_path.push(".lock");
+		let file = OpenOptions::new()
+			.write(true)
+			.create(true)
+			.truncate(false)
+			.open(lock_path)?;
+
+		match wait {
+			true => file.lock()?,
+			false => file.try_lock().map_err(|e| match e {
+				TryLockError::WouldBlock => io::Error::from(io::ErrorKind::WouldBlock),
+				TryLockError::Error(e) => e,
+			})?,
+		};
+
+		Ok(file)
+	}
+
+	/// Reads cached hashes written by `write_binary()`, such as by an
+	/// earlier run of a program, into a new `HashCache`.
+	///
+	/// # Errors
+	///
+	/// Returns an error if reading fails or the data isn't a hash cache in
+	/// dupcheck's binary format.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use std::fs::File;
+	/// use std::io::BufReader;
+	///
+	/// let cache = match File::open("hashes.duph") {
+	///     Ok(file) => dupcheck::HashCache::read_binary(BufReader::new(file)).unwrap_or_default(),
+	///     Err(_) => dupcheck::HashCache::new(),
+	/// };
+	///
+	/// let mut options = dupcheck::DupOptions::new();
+	/// options.set_hash_cache(Some(cache));
+	/// ```
+	pub fn read_binary<R: Read>(reader: R) -> io::Result<HashCache> {
+		let mut decoder = Decoder::new(reader, CACHE_MAGIC)?;
+		let len = decoder.size()?;
+		let mut entries = HashMap::new();
+
+		for _ in 0..len {
+			let file = match decoder.byte()? {
+				0 => CachedFile::Inode(decoder.number()?, decoder.number()?),
+				1 => CachedFile::Path(decoder.path()?),
+				_ => return Err(invalid("invalid cached file")),
+			};
+			let size = decoder.number()?;
+			let modified = decoder
+				.time()?
+				.ok_or_else(|| invalid("missing modification time"))?;
+			entries.insert(file, (size, modified, decoder.hash()?));
+		}
+
+		Ok(HashCache {
+			entries: Arc::new(Mutex::new(entries)),
+		})
+	}
+
+	/// Writes the cached hashes in dupcheck's compact binary format, to be
+	/// read back with `read_binary()`.  Files are identified by their device
+	/// and inode numbers where the platform provides them, so the cache
+	/// should only be read back on the same system.
+	///
+	/// # Errors
+	///
+	/// Returns an error if writing fails.
+	pub fn write_binary<W: Write>(&self, writer: W) -> io::Result<()> {
+		let entries = self.lock();
+		let mut encoder = Encoder::new(writer, CACHE_MAGIC)?;
+		encoder.number(entries.len() as u64)?;
+
+		for (file, (size, modified, hash)) in entries.iter() {
+			match file {
+				CachedFile::Inode(device, inode) => {
+					encoder.number(0)?;
+					encoder.number(*device)?;
+					encoder.number(*inode)?;
+				}
+				CachedFile::Path(path) => {
+					encoder.number(1)?;
+					encoder.path(path)?;
+				}
+			};
+
+			encoder.number(*size)?;
+			encoder.time(Some(*modified))?;
+			encoder.hash(hash)?;
+		}
+
+		encoder.flush()
+	}
+
+	/// Adds a file's hash that's already known, such as from a previous
+	/// manifest or another program's store of hashes, so checks using the
+	/// cache don't read the file to hash it.  Returns whether the hash was
+	/// added, which it isn't if the file's size is no longer `size`.
+	///
+	/// The hash is assumed to be the file's current BLAKE3 hash, and is used
+	/// until the file's size or modification time changes.
+	///
+	/// # Errors
+	///
+	/// Returns an error if the file's metadata can't be read.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use std::path::PathBuf;
+	///
+	/// let cache = dupcheck::HashCache::new();
+	/// let known = cache.insert(
+	///     "foo/empty.txt",
+	///     0,
+	///     "af1349b9f5f9a1a6a0404dea36dcc9499bcb25c9adc112b7cc9a93cae41f3262".to_string(),
+	/// );
+	///
+	/// if let Err(insert_error) = known {
+	///     // Error handling
+	/// }
+	///
+	/// let mut options = dupcheck::DupOptions::new();
+	/// options.set_hash_cache(Some(cache));
+	///
+	/// let dirs = vec![PathBuf::from("foo")];
+	/// let mut dup_result = dupcheck::DupResults::with_options(options);
+	///
+	/// if let Err(dup_error) = dup_result.within(&dirs) {
+	///     // Error handling
+	/// }
+	/// ```
+	pub fn insert<P: AsRef<Path>>(&self, path: P, size: u64, hash: String) -> io::Result<bool> {
+		let path = path.as_ref();
+		let metadata = path.metadata()?;
+
+		if metadata.len() != size {
+			return Ok(false);
+		}
+
+		let modified = metadata.modified()?;
+		self
+			.lock()
+			.insert(cached_file(path, &metadata), (size, modified, hash));
+
+		Ok(true)
+	}
+
+	/// Returns a file's BLAKE3 hash, from the cache if the file hasn't
+	/// changed since it was cached, or otherwise by reading the file with
+	/// `read_hash` and caching its hash.
+	pub(crate) fn hash<F>(&self, path: &Path, read_hash: F) -> io::Result<String>
+	where
+		F: FnOnce(&Path) -> io::Result<String>,
+	{
+		let metadata = path.metadata()?;
+		let len = metadata.len();
+		let modified = metadata.modified()?;
+		let file = cached_file(path, &metadata);
+
+		if let Some((cached_len, cached_modified, hash)) = self.lock().get(&file) {
+			if *cached_len == len && *cached_modified == modified {
+				return Ok(hash.clone());
+			}
+		}
+
+		let hash = read_hash(path)?;
+		self.lock().insert(file, (len, modified, hash.clone()));
+
+		Ok(hash)
+	}
+
+	/// Locks the cached hashes, recovering them if another thread panicked
+	/// while holding the lock.
+	fn lock(&self) -> MutexGuard<'_, Entries> {
+		self.entries.lock().unwrap_or_else(|e| e.into_inner())
+	}
+}
+
+/// Returns how a file with the given metadata is identified in the cache.
+fn cached_file(path: &Path, metadata: &Metadata) -> CachedFile {
+	match file_id(metadata) {
+		Some((device, inode)) => CachedFile::Inode(device, inode),
+		None => CachedFile::Path(path.to_path_buf()),
+	}
+}
@@ -0,0 +1,96 @@
+use crate::duperror::DupError;
+use crate::keep::KeepPolicy;
+use crate::{DupGroup, DupResults};
+use std::path::Path;
+
+impl DupResults {
+	/// Designates the file selected by the keep policy in each duplicate file
+	/// group as the group's canonical copy, replacing any already designated.
+	///
+	/// # Errors
+	///
+	/// The `DupResults` will contain errors for any groups whose canonical
+	/// copies couldn't be chosen because file metadata couldn't be read.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use dupcheck::{Action, KeepPolicy};
+	/// use std::path::PathBuf;
+	///
+	/// let dirs = vec![PathBuf::from("foo")];
+	/// let mut dup_result = dupcheck::DupResults::new();
+	///
+	/// if let Err(dup_error) = dup_result.within(&dirs) {
+	///     // Error handling
+	/// }
+	///
+	/// dup_result.designate_canonical(&KeepPolicy::Oldest);
+	///
+	/// // Plans keep the canonical copies, whatever policy they're given.
+	/// let (plans, errors) = dup_result.plan_actions(&KeepPolicy::First, Action::Delete);
+	/// ```
+	pub fn designate_canonical(&mut self, policy: &KeepPolicy) {
+		for group in &mut self.duplicates {
+			match policy.select(group) {
+				Ok(i) => group.canonical = Some(i),
+				Err(e) => self
+					.errors
+					.push(DupError::new(group.files[0].to_path_buf(), e)),
+			};
+		}
+	}
+}
+
+impl DupGroup {
+	/// Returns the path to the file designated as the group's canonical copy,
+	/// if any.
+	///
+	/// The canonical copy is the file to keep when acting on the group, so
+	/// `DupGroup::plan()` keeps it in place of the file selected by its keep
+	/// policy, and exports mark it as the group's original.
+	pub fn canonical(&self) -> Option<&Path> {
+		self.canonical.map(|i| &*self.files[i])
+	}
+
+	/// Designates the given file as the group's canonical copy, returning
+	/// whether it was designated, which it isn't if it isn't one of the
+	/// group's files.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use std::path::PathBuf;
+	///
+	/// let dirs = vec![PathBuf::from("foo")];
+	/// let mut dup_result = dupcheck::DupResults::new();
+	///
+	/// if let Err(dup_error) = dup_result.within(&dirs) {
+	///     // Error handling
+	/// }
+	///
+	/// if let Some(group) = dup_result.group_by_hash_mut("af1349b9f5f9a1a6a0404dea36dcc9499bcb25c9adc112b7cc9a93cae41f3262") {
+	///     group.set_canonical("foo/photos/beach.jpg");
+	/// }
+	/// ```
+	pub fn set_canonical<P: AsRef<Path>>(&mut self, file: P) -> bool {
+		match self.position(file.as_ref()) {
+			Some(i) => {
+				self.canonical = Some(i);
+				true
+			}
+			None => false,
+		}
+	}
+
+	/// Removes the group's canonical copy designation, if any.
+	pub fn clear_canonical(&mut self) {
+		self.canonical = None;
+	}
+
+	/// Returns the index of the canonical copy, or of the first file if none
+	/// has been designated.
+	pub(crate) fn canonical_index(&self) -> usize {
+		self.canonical.unwrap_or(0)
+	}
+}
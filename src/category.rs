@@ -0,0 +1,101 @@
+use crate::content_type::matches;
+use std::str::FromStr;
+
+/// A broad category of files, used to limit checks to files of certain
+/// kinds.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Category {
+	/// Image files, such as JPEG, PNG and HEIF files and camera raw files.
+	Images,
+
+	/// Video files, such as MP4, QuickTime and Matroska files.
+	Video,
+
+	/// Audio files, such as MP3, FLAC and Ogg files.
+	Audio,
+
+	/// Documents, such as PDF, office and plain text files.
+	Documents,
+
+	/// Archives and compressed files, such as zip, tar and gzip files.
+	Archives,
+}
+
+impl FromStr for Category {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Category, String> {
+		match s {
+			"images" | "image" => Ok(Category::Images),
+			"video" | "videos" => Ok(Category::Video),
+			"audio" => Ok(Category::Audio),
+			"documents" | "document" => Ok(Category::Documents),
+			"archives" | "archive" => Ok(Category::Archives),
+			_ => Err(format!("unknown file category {}", s)),
+		}
+	}
+}
+
+impl Category {
+	/// Returns the lowercase file extensions, without a leading `.`, of files
+	/// in this category.
+	pub fn extensions(&self) -> &'static [&'static str] {
+		match self {
+			Category::Images => &[
+				"jpg", "jpeg", "jpe", "jfif", "png", "gif", "webp", "bmp", "tif", "tiff", "heic", "heif",
+				"avif", "ico", "svg", "psd", "raw", "cr2", "cr3", "nef", "arw", "orf", "rw2", "dng", "raf",
+			],
+			Category::Video => &[
+				"mp4", "m4v", "mov", "mkv", "webm", "avi", "wmv", "flv", "mpg", "mpeg", "3gp", "3g2", "mts",
+				"m2ts", "ts", "vob", "ogv",
+			],
+			Category::Audio => &[
+				"mp3", "flac", "ogg", "oga", "opus", "wav", "m4a", "m4b", "aac", "wma", "aiff", "aif", "ape",
+				"alac", "mid", "midi",
+			],
+			Category::Documents => &[
+				"pdf", "doc", "docx", "odt", "rtf", "txt", "md", "xls", "xlsx", "ods", "csv", "ppt", "pptx",
+				"odp", "epub", "mobi", "pages", "numbers", "key", "tex",
+			],
+			Category::Archives => &[
+				"zip", "tar", "gz", "tgz", "bz2", "tbz2", "xz", "txz", "zst", "7z", "rar", "iso", "dmg", "cab",
+				"lz", "lzma",
+			],
+		}
+	}
+
+	/// Returns the MIME types of files in this category, in the form used by
+	/// `DupOptions::set_content_types()`.
+	pub fn content_types(&self) -> &'static [&'static str] {
+		match self {
+			Category::Images => &["image/*"],
+			Category::Video => &["video/*"],
+			Category::Audio => &["audio/*"],
+			Category::Documents => &[
+				"application/pdf",
+				"application/rtf",
+				"application/x-ole-storage",
+			],
+			Category::Archives => &[
+				"application/zip",
+				"application/gzip",
+				"application/x-bzip2",
+				"application/x-xz",
+				"application/zstd",
+				"application/x-7z-compressed",
+				"application/vnd.rar",
+				"application/x-tar",
+			],
+		}
+	}
+
+	/// Returns whether a lowercase file extension is one of this category's.
+	pub(crate) fn has_extension(&self, extension: &str) -> bool {
+		self.extensions().contains(&extension)
+	}
+
+	/// Returns whether a MIME type is one of this category's.
+	pub(crate) fn has_content_type(&self, mime: &str) -> bool {
+		self.content_types().iter().any(|p| matches(mime, p))
+	}
+}
@@ -0,0 +1,238 @@
+use crate::duperror::DupError;
+use crate::paths::{FilePath, PathInterner};
+use crate::pipeline::Stage;
+use crate::priority;
+use crate::utilities::PathUtilities;
+use crate::DupResults;
+use std::collections::{HashMap, HashSet};
+use std::io;
+use std::path::PathBuf;
+use std::sync::mpsc::{sync_channel, Receiver, SyncSender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+/// The most files that can be waiting to go from one step of a concurrent
+/// search to the next, so a fast step doesn't run far ahead of a slow one.
+const QUEUE_LEN: usize = 256;
+
+/// The files found by a concurrent search of directories, with their sizes
+/// and any hashes read.
+pub(crate) struct Found {
+	/// The files whose sizes were read, in the order they were found.  Files
+	/// in the same directory share their parent directory's path.
+	pub(crate) files: Vec<FilePath>,
+
+	/// The size of each file.
+	pub(crate) sizes: HashMap<FilePath, u64>,
+
+	/// The hash of each file found to have the same size as another file.
+	pub(crate) hashes: HashMap<FilePath, String>,
+
+	/// Errors found while searching the directories, which have already been
+	/// sent as events.
+	pub(crate) search_errors: Vec<DupError>,
+
+	/// Errors found while reading the sizes or hashes of files, which have
+	/// been left out of `files`.
+	pub(crate) read_errors: Vec<DupError>,
+}
+
+impl DupResults {
+	/// Returns whether `within()` searches directories and reads files'
+	/// sizes and hashes concurrently.
+	///
+	/// This is only done if the comparison pipeline starts by comparing
+	/// sizes and then full hashes, since those are the stages that are run
+	/// early, and not if the sizes or hashes of all files are to be read
+	/// first anyway.
+	pub(crate) fn is_concurrent(&self) -> bool {
+		!self.options.sequential()
+			&& !self.options.prescan()
+			&& !self.options.hash_all()
+			&& self
+				.options
+				.pipeline()
+				.stages()
+				.starts_with(&[Stage::Size, Stage::FullHash])
+	}
+
+	/// Searches the given directories for files, reading the size of each
+	/// file as it's found and hashing each file as soon as another file with
+	/// the same size is found, so the directories are still being searched
+	/// while files are hashed.
+	///
+	/// The search, the size reads and the hashing run on their own threads,
+	/// with the hashing spread across as many threads as the system can run
+	/// at once, connected by queues holding at most `QUEUE_LEN` files.  Files
+	/// with the same size as the first file of an existing group are hashed,
+	/// and files already in a group aren't.
+	pub(crate) fn find_concurrently(&self, dirs: &[PathBuf]) -> Found {
+		let (file_sender, file_receiver) = sync_channel(QUEUE_LEN);
+		let (hash_sender, hash_receiver) = sync_channel(QUEUE_LEN);
+		let (result_sender, result_receiver) = sync_channel(QUEUE_LEN);
+		let hash_receiver = Arc::new(Mutex::new(hash_receiver));
+		let hashers = thread::available_parallelism().map_or(1, |n| n.get());
+
+		thread::scope(|scope| {
+			let search = scope.spawn(|| self.search(dirs, file_sender));
+			let sizes = scope.spawn(|| self.read_sizes(file_receiver, hash_sender));
+
+			for _ in 0..hashers {
+				let receiver = Arc::clone(&hash_receiver);
+				let sender = result_sender.clone();
+				scope.spawn(move || self.read_hashes(&receiver, sender));
+			}
+
+			// The results are only finished once every hashing thread has
+			// dropped its sender.
+			drop(result_sender);
+
+			let mut hashes = HashMap::new();
+			let mut unhashed = HashSet::new();
+			let mut read_errors = vec![];
+
+			for (path, hash) in result_receiver {
+				match hash {
+					Ok(h) => {
+						hashes.insert(path, h);
+					}
+					Err(e) => {
+						read_errors.push(DupError::new(path.to_path_buf(), e));
+						unhashed.insert(path);
+					}
+				};
+			}
+
+			let search_errors = search.join().unwrap_or_default();
+			let (mut files, sizes, mut size_errors) = sizes.join().unwrap_or_default();
+			files.retain(|f| !unhashed.contains(f));
+			read_errors.append(&mut size_errors);
+
+			Found {
+				files,
+				sizes,
+				hashes,
+				search_errors,
+				read_errors,
+			}
+		})
+	}
+
+	/// Searches the given directories, sending each file found to `files`,
+	/// and returns any errors found.
+	fn search(&self, dirs: &[PathBuf], files: SyncSender<PathBuf>) -> Vec<DupError> {
+		self.lower_priority();
+
+		let mut errors = vec![];
+		let mut searched = HashSet::new();
+
+		for dir in dirs {
+			let mut dir_errors = dir.walk_files(None, &self.options, &mut searched, &|f| {
+				let _ = files.send(f);
+			});
+			errors.append(&mut dir_errors);
+		}
+
+		errors
+	}
+
+	/// Reads the size of each file received from `files`, sending files to
+	/// `to_hash` once they're known to have the same size as another file,
+	/// and returns the files whose sizes were read, their sizes and any
+	/// errors found.
+	fn read_sizes(
+		&self,
+		files: Receiver<PathBuf>,
+		to_hash: SyncSender<FilePath>,
+	) -> (Vec<FilePath>, HashMap<FilePath, u64>, Vec<DupError>) {
+		self.lower_priority();
+
+		let retry = self.options.retry_policy();
+		let mut interner = PathInterner::new();
+		let mut found = vec![];
+		let mut sizes = HashMap::new();
+		let mut errors = vec![];
+
+		// The first file found with each size waits until another file with
+		// that size is found.  Sizes of existing groups' files, and sizes
+		// whose first file has been sent, have no file waiting.
+		let mut waiting: HashMap<u64, Option<FilePath>> = self
+			.duplicates
+			.iter()
+			.filter_map(|g| g.files[0].metadata().ok())
+			.map(|m| (m.len(), None))
+			.collect();
+
+		for file in files {
+			self.options.checkpoint();
+
+			let size = match retry.run(|| file.metadata()) {
+				Ok(metadata) => metadata.len(),
+				Err(e) => {
+					errors.push(DupError::new(file, e));
+					continue;
+				}
+			};
+
+			let checked = self.contains(&file);
+			let file = interner.intern(file);
+			found.push(file.clone());
+			sizes.insert(file.clone(), size);
+
+			if checked {
+				continue;
+			}
+
+			let to_send = match waiting.get_mut(&size) {
+				Some(first) => first.take().into_iter().chain([file]).collect(),
+				None => {
+					waiting.insert(size, Some(file));
+					vec![]
+				}
+			};
+
+			for file in to_send {
+				self.options.record(|p| p.add_total(1, size));
+				let _ = to_hash.send(file);
+			}
+		}
+
+		(found, sizes, errors)
+	}
+
+	/// Hashes each file received from `files`, sending its hash or the error
+	/// found to `hashes`.  The receiver is shared with the other hashing
+	/// threads.
+	fn read_hashes(
+		&self,
+		files: &Mutex<Receiver<FilePath>>,
+		hashes: SyncSender<(FilePath, io::Result<String>)>,
+	) {
+		self.lower_priority();
+
+		let retry = self.options.retry_policy();
+
+		loop {
+			// The lock is only held while waiting for the next file, so other
+			// threads can hash files at the same time.
+			let file = match files.lock().unwrap_or_else(|e| e.into_inner()).recv() {
+				Ok(f) => f,
+				Err(_) => return,
+			};
+
+			self.options.checkpoint();
+			let path = file.to_path_buf();
+			self.options.record(|p| p.set_current_path(Some(&path)));
+			let hash = retry.run(|| self.hash(&path));
+			let _ = hashes.send((file, hash));
+		}
+	}
+
+	/// Lowers the priority of the current thread, if checks are to run at a
+	/// lower priority.
+	fn lower_priority(&self) {
+		if self.options.low_priority() {
+			priority::lower_thread_priority();
+		}
+	}
+}
@@ -0,0 +1,307 @@
+use crate::actions::Action;
+use crate::keep::{KeepPolicy, KeepRules};
+use crate::options::DupOptions;
+use crate::schedule::Schedule;
+use crate::walker::BuiltinWalker;
+use std::fs::File;
+use std::io;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// A configuration file containing named scan profiles.
+///
+/// Each profile starts with its name in square brackets, followed by lines
+/// of `key = value` settings.  Blank lines and lines starting with `#` are
+/// ignored.  Settings that can be given more than once, such as `within`,
+/// add to the previous values.
+///
+/// ```text
+/// [photos]
+/// within = /home/me/Pictures
+/// within = /mnt/backup/photos
+/// type = images
+/// skip-junk = true
+/// keep-rule = under:/home/me/Pictures
+/// keep-rule = oldest
+/// action = hardlink
+/// ```
+///
+/// The settings are:
+///
+/// - `within`: a directory to check.
+/// - `skip-junk`: `true` to skip system junk files.
+/// - `skip-placeholders`: `true` to skip cloud storage placeholder files.
+/// - `exclude-dir`: a directory to skip.
+/// - `scan-virtual-filesystems`: `true` to search virtual filesystems such
+///   as `/proc` on Linux.
+/// - `type`: a category of files to check, as parsed by `Category`.
+/// - `mime` and `exclude-mime`: a MIME type of files to check or skip.
+/// - `exclude-signature`: the bytes files to skip start with, as parsed by
+///   `Signature`.
+/// - `normalize`: a normalization, as parsed by `Normalization`.
+/// - `ignore-hash`: a BLAKE3 hash whose duplicates are left out.
+/// - `min-group-size` and `min-redundant-bytes`: the fewest files or
+///   reclaimable bytes of groups to report.
+/// - `keep`: the keep policy, as parsed by `KeepPolicy`.
+/// - `keep-rule`: a keep rule, as parsed by `KeepRule`, used instead of
+///   `keep`.
+/// - `action`: the action taken on duplicates, as parsed by `Action`, or
+///   `move-to:<directory>`.
+/// - `schedule`: when a `Daemon` checks the profile's directories, as parsed
+///   by `Schedule`.
+/// - `webhook`: a URL to send a summary of the results to, as described for
+///   `DupResults::notify_webhook()`.
+#[derive(Clone, Debug, Default)]
+pub struct Config {
+	/// The profiles, in the order they're given.
+	profiles: Vec<Profile>,
+}
+
+impl Config {
+	/// Reads a configuration file from the given path.
+	///
+	/// # Errors
+	///
+	/// Returns an error if the file can't be read or if any line isn't a
+	/// valid profile name or setting.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// match dupcheck::Config::from_file("dupcheck.conf") {
+	///     Ok(config) => {
+	///         if let Some(profile) = config.profile("photos") {
+	///             let mut dup_result = dupcheck::DupResults::with_options(profile.options().clone());
+	///
+	///             if let Err(dup_error) = dup_result.within(profile.roots()) {
+	///                 // Error handling
+	///             }
+	///         }
+	///     }
+	///     Err(config_error) => {
+	///         // Error handling
+	///     }
+	/// }
+	/// ```
+	pub fn from_file<P: AsRef<Path>>(path: P) -> io::Result<Config> {
+		Config::read(BufReader::new(File::open(path)?))
+	}
+
+	/// Reads a configuration file from the given reader.
+	///
+	/// # Errors
+	///
+	/// Returns an error if the reader fails or if any line isn't a valid
+	/// profile name or setting.
+	pub fn read<R: BufRead>(reader: R) -> io::Result<Config> {
+		let mut config = Config::default();
+
+		for (i, line) in reader.lines().enumerate() {
+			let line = line?;
+			let line = line.trim();
+
+			if line.is_empty() || line.starts_with('#') {
+				continue;
+			}
+
+			let invalid = |message: String| {
+				io::Error::new(
+					io::ErrorKind::InvalidData,
+					format!("config line {}: {}", i + 1, message),
+				)
+			};
+
+			if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+				config.profiles.push(Profile::new(name.trim()));
+				continue;
+			}
+
+			let profile = config
+				.profiles
+				.last_mut()
+				.ok_or_else(|| invalid("setting outside a profile".to_string()))?;
+			let (key, value) = line
+				.split_once('=')
+				.ok_or_else(|| invalid("expected key = value".to_string()))?;
+			profile.set(key.trim(), value.trim()).map_err(invalid)?;
+		}
+
+		Ok(config)
+	}
+
+	/// Returns a reference to the profiles, in the order they're given.
+	pub fn profiles(&self) -> &[Profile] {
+		&self.profiles
+	}
+
+	/// Returns a reference to the profile with the given name, if any.
+	pub fn profile(&self, name: &str) -> Option<&Profile> {
+		self.profiles.iter().find(|p| p.name == name)
+	}
+}
+
+/// A named scan profile from a configuration file, giving the directories
+/// to check, the options to check them with and what to do with any
+/// duplicates found.
+#[derive(Clone, Debug)]
+pub struct Profile {
+	/// The profile's name.
+	name: String,
+
+	/// The directories to check.
+	roots: Vec<PathBuf>,
+
+	/// The options to check the directories with.
+	options: DupOptions,
+
+	/// The policy for choosing the file to keep from each group.
+	keep: KeepPolicy,
+
+	/// The action to take on duplicates, if any.
+	action: Option<Action>,
+
+	/// When a `Daemon` checks the directories, if it does.
+	schedule: Option<Schedule>,
+
+	/// The URL to send a summary of the results to, if any.
+	webhook: Option<String>,
+}
+
+impl Profile {
+	/// Creates a new `Profile` with the given name and default settings.
+	fn new(name: &str) -> Profile {
+		Profile {
+			name: name.to_string(),
+			roots: vec![],
+			options: DupOptions::new(),
+			keep: KeepPolicy::First,
+			action: None,
+			schedule: None,
+			webhook: None,
+		}
+	}
+
+	/// Returns the profile's name.
+	pub fn name(&self) -> &str {
+		&self.name
+	}
+
+	/// Returns a reference to the directories to check.
+	pub fn roots(&self) -> &[PathBuf] {
+		&self.roots
+	}
+
+	/// Returns a reference to the options to check the directories with.
+	pub fn options(&self) -> &DupOptions {
+		&self.options
+	}
+
+	/// Returns a reference to the policy for choosing the file to keep from
+	/// each group.
+	pub fn keep(&self) -> &KeepPolicy {
+		&self.keep
+	}
+
+	/// Returns a reference to the action to take on duplicates, if any.
+	pub fn action(&self) -> Option<&Action> {
+		self.action.as_ref()
+	}
+
+	/// Returns when a `Daemon` checks the directories, if it does.
+	pub fn schedule(&self) -> Option<Schedule> {
+		self.schedule
+	}
+
+	/// Returns the URL to send a summary of the results to, if any.
+	pub fn webhook(&self) -> Option<&str> {
+		self.webhook.as_deref()
+	}
+
+	/// Applies a setting from a configuration file.
+	fn set(&mut self, key: &str, value: &str) -> Result<(), String> {
+		let options = &mut self.options;
+
+		match key {
+			"within" => self.roots.push(PathBuf::from(value)),
+			"skip-junk" => options.set_skip_junk(parse_bool(value)?),
+			"skip-placeholders" => options.set_skip_placeholders(parse_bool(value)?),
+			"exclude-dir" => {
+				let mut dirs = options.excluded_dirs().to_vec();
+				dirs.push(PathBuf::from(value));
+				options.set_excluded_dirs(dirs);
+			}
+			"scan-virtual-filesystems" => options.set_scan_virtual_filesystems(parse_bool(value)?),
+			"walker" => options.set_walker(Some(Arc::new(value.parse::<BuiltinWalker>()?))),
+			"type" => {
+				let mut categories = options.categories().to_vec();
+				categories.push(value.parse()?);
+				options.set_categories(categories);
+			}
+			"mime" => {
+				let mut types = options.content_types().to_vec();
+				types.push(value.to_string());
+				options.set_content_types(types);
+			}
+			"exclude-mime" => {
+				let mut types = options.excluded_content_types().to_vec();
+				types.push(value.to_string());
+				options.set_excluded_content_types(types);
+			}
+			"exclude-signature" => {
+				let mut signatures = options.excluded_signatures().to_vec();
+				signatures.push(value.parse()?);
+				options.set_excluded_signatures(signatures);
+			}
+			"normalize" => {
+				let mut normalizations = options.normalizations().to_vec();
+				normalizations.push(value.parse()?);
+				options.set_normalizations(normalizations);
+			}
+			"ignore-hash" => {
+				let mut hashes = options.ignored_hashes().to_vec();
+				hashes.push(value.to_string());
+				options.set_ignored_hashes(hashes);
+			}
+			"min-group-size" => options.set_min_group_size(parse_number(value)?),
+			"min-redundant-bytes" => options.set_min_redundant_bytes(parse_number(value)?),
+			"keep" => self.keep = value.parse()?,
+			"keep-rule" => {
+				let mut rules = match &self.keep {
+					KeepPolicy::Rules(rules) => rules.clone(),
+					_ => KeepRules::default(),
+				};
+				rules.add(value.parse()?);
+				self.keep = KeepPolicy::Rules(rules);
+			}
+			"action" => {
+				self.action = Some(match value.strip_prefix("move-to:") {
+					Some(dir) => Action::MoveTo(PathBuf::from(dir)),
+					None => value.parse()?,
+				})
+			}
+			"schedule" => self.schedule = Some(value.parse()?),
+			"webhook" => self.webhook = Some(value.to_string()),
+			_ => return Err(format!("unknown setting {}", key)),
+		};
+
+		Ok(())
+	}
+}
+
+/// Parses a `true` or `false` setting value.
+fn parse_bool(value: &str) -> Result<bool, String> {
+	match value {
+		"true" => Ok(true),
+		"false" => Ok(false),
+		_ => Err(format!("expected true or false, not {}", value)),
+	}
+}
+
+/// Parses a whole number setting value.
+fn parse_number<T: FromStr>(value: &str) -> Result<T, String> {
+	value
+		.parse()
+		.map_err(|_| format!("expected a whole number, not {}", value))
+}
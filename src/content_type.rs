@@ -0,0 +1,101 @@
+use std::fs::File;
+use std::io;
+use std::io::Read;
+use std::path::Path;
+
+/// The number of bytes read from the start of a file to detect its type.
+const MAGIC_LEN: u64 = 512;
+
+/// Returns the MIME type of a file detected from the magic bytes at the
+/// start of its contents, or `None` if the type isn't recognised.
+pub(crate) fn content_type(file: &Path) -> io::Result<Option<&'static str>> {
+	let mut bytes = vec![];
+	File::open(file)?.take(MAGIC_LEN).read_to_end(&mut bytes)?;
+
+	Ok(detect(&bytes))
+}
+
+/// Returns the MIME type indicated by the given bytes from the start of a
+/// file, or `None` if the type isn't recognised.
+fn detect(bytes: &[u8]) -> Option<&'static str> {
+	let at = |offset: usize, magic: &[u8]| bytes.get(offset..offset + magic.len()) == Some(magic);
+
+	// RIFF containers give the type of their contents at offset 8.
+	if at(0, b"RIFF") {
+		return match bytes.get(8..12) {
+			Some(b"WEBP") => Some("image/webp"),
+			Some(b"WAVE") => Some("audio/wav"),
+			Some(b"AVI ") => Some("video/x-msvideo"),
+			_ => None,
+		};
+	}
+
+	// ISO base media files give their brand after `ftyp` at offset 4.
+	if at(4, b"ftyp") {
+		return match bytes.get(8..12) {
+			Some(b"heic") | Some(b"heix") | Some(b"mif1") | Some(b"msf1") => Some("image/heif"),
+			Some(b"avif") => Some("image/avif"),
+			Some(b"qt  ") => Some("video/quicktime"),
+			Some(b"M4A ") | Some(b"M4B ") => Some("audio/mp4"),
+			Some(b"3gp4") | Some(b"3gp5") | Some(b"3g2a") => Some("video/3gpp"),
+			_ => Some("video/mp4"),
+		};
+	}
+
+	let signatures: &[(usize, &[u8], &str)] = &[
+		(0, b"\xFF\xD8\xFF", "image/jpeg"),
+		(0, b"\x89PNG\r\n\x1A\n", "image/png"),
+		(0, b"GIF87a", "image/gif"),
+		(0, b"GIF89a", "image/gif"),
+		(0, b"II*\0", "image/tiff"),
+		(0, b"MM\0*", "image/tiff"),
+		(0, b"BM", "image/bmp"),
+		(0, b"\0\0\x01\0", "image/x-icon"),
+		(0, b"8BPS", "image/vnd.adobe.photoshop"),
+		(0, b"fLaC", "audio/flac"),
+		(0, b"OggS", "audio/ogg"),
+		(0, b"ID3", "audio/mpeg"),
+		(0, b"\xFF\xFB", "audio/mpeg"),
+		(0, b"\xFF\xF3", "audio/mpeg"),
+		(0, b"\xFF\xF2", "audio/mpeg"),
+		(0, b"\x1A\x45\xDF\xA3", "video/x-matroska"),
+		(0, b"FLV", "video/x-flv"),
+		(0, b"%PDF-", "application/pdf"),
+		(0, b"{\\rtf", "application/rtf"),
+		(
+			0,
+			b"\xD0\xCF\x11\xE0\xA1\xB1\x1A\xE1",
+			"application/x-ole-storage",
+		),
+		(0, b"PK\x03\x04", "application/zip"),
+		(0, b"PK\x05\x06", "application/zip"),
+		(0, b"\x1F\x8B", "application/gzip"),
+		(0, b"BZh", "application/x-bzip2"),
+		(0, b"\xFD7zXZ\0", "application/x-xz"),
+		(0, b"\x28\xB5\x2F\xFD", "application/zstd"),
+		(0, b"7z\xBC\xAF\x27\x1C", "application/x-7z-compressed"),
+		(0, b"Rar!\x1A\x07", "application/vnd.rar"),
+		(257, b"ustar", "application/x-tar"),
+		(0, b"\x7FELF", "application/x-executable"),
+		(0, b"MZ", "application/vnd.microsoft.portable-executable"),
+		(0, b"\0asm", "application/wasm"),
+		(0, b"SQLite format 3\0", "application/vnd.sqlite3"),
+		(0, b"wOFF", "font/woff"),
+		(0, b"wOF2", "font/woff2"),
+	];
+
+	signatures
+		.iter()
+		.find(|(offset, magic, _)| at(*offset, magic))
+		.map(|(_, _, mime)| *mime)
+}
+
+/// Returns whether a MIME type matches a pattern, which is either a full
+/// MIME type such as `image/png` or a type with a wildcard subtype such as
+/// `image/*`.
+pub(crate) fn matches(mime: &str, pattern: &str) -> bool {
+	match pattern.strip_suffix("/*") {
+		Some(top) => mime.split('/').next() == Some(top),
+		None => mime.eq_ignore_ascii_case(pattern),
+	}
+}
@@ -0,0 +1,371 @@
+use crate::cache::HashCache;
+use crate::config::Profile;
+use crate::handle::ScanHandle;
+use crate::json;
+use crate::metrics::Metrics;
+use crate::progress::Progress;
+use crate::utilities::temp_path;
+use crate::{DupOptions, DupResults};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// A long-running duplicate checker that keeps a cache of file hashes
+/// between checks, so repeated checks of the same directories only read
+/// files that have changed.
+///
+/// Requests are single lines of text, and each response is a single line of
+/// JSON:
+///
+/// - `scan <dir>`, with any further directories separated by tabs, checks
+///   the directories for duplicates and responds with the results in the
+///   format written by `DupResults::write_json()`.
+/// - `ping` responds with `{"ok":true}`.
+/// - `clear` empties the hash cache.
+/// - `pause` pauses all checks before they next read a file or directory,
+///   and `resume` resumes them.  Both respond with `{"ok":true}`.
+/// - `metrics` responds with the number of checks run and the numbers of
+///   files found and hashed, and bytes hashed, by all of them, along with
+///   the total duplicate groups, redundant files and reclaimable bytes found
+///   by the latest check of each set of directories.
+/// - `results <profile>` responds with the saved results of the latest
+///   scheduled check of a profile, as described for `schedule()`.
+/// - `shutdown` stops `listen()` once the response is sent.
+///
+/// Requests that fail are responded to with `{"error":"<message>"}`.
+#[derive(Clone, Debug)]
+pub struct Daemon {
+	/// The options used for each check, including the shared hash cache.
+	options: DupOptions,
+
+	/// Totals recorded over all checks.
+	metrics: Arc<Mutex<Metrics>>,
+
+	/// The directory that the results of scheduled checks are saved in, if
+	/// any checks are scheduled.
+	results_dir: Option<PathBuf>,
+}
+
+impl Daemon {
+	/// Creates a new `Daemon` that runs checks with the given options.  If
+	/// the options don't have a hash cache or scan handle, new ones are
+	/// used.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// let daemon = dupcheck::Daemon::new(dupcheck::DupOptions::new());
+	/// let response = daemon.handle("scan foo");
+	/// ```
+	pub fn new(mut options: DupOptions) -> Daemon {
+		if options.hash_cache().is_none() {
+			options.set_hash_cache(Some(HashCache::new()));
+		}
+
+		if options.scan_handle().is_none() {
+			options.set_scan_handle(Some(ScanHandle::new()));
+		}
+
+		Daemon {
+			options,
+			metrics: Arc::new(Mutex::new(Metrics::default())),
+			results_dir: None,
+		}
+	}
+
+	/// Returns a reference to the options used for each check.
+	pub fn options(&self) -> &DupOptions {
+		&self.options
+	}
+
+	/// Handles a single request, returning the response as a line of JSON
+	/// without a trailing newline.
+	pub fn handle(&self, request: &str) -> String {
+		let request = request.trim_end_matches(['\r', '\n']);
+		let (command, args) = match request.split_once(' ') {
+			Some((command, args)) => (command, args),
+			None => (request, ""),
+		};
+
+		match command {
+			"scan" if !args.is_empty() => {
+				let dirs: Vec<PathBuf> = args.split('\t').map(PathBuf::from).collect();
+
+				match self
+					.check(self.options.clone(), &dirs)
+					.and_then(|r| results_json(&r))
+				{
+					Ok(results) => results,
+					Err(e) => json::error(&e.to_string()),
+				}
+			}
+			"scan" => json::error("no directories given"),
+			"ping" | "shutdown" => String::from("{\"ok\":true}"),
+			"clear" => {
+				if let Some(cache) = self.options.hash_cache() {
+					cache.clear();
+				}
+
+				String::from("{\"ok\":true}")
+			}
+			"pause" | "resume" => {
+				if let Some(handle) = self.options.scan_handle() {
+					match command {
+						"pause" => handle.pause(),
+						_ => handle.resume(),
+					};
+				}
+
+				String::from("{\"ok\":true}")
+			}
+			"metrics" => self.lock_metrics().json(),
+			"results" => match self.saved_results(args) {
+				Ok(results) => results,
+				Err(e) => json::error(&e.to_string()),
+			},
+			_ => json::error(&format!("unknown command: {}", command)),
+		}
+	}
+
+	/// Starts checking the directories of each of the given profiles that
+	/// has a schedule, according to its schedule, with the profile's options
+	/// and this daemon's hash cache.  Profiles' actions aren't taken.
+	///
+	/// After each check, the results are saved in `results_dir` as
+	/// `<profile>.json`, replacing the previous results, in the form
+	/// `{"profile":"<name>","finished":<time>,"results":<results>}`, where the
+	/// time is in seconds since the Unix epoch and the results are in the
+	/// format written by `DupResults::write_json()`, or are an error object if
+	/// the check failed.  If the profile has a webhook, it's sent a summary
+	/// of the results, and a `webhook_error` is added if that fails.  The
+	/// latest results can also be requested with `results <profile>`.
+	///
+	/// # Errors
+	///
+	/// Returns an error if `results_dir` can't be created.
+	///
+	/// # Examples
+	///
+	/// ```no_run
+	/// let mut daemon = dupcheck::Daemon::new(dupcheck::DupOptions::new());
+	///
+	/// match dupcheck::Config::from_file("dupcheck.conf") {
+	///     Ok(config) => {
+	///         if let Err(schedule_error) = daemon.schedule(config.profiles(), "results") {
+	///             // Error handling
+	///         }
+	///     }
+	///     Err(config_error) => {
+	///         // Error handling
+	///     }
+	/// }
+	/// ```
+	pub fn schedule<P: AsRef<Path>>(
+		&mut self,
+		profiles: &[Profile],
+		results_dir: P,
+	) -> io::Result<()> {
+		let results_dir = results_dir.as_ref().to_path_buf();
+		fs::create_dir_all(&results_dir)?;
+		self.results_dir = Some(results_dir.clone());
+
+		for profile in profiles {
+			let schedule = match profile.schedule() {
+				Some(s) => s,
+				None => continue,
+			};
+			let daemon = self.clone();
+			let profile = profile.clone();
+			let path = results_dir.join(format!("{}.json", profile.name()));
+
+			thread::spawn(move || loop {
+				let due = match schedule.next_after(SystemTime::now()) {
+					Some(due) => due,
+					None => return,
+				};
+
+				// The wait is split up so that changes to the system clock
+				// don't leave checks waiting too long.
+				while let Ok(wait) = due.duration_since(SystemTime::now()) {
+					thread::sleep(wait.min(Duration::from_secs(60)));
+				}
+
+				let mut options = profile.options().clone();
+				options.set_hash_cache(daemon.options.hash_cache().cloned());
+				options.set_scan_handle(daemon.options.scan_handle().cloned());
+
+				let (results, webhook_error) = match daemon.check(options, profile.roots()) {
+					Ok(dup_results) => (
+						results_json(&dup_results),
+						profile
+							.webhook()
+							.and_then(|url| dup_results.notify_webhook(url).err()),
+					),
+					Err(e) => (Err(e), None),
+				};
+				let finished = SystemTime::now()
+					.duration_since(UNIX_EPOCH)
+					.map_or(0, |d| d.as_secs());
+				let mut saved = format!(
+					"{{\"profile\":{},\"finished\":{},\"results\":{}",
+					json::string(profile.name()),
+					finished,
+					results.unwrap_or_else(|e| json::error(&e.to_string())),
+				);
+
+				if let Some(e) = webhook_error {
+					saved.push_str(&format!(
+						",\"webhook_error\":{}",
+						json::string(&e.to_string())
+					));
+				}
+
+				saved.push_str("}\n");
+				let temp_path = temp_path(&path);
+
+				let _ = fs::write(&temp_path, saved).and_then(|_| fs::rename(&temp_path, &path));
+			});
+		}
+
+		Ok(())
+	}
+
+	/// Checks the given directories with the given options, recording
+	/// metrics, and returns the results.
+	fn check(&self, mut options: DupOptions, dirs: &[PathBuf]) -> io::Result<DupResults> {
+		let progress = Progress::new();
+		options.set_progress(Some(progress.clone()));
+
+		let mut dup_results = DupResults::with_options(options);
+		let checked = dup_results.within(dirs);
+		self
+			.lock_metrics()
+			.record(dirs, &progress, checked.as_ref().ok().map(|_| &dup_results));
+
+		checked.map(|_| dup_results)
+	}
+
+	/// Returns the saved results of the latest scheduled check of a profile.
+	fn saved_results(&self, name: &str) -> io::Result<String> {
+		let not_found = || io::Error::new(io::ErrorKind::NotFound, "no saved results for profile");
+
+		// Only plain names are accepted, so requests can't read other files.
+		let results_dir = match &self.results_dir {
+			Some(dir) if !name.is_empty() && Path::new(name).file_name() == Some(name.as_ref()) => dir,
+			_ => return Err(not_found()),
+		};
+
+		match fs::read_to_string(results_dir.join(format!("{}.json", name))) {
+			Ok(results) => Ok(results.trim_end().to_string()),
+			Err(e) if e.kind() == io::ErrorKind::NotFound => Err(not_found()),
+			Err(e) => Err(e),
+		}
+	}
+
+	/// Locks the metrics, recovering them if another thread panicked while
+	/// holding the lock.
+	fn lock_metrics(&self) -> MutexGuard<'_, Metrics> {
+		self.metrics.lock().unwrap_or_else(|e| e.into_inner())
+	}
+}
+
+#[cfg(unix)]
+impl Daemon {
+	/// Listens for requests on a Unix socket at the given path until a
+	/// `shutdown` request is received, then removes the socket.  Each
+	/// connection is handled on its own thread, and may send any number of
+	/// requests.
+	///
+	/// If a socket already exists at the path but nothing is listening on
+	/// it, it's replaced.
+	///
+	/// # Errors
+	///
+	/// Returns an error if another process is already listening at the path
+	/// or the socket can't be created.
+	///
+	/// # Examples
+	///
+	/// ```no_run
+	/// let daemon = dupcheck::Daemon::new(dupcheck::DupOptions::new());
+	///
+	/// if let Err(daemon_error) = daemon.listen("/tmp/dupcheck.sock") {
+	///     // Error handling
+	/// }
+	/// ```
+	pub fn listen<P: AsRef<std::path::Path>>(&self, path: P) -> io::Result<()> {
+		use std::io::{BufRead, BufReader, Write};
+		use std::os::unix::net::{UnixListener, UnixStream};
+		use std::sync::atomic::{AtomicBool, Ordering};
+		use std::sync::Arc;
+		use std::thread;
+
+		let path = path.as_ref().to_path_buf();
+
+		if path.exists() {
+			if UnixStream::connect(&path).is_ok() {
+				return Err(io::Error::new(
+					io::ErrorKind::AddrInUse,
+					"another process is already listening on the socket",
+				));
+			}
+
+			std::fs::remove_file(&path)?;
+		}
+
+		let listener = UnixListener::bind(&path)?;
+		let stopping = Arc::new(AtomicBool::new(false));
+
+		for stream in listener.incoming() {
+			if stopping.load(Ordering::SeqCst) {
+				break;
+			}
+
+			let mut stream = match stream {
+				Ok(s) => s,
+				Err(_) => continue,
+			};
+			let daemon = self.clone();
+			let stopping = Arc::clone(&stopping);
+			let path = path.clone();
+
+			thread::spawn(move || {
+				let reader = match stream.try_clone() {
+					Ok(s) => BufReader::new(s),
+					Err(_) => return,
+				};
+
+				for line in reader.lines() {
+					let line = match line {
+						Ok(l) => l,
+						Err(_) => return,
+					};
+
+					if writeln!(stream, "{}", daemon.handle(&line)).is_err() {
+						return;
+					}
+
+					if line.trim() == "shutdown" {
+						stopping.store(true, Ordering::SeqCst);
+						// Wakes the listener so it sees that it's stopping.
+						let _ = UnixStream::connect(&path);
+						return;
+					}
+				}
+			});
+		}
+
+		std::fs::remove_file(&path)
+	}
+}
+
+/// Returns results as a line of JSON without a trailing newline.
+fn results_json(dup_results: &DupResults) -> io::Result<String> {
+	let mut results = vec![];
+	dup_results.write_json(&mut results)?;
+
+	Ok(String::from_utf8_lossy(&results).trim_end().to_string())
+}
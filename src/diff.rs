@@ -0,0 +1,63 @@
+use crate::manifest::Manifest;
+use crate::DupResults;
+use std::collections::HashSet;
+use std::path::Path;
+
+impl DupResults {
+	/// Returns a manifest listing the hash and path of each file in the
+	/// duplicate groups, which can be saved with `Manifest::write()` and read
+	/// back to pass to `retain_new()` after a later check.
+	pub fn duplicates_manifest(&self) -> Manifest {
+		let mut manifest = Manifest::new();
+
+		for group in &self.duplicates {
+			for file in &group.files {
+				manifest.add(group.hash.clone(), file.to_path_buf());
+			}
+		}
+
+		manifest
+	}
+
+	/// Removes the duplicate groups that were already found by a previous
+	/// check, given the manifest returned by `duplicates_manifest()` after
+	/// that check, so that only new duplicates are left.
+	///
+	/// A group is kept if any of its files weren't listed with the group's
+	/// hash in the previous manifest, such as when another copy of a known
+	/// duplicate has appeared.  All of the files in a kept group are kept,
+	/// including those already listed.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use std::path::PathBuf;
+	///
+	/// let dirs = vec![PathBuf::from("foo")];
+	/// let mut dup_result = dupcheck::DupResults::new();
+	///
+	/// if let Err(dup_error) = dup_result.within(&dirs) {
+	///     // Error handling
+	/// }
+	///
+	/// match dupcheck::Manifest::from_file("last-check.b3") {
+	///     Ok(previous) => dup_result.retain_new(&previous),
+	///     Err(manifest_error) => {
+	///         // Error handling
+	///     }
+	/// }
+	/// ```
+	pub fn retain_new(&mut self, previous: &Manifest) {
+		let known: HashSet<(&str, &Path)> = previous
+			.entries()
+			.iter()
+			.map(|(hash, path)| (hash.as_str(), path.as_path()))
+			.collect();
+
+		self.duplicates.retain(|g| {
+			g.files
+				.iter()
+				.any(|f| !known.contains(&(g.hash.as_str(), &**f)))
+		});
+	}
+}
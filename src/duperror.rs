@@ -1,7 +1,7 @@
 use std::error::Error;
 use std::fmt;
 use std::io;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 #[derive(Debug)]
 pub struct DupError {
@@ -13,6 +13,16 @@ impl DupError {
 	pub fn new(path: PathBuf, io_error: io::Error) -> DupError {
 		DupError { path, io_error }
 	}
+
+	/// Returns the path of the file or directory the error occurred for.
+	pub(crate) fn path(&self) -> &Path {
+		&self.path
+	}
+
+	/// Returns the error that occurred.
+	pub(crate) fn io_error(&self) -> &io::Error {
+		&self.io_error
+	}
 }
 
 impl fmt::Display for DupError {
@@ -0,0 +1,215 @@
+use crate::export::group_json;
+use crate::json;
+use crate::DupGroup;
+use std::path::{Path, PathBuf};
+
+/// An event during a duplicate file check, sent to the event sender set by
+/// `DupOptions::set_event_sender()`.
+#[derive(Clone, Debug)]
+pub enum ScanEvent {
+	/// A directory is being searched for files.
+	DirEntered(PathBuf),
+
+	/// A file in a directory being searched won't be checked, or a directory
+	/// won't be searched.
+	FileSkipped {
+		/// The path to the file or directory.
+		path: PathBuf,
+
+		/// Why the file won't be checked.
+		reason: SkipReason,
+	},
+
+	/// A file's hash was found.
+	FileHashed {
+		/// The path to the file.
+		path: PathBuf,
+
+		/// The file's hash.  This is a hash of the normalized contents if the
+		/// file was hashed by `Stage::NormalizedHash`.
+		hash: String,
+	},
+
+	/// A duplicate file group was found, or more duplicates were added to
+	/// it.  The group contains all of its files so far, and is boxed since
+	/// it's much larger than the other events.
+	GroupUpdated(Box<DupGroup>),
+
+	/// An error occurred, which has also been added to the results' errors.
+	Error(String),
+
+	/// A check finished.
+	Finished {
+		/// A summary of the results.
+		stats: ScanStats,
+	},
+}
+
+impl ScanEvent {
+	/// Returns the event as a single line of JSON, so events can be written
+	/// as newline-delimited JSON while a check runs.
+	///
+	/// The event is an object whose `event` is the name of the event in
+	/// snake case, such as `file_hashed`, with its fields.  A `file_skipped`
+	/// event's `reason` is also in snake case, such as `already_searched`.  A
+	/// `group_updated` event's `group` is in the form of the groups written
+	/// by `DupResults::write_json()`, an `error` event has a `message`, and a
+	/// `finished` event's `stats` has the fields of a `ScanStats`.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use std::path::PathBuf;
+	/// use std::sync::mpsc;
+	/// use std::thread;
+	///
+	/// let (sender, receiver) = mpsc::channel();
+	/// let mut options = dupcheck::DupOptions::new();
+	/// options.set_event_sender(Some(sender));
+	///
+	/// thread::spawn(move || {
+	///     let dirs = vec![PathBuf::from("foo")];
+	///     let mut dup_result = dupcheck::DupResults::with_options(options);
+	///     let _ = dup_result.within(&dirs);
+	/// });
+	///
+	/// for event in receiver {
+	///     println!("{}", event.to_json());
+	/// }
+	/// ```
+	pub fn to_json(&self) -> String {
+		match self {
+			ScanEvent::DirEntered(path) => {
+				format!("{{\"event\":\"dir_entered\",\"path\":{}}}", path_json(path))
+			}
+			ScanEvent::FileSkipped { path, reason } => format!(
+				"{{\"event\":\"file_skipped\",\"path\":{},\"reason\":\"{}\"}}",
+				path_json(path),
+				reason.name()
+			),
+			ScanEvent::FileHashed { path, hash } => format!(
+				"{{\"event\":\"file_hashed\",\"path\":{},\"hash\":{}}}",
+				path_json(path),
+				json::string(hash)
+			),
+			ScanEvent::GroupUpdated(group) => format!(
+				"{{\"event\":\"group_updated\",\"group\":{}}}",
+				group_json(group)
+			),
+			ScanEvent::Error(message) => format!(
+				"{{\"event\":\"error\",\"message\":{}}}",
+				json::string(message)
+			),
+			ScanEvent::Finished { stats } => format!(
+				"{{\"event\":\"finished\",\"stats\":{{\"files_checked\":{},\"groups\":{},\"files\":{},\"errors\":{}}}}}",
+				stats.files_checked, stats.groups, stats.files, stats.errors
+			),
+		}
+	}
+}
+
+/// Why a file found in a directory won't be checked.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SkipReason {
+	/// The file is a system junk file and `DupOptions::skip_junk()` is
+	/// enabled.
+	Junk,
+
+	/// The file is a cloud storage placeholder whose contents aren't stored
+	/// locally, and `DupOptions::skip_placeholders()` is enabled.
+	Placeholder,
+
+	/// The file starts with one of the signatures given by
+	/// `DupOptions::excluded_signatures()`.
+	Signature,
+
+	/// No file being checked for duplicates has the same size.
+	Size,
+
+	/// The file isn't allowed by the category or MIME type options.
+	Filtered,
+
+	/// The directory is excluded by `DupOptions::excluded_dirs()`, or is a
+	/// virtual filesystem, so none of its files are checked.
+	ExcludedDir,
+
+	/// The directory was already searched by the check, as it's the same
+	/// directory as one reached through another path, such as a bind mount,
+	/// a symbolic link or a directory given more than once.
+	AlreadySearched,
+
+	/// The file was already checked by a previous check with the same
+	/// `DupResults`.
+	AlreadyChecked,
+}
+
+impl SkipReason {
+	/// Returns the reason's name in snake case.
+	fn name(&self) -> &'static str {
+		match self {
+			SkipReason::Junk => "junk",
+			SkipReason::Placeholder => "placeholder",
+			SkipReason::Signature => "signature",
+			SkipReason::Size => "size",
+			SkipReason::Filtered => "filtered",
+			SkipReason::ExcludedDir => "excluded_dir",
+			SkipReason::AlreadySearched => "already_searched",
+			SkipReason::AlreadyChecked => "already_checked",
+		}
+	}
+}
+
+/// A summary of the results of a finished check.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ScanStats {
+	/// The number of files checked.
+	files_checked: usize,
+
+	/// The number of duplicate file groups.
+	groups: usize,
+
+	/// The number of files in duplicate file groups.
+	files: usize,
+
+	/// The number of errors.
+	errors: usize,
+}
+
+impl ScanStats {
+	/// Creates a new `ScanStats`.
+	pub(crate) fn new(files_checked: usize, groups: usize, files: usize, errors: usize) -> ScanStats {
+		ScanStats {
+			files_checked,
+			groups,
+			files,
+			errors,
+		}
+	}
+
+	/// Returns the number of files checked by the check, not counting files
+	/// that were skipped.
+	pub fn files_checked(&self) -> usize {
+		self.files_checked
+	}
+
+	/// Returns the number of duplicate file groups in the results, including
+	/// any from previous checks with the same `DupResults`.
+	pub fn groups(&self) -> usize {
+		self.groups
+	}
+
+	/// Returns the number of files in duplicate file groups in the results.
+	pub fn files(&self) -> usize {
+		self.files
+	}
+
+	/// Returns the number of errors in the results.
+	pub fn errors(&self) -> usize {
+		self.errors
+	}
+}
+
+/// Returns a path as a JSON string.
+fn path_json(path: &Path) -> String {
+	json::string(&path.to_string_lossy())
+}
@@ -0,0 +1,99 @@
+use crate::events::SkipReason;
+use crate::pipeline::Stage;
+use crate::utilities::absolute;
+use crate::DupResults;
+use std::path::Path;
+
+/// Why a file did or didn't end up in a duplicate file group, as given by
+/// `DupResults::explain()`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Explanation {
+	/// The file is in the duplicate file group with the given hash, as given
+	/// by `DupGroup::get_hash()`.
+	Duplicate(String),
+
+	/// The file couldn't be read, with the given error message.
+	Error(String),
+
+	/// The file, or a directory containing it, was skipped for the given
+	/// reason.
+	Skipped(SkipReason),
+
+	/// The file was compared, but no other file matched it at the given
+	/// comparison stage, such as `Stage::Size` for a file whose size is
+	/// unique.
+	Unique(Stage),
+
+	/// Nothing is known about the file.  It wasn't found by a check with
+	/// these results or `DupOptions::explainable()` wasn't enabled for the
+	/// check, or it matched other files in a group left out of the results by
+	/// `DupOptions::ignored_hashes()`, `DupOptions::min_group_size()` or
+	/// `DupOptions::min_redundant_bytes()`.
+	Unknown,
+}
+
+impl DupResults {
+	/// Returns why a file did or didn't end up in a duplicate file group in
+	/// the checks with these `DupResults`, which is useful for finding out
+	/// why a file expected to be a duplicate isn't.
+	///
+	/// Files in groups and files that couldn't be read are always explained.
+	/// Skipped files and files found to have no duplicates can only be
+	/// explained if `DupOptions::explainable()` was enabled for the check.
+	/// They're kept in results saved by `write_binary()`, so results read
+	/// back with `read_binary()` can be explained without checking again,
+	/// apart from files that couldn't be read.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use std::path::PathBuf;
+	///
+	/// let dirs = vec![PathBuf::from("foo")];
+	/// let mut options = dupcheck::DupOptions::new();
+	/// options.set_explainable(true);
+	///
+	/// let mut dup_result = dupcheck::DupResults::with_options(options);
+	///
+	/// if let Err(dup_error) = dup_result.within(&dirs) {
+	///     // Error handling
+	/// }
+	///
+	/// println!("{:?}", dup_result.explain("foo/bar.txt"));
+	/// ```
+	pub fn explain<P: AsRef<Path>>(&self, path: P) -> Explanation {
+		let path = absolute(path.as_ref());
+		let is_path = |p: &Path| absolute(p) == path;
+
+		// A file's group is given even if it was unique or skipped in an
+		// earlier check.
+		let group = self
+			.duplicates
+			.iter()
+			.find(|g| g.files.iter().chain(&g.queried).any(|f| is_path(f)));
+
+		if let Some(group) = group {
+			return Explanation::Duplicate(group.hash.clone());
+		}
+
+		if let Some(dup_error) = self.errors.iter().rev().find(|e| is_path(e.path())) {
+			return Explanation::Error(dup_error.io_error().to_string());
+		}
+
+		// Files within skipped directories are explained by the directory.
+		let skipped = self
+			.skipped
+			.iter()
+			.rev()
+			.find(|(p, _)| path.starts_with(absolute(p)));
+
+		if let Some((_, reason)) = skipped {
+			return Explanation::Skipped(*reason);
+		}
+
+		match self.unique.iter().find(|(p, _)| is_path(p)) {
+			Some((_, stage)) => Explanation::Unique(*stage),
+			None => Explanation::Unknown,
+		}
+	}
+}
@@ -0,0 +1,213 @@
+use crate::json;
+use crate::utilities::file_id;
+use crate::{DupGroup, DupResults};
+use std::io;
+use std::io::Write;
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+
+impl DupResults {
+	/// Writes the duplicate file groups to the given writer in the JSON format
+	/// used by rmlint's `json` formatter, so they can be used by tools and
+	/// scripts that understand rmlint's output.
+	///
+	/// The canonical copy of each group, or its first file if none has been
+	/// designated, is marked as the original and the others as its
+	/// duplicates.  Files that can no longer be read are written with
+	/// a size, inode and modification time of zero.
+	///
+	/// # Errors
+	///
+	/// Returns an error if writing fails.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use std::path::PathBuf;
+	///
+	/// let dirs = vec![PathBuf::from("foo")];
+	/// let mut dup_result = dupcheck::DupResults::new();
+	///
+	/// if let Err(dup_error) = dup_result.within(&dirs) {
+	///     // Error handling
+	/// }
+	///
+	/// if let Err(write_error) = dup_result.write_rmlint_json(&mut std::io::stdout()) {
+	///     // Error handling
+	/// }
+	/// ```
+	pub fn write_rmlint_json<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+		let cwd = std::env::current_dir().unwrap_or_default();
+
+		writeln!(writer, "[")?;
+		writeln!(
+			writer,
+			"{{\"description\": \"rmlint json-dump of lint files\", \"cwd\": {}, \"args\": {}, \"version\": {}, \"checksum_type\": \"blake3\"}},",
+			json::string(&cwd.to_string_lossy()),
+			json::string(&std::env::args().collect::<Vec<String>>().join(" ")),
+			json::string(env!("CARGO_PKG_VERSION")),
+		)?;
+
+		let mut id = 0;
+		let mut total_lint_size = 0;
+
+		for group in &self.duplicates {
+			let original = group.canonical_index();
+
+			for (i, file) in group.files.iter().enumerate() {
+				let metadata = file.metadata().ok();
+				let size = metadata.as_ref().map_or(0, |m| m.len());
+				let (disk_id, inode) = metadata.as_ref().and_then(file_id).unwrap_or((0, 0));
+				let mtime = metadata
+					.as_ref()
+					.and_then(|m| m.modified().ok())
+					.and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+					.map_or(0.0, |d| d.as_secs_f64());
+				let depth = file.components().count().saturating_sub(1);
+				id += 1;
+
+				if i != original {
+					total_lint_size += size;
+				}
+
+				writeln!(
+					writer,
+					"{{\"id\": {}, \"type\": \"duplicate_file\", \"progress\": 100, \"checksum\": {}, \"path\": {}, \"size\": {}, \"depth\": {}, \"inode\": {}, \"disk_id\": {}, \"is_original\": {}, \"mtime\": {:.6}}},",
+					id,
+					json::string(&group.hash),
+					json::string(&file.to_string_lossy()),
+					size,
+					depth,
+					inode,
+					disk_id,
+					i == original,
+					mtime,
+				)?;
+			}
+		}
+
+		writeln!(
+			writer,
+			"{{\"aborted\": false, \"progress\": 100, \"duplicates\": {}, \"duplicate_sets\": {}, \"total_files\": {}, \"total_lint_size\": {}}}",
+			self.file_count() - self.duplicates.len(),
+			self.duplicates.len(),
+			self.file_count(),
+			total_lint_size,
+		)?;
+		writeln!(writer, "]")?;
+
+		Ok(())
+	}
+
+	/// Writes the results to the given writer as a single line of JSON, with
+	/// the duplicate groups, similar groups, blocked files and errors.  Each
+	/// duplicate group's `roots` give the directory each of its `files` was
+	/// found in, as described for `DupGroup::root()`, or `null`, its
+	/// `queried` files are those given by `DupGroup::queried_files()`, its
+	/// `canonical` is the path given by `DupGroup::canonical()` or `null`, and
+	/// its `tags` are an object of the keys and values given by
+	/// `DupGroup::tags()`.
+	///
+	/// # Errors
+	///
+	/// Returns an error if writing fails.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use std::path::PathBuf;
+	///
+	/// let dirs = vec![PathBuf::from("foo")];
+	/// let mut dup_result = dupcheck::DupResults::new();
+	///
+	/// if let Err(dup_error) = dup_result.within(&dirs) {
+	///     // Error handling
+	/// }
+	///
+	/// if let Err(write_error) = dup_result.write_json(&mut std::io::stdout()) {
+	///     // Error handling
+	/// }
+	/// ```
+	pub fn write_json<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+		let groups: Vec<String> = self.duplicates.iter().map(group_json).collect();
+		let similar: Vec<String> = self
+			.similar
+			.iter()
+			.map(|group| {
+				format!(
+					"{{\"similarity\":{},\"files\":{}}}",
+					group.similarity(),
+					paths(group.get_files()),
+				)
+			})
+			.collect();
+		let blocked: Vec<String> = self
+			.blocked
+			.iter()
+			.map(|(hash, file)| {
+				format!(
+					"{{\"hash\":{},\"file\":{}}}",
+					json::string(hash),
+					json::string(&file.to_string_lossy()),
+				)
+			})
+			.collect();
+		let errors: Vec<String> = self
+			.errors
+			.iter()
+			.map(|e| json::string(&e.to_string()))
+			.collect();
+
+		writeln!(
+			writer,
+			"{{\"duplicates\":[{}],\"similar\":[{}],\"blocked\":[{}],\"errors\":[{}]}}",
+			groups.join(","),
+			similar.join(","),
+			blocked.join(","),
+			errors.join(","),
+		)
+	}
+}
+
+/// Returns a duplicate file group as a JSON object, in the form written by
+/// `DupResults::write_json()`.
+pub(crate) fn group_json(group: &DupGroup) -> String {
+	let roots: Vec<String> = (0..group.files.len())
+		.map(|i| match group.root_at(i) {
+			Some(root) => json::string(&root.to_string_lossy()),
+			None => String::from("null"),
+		})
+		.collect();
+
+	let tags: Vec<String> = group
+		.tags
+		.iter()
+		.map(|(key, value)| format!("{}:{}", json::string(key), json::string(value)))
+		.collect();
+
+	let canonical = match group.canonical() {
+		Some(file) => json::string(&file.to_string_lossy()),
+		None => String::from("null"),
+	};
+
+	format!(
+		"{{\"hash\":{},\"files\":{},\"roots\":[{}],\"baseline\":{},\"queried\":{},\"canonical\":{},\"tags\":{{{}}}}}",
+		json::string(&group.hash),
+		paths(&group.files),
+		roots.join(","),
+		paths(&group.baseline),
+		paths(&group.queried),
+		canonical,
+		tags.join(","),
+	)
+}
+
+/// Returns the given paths as a JSON array of strings.
+fn paths<P: AsRef<Path>>(files: &[P]) -> String {
+	let files: Vec<String> = files
+		.iter()
+		.map(|f| json::string(&f.as_ref().to_string_lossy()))
+		.collect();
+
+	format!("[{}]", files.join(","))
+}
@@ -0,0 +1,256 @@
+use crate::utilities::file_id;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Where a range of storage used by a file is.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum Storage {
+	/// Within the blocks of the device with the given ID, for files whose
+	/// physical extents can be read.
+	Device(u64),
+
+	/// Within the file with the given device and inode numbers, for files
+	/// whose extents can't be read.
+	Inode(u64, u64),
+
+	/// Within the file at the given path, on platforms where files can't be
+	/// identified otherwise.
+	Path(PathBuf),
+}
+
+/// Ranges of storage, as start and end offsets within each storage area.
+#[derive(Debug, Default)]
+pub(crate) struct Spans {
+	/// The ranges within each storage area, which may overlap.
+	ranges: BTreeMap<Storage, Vec<(u64, u64)>>,
+}
+
+impl Spans {
+	/// Creates a new, empty `Spans`.
+	pub(crate) fn new() -> Spans {
+		Spans::default()
+	}
+
+	/// Adds the storage used by a file.
+	///
+	/// On Linux, this is the file's physical extents, so files sharing
+	/// extents, such as reflinked copies, are found to share storage.  Files
+	/// whose extents can't be read, and files on other platforms, are
+	/// treated as using their allocated size on their own, so only hard
+	/// links to the same file share storage.
+	///
+	/// # Errors
+	///
+	/// Returns an error if the file's metadata can't be read.
+	pub(crate) fn add_file(&mut self, file: &Path) -> io::Result<()> {
+		let metadata = fs::metadata(file)?;
+
+		if let Some((device, _)) = file_id(&metadata) {
+			if let Some(extents) = physical_extents(file) {
+				for (start, len) in extents {
+					self.add(Storage::Device(device), start, start.saturating_add(len));
+				}
+
+				return Ok(());
+			}
+		}
+
+		let storage = match file_id(&metadata) {
+			Some((device, inode)) => Storage::Inode(device, inode),
+			None => Storage::Path(file.to_path_buf()),
+		};
+		self.add(storage, 0, allocated_size(&metadata));
+
+		Ok(())
+	}
+
+	/// Adds all of the ranges in `other`.
+	pub(crate) fn extend(&mut self, other: Spans) {
+		for (storage, mut ranges) in other.ranges {
+			self.ranges.entry(storage).or_default().append(&mut ranges);
+		}
+	}
+
+	/// Returns the total length of the ranges that aren't in `other`, with
+	/// ranges that overlap each other counted once.
+	pub(crate) fn len_excluding(&self, other: &Spans) -> u64 {
+		let mut len = 0;
+
+		for (storage, ranges) in &self.ranges {
+			let excluded = other
+				.ranges
+				.get(storage)
+				.map_or(vec![], |r| merge(r.clone()));
+
+			for range in merge(ranges.clone()) {
+				len += range.1 - range.0 - overlap(range, &excluded);
+			}
+		}
+
+		len
+	}
+
+	/// Returns whether these spans and `other` cover exactly the same
+	/// storage, which isn't the case if either is empty.
+	pub(crate) fn same_storage(&self, other: &Spans) -> bool {
+		!self.ranges.is_empty()
+			&& !other.ranges.is_empty()
+			&& self.len_excluding(other) == 0
+			&& other.len_excluding(self) == 0
+	}
+
+	/// Adds a range within a storage area.
+	fn add(&mut self, storage: Storage, start: u64, end: u64) {
+		if start < end {
+			self.ranges.entry(storage).or_default().push((start, end));
+		}
+	}
+}
+
+/// Returns whether two files use exactly the same storage, such as hard
+/// links to the same file or, on Linux, copies that share all of their
+/// extents.  Files with no storage, or whose metadata can't be read, don't
+/// share storage.
+pub(crate) fn shares_all_storage(file: &Path, other: &Path) -> bool {
+	let mut spans = Spans::new();
+	let mut other_spans = Spans::new();
+
+	spans.add_file(file).is_ok()
+		&& other_spans.add_file(other).is_ok()
+		&& spans.same_storage(&other_spans)
+}
+
+/// Returns the given ranges sorted, with overlapping and adjacent ranges
+/// merged.
+fn merge(mut ranges: Vec<(u64, u64)>) -> Vec<(u64, u64)> {
+	ranges.sort_unstable();
+	let mut merged: Vec<(u64, u64)> = vec![];
+
+	for (start, end) in ranges {
+		match merged.last_mut() {
+			Some(last) if start <= last.1 => last.1 = last.1.max(end),
+			_ => merged.push((start, end)),
+		};
+	}
+
+	merged
+}
+
+/// Returns the length of a range that overlaps the given merged ranges.
+fn overlap(range: (u64, u64), ranges: &[(u64, u64)]) -> u64 {
+	ranges
+		.iter()
+		.map(|(start, end)| range.1.min(*end).saturating_sub(range.0.max(*start)))
+		.sum()
+}
+
+/// Returns the space allocated to a file, which may differ from its length
+/// for sparse files and small files.
+#[cfg(unix)]
+fn allocated_size(metadata: &fs::Metadata) -> u64 {
+	use std::os::unix::fs::MetadataExt;
+
+	metadata.blocks() * 512
+}
+
+/// Returns the space allocated to a file, which is taken to be its length
+/// on this platform.
+#[cfg(not(unix))]
+fn allocated_size(metadata: &fs::Metadata) -> u64 {
+	metadata.len()
+}
+
+/// The `FS_IOC_FIEMAP` ioctl request number.
+#[cfg(target_os = "linux")]
+const FS_IOC_FIEMAP: u64 = 0xC020660B;
+
+/// The number of extents requested from `FS_IOC_FIEMAP` at a time.
+#[cfg(target_os = "linux")]
+const EXTENT_BATCH: usize = 64;
+
+/// Extent flags meaning the extent's physical location isn't known or
+/// isn't its own: `FIEMAP_EXTENT_UNKNOWN`, `FIEMAP_EXTENT_DELALLOC`,
+/// `FIEMAP_EXTENT_NOT_ALIGNED` and `FIEMAP_EXTENT_DATA_INLINE`.
+#[cfg(target_os = "linux")]
+const UNLOCATED_EXTENT: u32 = 0x2 | 0x4 | 0x100 | 0x200;
+
+/// The `FIEMAP_EXTENT_LAST` flag, marking a file's last extent.
+#[cfg(target_os = "linux")]
+const LAST_EXTENT: u32 = 0x1;
+
+/// A `struct fiemap` request with space for a batch of extents.
+#[cfg(target_os = "linux")]
+#[repr(C)]
+struct Fiemap {
+	start: u64,
+	length: u64,
+	flags: u32,
+	mapped_extents: u32,
+	extent_count: u32,
+	reserved: u32,
+	extents: [FiemapExtent; EXTENT_BATCH],
+}
+
+/// A `struct fiemap_extent`.
+#[cfg(target_os = "linux")]
+#[repr(C)]
+#[derive(Clone, Copy, Default)]
+struct FiemapExtent {
+	logical: u64,
+	physical: u64,
+	length: u64,
+	reserved64: [u64; 2],
+	flags: u32,
+	reserved: [u32; 3],
+}
+
+/// Returns the physical offsets and lengths of a file's extents, using the
+/// `FS_IOC_FIEMAP` ioctl, or `None` if they can't all be read.
+#[cfg(target_os = "linux")]
+fn physical_extents(file: &Path) -> Option<Vec<(u64, u64)>> {
+	use std::os::unix::io::AsRawFd;
+
+	let handle = fs::File::open(file).ok()?;
+	let mut extents = vec![];
+	let mut start = 0;
+
+	loop {
+		let mut request = Fiemap {
+			start,
+			length: u64::MAX - start,
+			flags: 0,
+			mapped_extents: 0,
+			extent_count: EXTENT_BATCH as u32,
+			reserved: 0,
+			extents: [FiemapExtent::default(); EXTENT_BATCH],
+		};
+		let result = unsafe { libc::ioctl(handle.as_raw_fd(), FS_IOC_FIEMAP as _, &mut request) };
+
+		if result == -1 {
+			return None;
+		}
+
+		let mapped = &request.extents[..(request.mapped_extents as usize).min(EXTENT_BATCH)];
+
+		for extent in mapped {
+			if extent.flags & UNLOCATED_EXTENT != 0 {
+				return None;
+			}
+
+			extents.push((extent.physical, extent.length));
+		}
+
+		match mapped.last() {
+			Some(last) if last.flags & LAST_EXTENT == 0 => start = last.logical + last.length,
+			_ => return Some(extents),
+		};
+	}
+}
+
+/// Extents can't be read on this platform.
+#[cfg(not(target_os = "linux"))]
+fn physical_extents(_file: &Path) -> Option<Vec<(u64, u64)>> {
+	None
+}
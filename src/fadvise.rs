@@ -0,0 +1,49 @@
+use std::fs::File;
+use std::io;
+use std::io::Read;
+use std::path::Path;
+
+/// Returns a file's BLAKE3 hash, advising the kernel that the file will be
+/// read sequentially and that its pages won't be needed once it has been
+/// read, so hashing large files doesn't evict other data from the page
+/// cache.
+pub(crate) fn blake3_uncached(path: &Path) -> io::Result<String> {
+	let mut file = File::open(path)?;
+	advise(&file, Advice::Sequential);
+	let mut bytes = vec![];
+	let read = file.read_to_end(&mut bytes);
+	advise(&file, Advice::DontNeed);
+	read?;
+
+	Ok(format!("{}", blake3::hash(&bytes)))
+}
+
+/// Advice about how a file's data will be used.
+#[derive(Clone, Copy)]
+enum Advice {
+	/// The file will be read from start to end.
+	Sequential,
+
+	/// The file's data won't be needed again soon.
+	DontNeed,
+}
+
+/// Gives advice about a whole file with `posix_fadvise()`.  The advice is
+/// only a hint, so any error is ignored.
+#[cfg(target_os = "linux")]
+fn advise(file: &File, advice: Advice) {
+	use std::os::unix::io::AsRawFd;
+
+	let advice = match advice {
+		Advice::Sequential => libc::POSIX_FADV_SEQUENTIAL,
+		Advice::DontNeed => libc::POSIX_FADV_DONTNEED,
+	};
+
+	unsafe {
+		libc::posix_fadvise(file.as_raw_fd(), 0, 0, advice);
+	}
+}
+
+/// Advice isn't given on this platform.
+#[cfg(not(target_os = "linux"))]
+fn advise(_file: &File, _advice: Advice) {}
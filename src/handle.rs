@@ -0,0 +1,80 @@
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+
+/// A handle for pausing and resuming checks from another thread.
+///
+/// Checks using a `ScanHandle`, given with `DupOptions::set_scan_handle()`,
+/// stop before reading each file or directory while the handle is paused,
+/// and carry on where they left off once it's resumed.  A paused check holds
+/// on to everything it has found so far, so no progress is lost.  Clones of
+/// a `ScanHandle` control the same checks.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::PathBuf;
+/// use std::thread;
+///
+/// let handle = dupcheck::ScanHandle::new();
+/// let mut options = dupcheck::DupOptions::new();
+/// options.set_scan_handle(Some(handle.clone()));
+///
+/// let check = thread::spawn(move || {
+///     let dirs = vec![PathBuf::from("foo")];
+///     let mut dup_result = dupcheck::DupResults::with_options(options);
+///     let _ = dup_result.within(&dirs);
+/// });
+///
+/// // Stop reading files while a backup runs.
+/// handle.pause();
+/// handle.resume();
+/// check.join().unwrap();
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct ScanHandle {
+	/// Whether checks are paused, and the condition variable that paused
+	/// checks wait on.
+	paused: Arc<(Mutex<bool>, Condvar)>,
+}
+
+impl ScanHandle {
+	/// Creates a new `ScanHandle` that isn't paused.
+	pub fn new() -> ScanHandle {
+		ScanHandle::default()
+	}
+
+	/// Pauses checks using this handle before they next read a file or
+	/// directory.
+	pub fn pause(&self) {
+		*self.lock() = true;
+	}
+
+	/// Resumes any paused checks using this handle.
+	pub fn resume(&self) {
+		*self.lock() = false;
+		self.paused.1.notify_all();
+	}
+
+	/// Returns whether checks using this handle are paused.
+	pub fn is_paused(&self) -> bool {
+		*self.lock()
+	}
+
+	/// Blocks the current thread until this handle isn't paused.
+	pub(crate) fn wait_while_paused(&self) {
+		let mut paused = self.lock();
+
+		while *paused {
+			paused = self
+				.paused
+				.1
+				.wait(paused)
+				.unwrap_or_else(|e| e.into_inner());
+		}
+	}
+
+	/// Locks whether checks are paused, recovering it if another thread
+	/// panicked while holding the lock.
+	fn lock(&self) -> MutexGuard<'_, bool> {
+		self.paused.0.lock().unwrap_or_else(|e| e.into_inner())
+	}
+}
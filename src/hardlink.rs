@@ -0,0 +1,35 @@
+use crate::utilities::temp_path;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Replaces `link` with a hard link to `original`.
+///
+/// The hard link is made under a temporary name in the same directory as
+/// `link` and then renamed over it, so `link` is never missing, even if the
+/// process is interrupted part way through.
+///
+/// # Errors
+///
+/// Returns an error if creating the temporary link fails, in which case
+/// `link` is left unchanged.  Returns an error if renaming the temporary link
+/// over `link` fails, in which case `link` is left unchanged and the
+/// temporary link is removed; if it can't be removed, the error gives its
+/// path so it can be removed by hand.
+pub(crate) fn hardlink(original: &Path, link: &Path) -> io::Result<()> {
+	let temp = temp_path(link);
+	fs::hard_link(original, &temp)?;
+
+	if let Err(e) = fs::rename(&temp, link) {
+		if fs::remove_file(&temp).is_err() {
+			return Err(io::Error::new(
+				e.kind(),
+				format!("{}; temporary link {} left behind", e, temp.display()),
+			));
+		}
+
+		return Err(e);
+	}
+
+	Ok(())
+}
@@ -0,0 +1,160 @@
+use crate::normalize::{normalized_hash, normalized_reader_hash, Normalization};
+use std::fs::File;
+use std::io;
+use std::io::Read;
+use std::path::Path;
+
+/// The default number of bytes read from a file at a time when hashing or
+/// comparing it, as given by `DupOptions::buffer_size()`.  This matches the
+/// kernel's default readahead on Unix, and is smaller on other platforms,
+/// where larger reads from network shares can be slower.
+#[cfg(unix)]
+pub const DEFAULT_BUFFER_SIZE: usize = 128 * 1024;
+
+/// The default number of bytes read from a file at a time when hashing or
+/// comparing it, as given by `DupOptions::buffer_size()`.  This matches the
+/// kernel's default readahead on Unix, and is smaller on other platforms,
+/// where larger reads from network shares can be slower.
+#[cfg(not(unix))]
+pub const DEFAULT_BUFFER_SIZE: usize = 64 * 1024;
+
+/// Returns the BLAKE3 hash of a file's contents, as a hexadecimal string.
+///
+/// This is the hash that identifies duplicate file groups found by the
+/// `Stage::FullHash` comparison stage, and that's written to manifests, so
+/// it can be used to look up a file in the results or in a manifest.  The
+/// file is read in chunks rather than all at once.
+///
+/// # Errors
+///
+/// Returns an error if the file can't be read.
+///
+/// # Examples
+///
+/// ```
+/// match dupcheck::hash_file("foo.txt") {
+///     Ok(hash) => println!("{}", hash),
+///     Err(hash_error) => {
+///         // Error handling
+///     }
+/// }
+/// ```
+pub fn hash_file<P: AsRef<Path>>(path: P) -> io::Result<String> {
+	hash_reader(File::open(path)?)
+}
+
+/// Returns the BLAKE3 hash of a file's contents, reading it into the given
+/// buffer a buffer's length at a time.
+pub(crate) fn hash_file_chunked(path: &Path, buffer: &mut [u8]) -> io::Result<String> {
+	let mut file = File::open(path)?;
+	let mut hasher = blake3::Hasher::new();
+
+	loop {
+		match file.read(buffer) {
+			Ok(0) => break,
+			Ok(read) => hasher.update(&buffer[..read]),
+			Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+			Err(e) => return Err(e),
+		};
+	}
+
+	Ok(format!("{}", hasher.finalize()))
+}
+
+/// Returns the BLAKE3 hash of a file's contents after applying the given
+/// normalizations in order, as a hexadecimal string.
+///
+/// This is the hash that identifies duplicate file groups found by the
+/// `Stage::NormalizedHash` comparison stage with the same normalizations,
+/// as given by `DupGroup::normalizations()`.  If no normalizations are
+/// given, this is the same as `hash_file()`.  Normalizing a file needs its
+/// whole contents, so files of up to 256 MiB are read all at once, and
+/// larger files are hashed without being normalized.
+///
+/// # Errors
+///
+/// Returns an error if the file can't be read.
+///
+/// # Examples
+///
+/// ```
+/// use dupcheck::Normalization;
+///
+/// let normalizations = [Normalization::LineEndings];
+///
+/// if let Ok(hash) = dupcheck::hash_file_normalized("foo.txt", &normalizations) {
+///     println!("{}", hash);
+/// }
+/// ```
+pub fn hash_file_normalized<P: AsRef<Path>>(
+	path: P,
+	normalizations: &[Normalization],
+) -> io::Result<String> {
+	match normalizations.is_empty() {
+		true => hash_file(path),
+		false => normalized_hash(path.as_ref(), normalizations),
+	}
+}
+
+/// Returns the BLAKE3 hash of everything read from a reader, as a
+/// hexadecimal string.
+///
+/// This is the same hash that `hash_file()` gives for a file with the same
+/// contents, so data from other sources, such as in-memory buffers, network
+/// streams or archive members, can be matched against duplicate file groups
+/// and manifests.  The data is read in chunks rather than all at once.
+///
+/// # Errors
+///
+/// Returns an error if reading fails.
+///
+/// # Examples
+///
+/// ```
+/// let data: &[u8] = b"Hello, world!";
+///
+/// if let Ok(hash) = dupcheck::hash_reader(data) {
+///     println!("{}", hash);
+/// }
+/// ```
+pub fn hash_reader<R: Read>(mut reader: R) -> io::Result<String> {
+	let mut hasher = blake3::Hasher::new();
+	io::copy(&mut reader, &mut hasher)?;
+
+	Ok(format!("{}", hasher.finalize()))
+}
+
+/// Returns the BLAKE3 hash of everything read from a reader after applying
+/// the given normalizations in order, as a hexadecimal string.
+///
+/// This is the same hash that `hash_file_normalized()` gives for a file
+/// with the same contents.  Normalizing the data needs all of it, so up to
+/// 256 MiB is read all at once unless no normalizations are given, and
+/// longer data is hashed without being normalized.
+///
+/// # Errors
+///
+/// Returns an error if reading fails.
+///
+/// # Examples
+///
+/// ```
+/// use dupcheck::Normalization;
+///
+/// let data: &[u8] = b"Hello,\r\nworld!\r\n";
+/// let normalizations = [Normalization::LineEndings];
+///
+/// if let Ok(hash) = dupcheck::hash_reader_normalized(data, &normalizations) {
+///     println!("{}", hash);
+/// }
+/// ```
+pub fn hash_reader_normalized<R: Read>(
+	reader: R,
+	normalizations: &[Normalization],
+) -> io::Result<String> {
+	if normalizations.is_empty() {
+		return hash_reader(reader);
+	}
+
+	normalized_reader_hash(reader, normalizations)
+}
@@ -0,0 +1,166 @@
+use crate::options::DupOptions;
+use crate::utilities::temp_path;
+use std::fs;
+use std::fs::File;
+use std::io;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+/// A file listing the BLAKE3 hashes of duplicate groups that have been
+/// reviewed and dismissed, so they can be left out of later checks' results.
+///
+/// The file has one hash per line, and lines starting with `#` are ignored,
+/// so it can also be given to `--ignore-hashes`.  Changes are only written to
+/// the file by `save()`.
+///
+/// Only groups found with `Stage::FullHash` or `Stage::NormalizedHash` have
+/// hashes of their files' contents as their identifiers.  Dismissing other
+/// groups by their identifiers won't leave them out of later results.
+#[derive(Clone, Debug)]
+pub struct IgnoreStore {
+	/// The path to the file.
+	path: PathBuf,
+
+	/// The dismissed hashes, in lowercase.
+	hashes: Vec<String>,
+}
+
+impl IgnoreStore {
+	/// Opens the store in the file at the given path, which doesn't need to
+	/// exist yet.
+	///
+	/// # Errors
+	///
+	/// Returns an error if the file exists but can't be read.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use std::path::PathBuf;
+	///
+	/// match dupcheck::IgnoreStore::open("ignored") {
+	///     Ok(store) => {
+	///         let mut options = dupcheck::DupOptions::new();
+	///         store.apply(&mut options);
+	///
+	///         let dirs = vec![PathBuf::from("foo")];
+	///         let mut dup_result = dupcheck::DupResults::with_options(options);
+	///
+	///         if let Err(dup_error) = dup_result.within(&dirs) {
+	///             // Error handling
+	///         }
+	///     }
+	///     Err(store_error) => {
+	///         // Error handling
+	///     }
+	/// }
+	/// ```
+	pub fn open<P: AsRef<Path>>(path: P) -> io::Result<IgnoreStore> {
+		let path = path.as_ref().to_path_buf();
+		let mut hashes = vec![];
+
+		match File::open(&path) {
+			Ok(file) => {
+				for line in BufReader::new(file).lines() {
+					let line = line?;
+					let hash = line.trim();
+
+					if !hash.is_empty() && !hash.starts_with('#') {
+						hashes.push(hash.to_ascii_lowercase());
+					}
+				}
+			}
+			Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+			Err(e) => return Err(e),
+		};
+
+		Ok(IgnoreStore { path, hashes })
+	}
+
+	/// Returns the path to the file.
+	pub fn path(&self) -> &Path {
+		&self.path
+	}
+
+	/// Returns a reference to the dismissed hashes, in lowercase.
+	pub fn hashes(&self) -> &[String] {
+		&self.hashes
+	}
+
+	/// Returns whether the group with the given hash has been dismissed.
+	/// Hashes are compared ignoring case.
+	pub fn is_dismissed(&self, hash: &str) -> bool {
+		self.hashes.contains(&hash.to_ascii_lowercase())
+	}
+
+	/// Dismisses the group with the given hash, returning whether it wasn't
+	/// already dismissed.
+	pub fn dismiss(&mut self, hash: &str) -> bool {
+		if self.is_dismissed(hash) {
+			return false;
+		}
+
+		self.hashes.push(hash.to_ascii_lowercase());
+		true
+	}
+
+	/// Restores a dismissed group with the given hash to later results,
+	/// returning whether it had been dismissed.
+	pub fn restore(&mut self, hash: &str) -> bool {
+		let hash = hash.to_ascii_lowercase();
+		let len = self.hashes.len();
+		self.hashes.retain(|h| *h != hash);
+
+		self.hashes.len() != len
+	}
+
+	/// Adds the dismissed hashes to the options' ignored hashes, so checks
+	/// with the options leave their groups out of the results.
+	pub fn apply(&self, options: &mut DupOptions) {
+		let mut hashes = options.ignored_hashes().to_vec();
+
+		for hash in &self.hashes {
+			if !hashes.contains(hash) {
+				hashes.push(hash.clone());
+			}
+		}
+
+		options.set_ignored_hashes(hashes);
+	}
+
+	/// Writes the store to its file, creating the file's directory if
+	/// needed.  The file is replaced in a single step, so it's left
+	/// unchanged if writing fails.
+	///
+	/// # Errors
+	///
+	/// Returns an error if the file or its directory can't be written.
+	pub fn save(&self) -> io::Result<()> {
+		if let Some(dir) = self.path.parent().filter(|d| !d.as_os_str().is_empty()) {
+			fs::create_dir_all(dir)?;
+		}
+
+		let temp = temp_path(&self.path);
+		let written = File::create(&temp).and_then(|file| {
+			let mut writer = BufWriter::new(file);
+			writeln!(
+				writer,
+				"# Hashes of duplicate groups dismissed with dupcheck"
+			)?;
+
+			for hash in &self.hashes {
+				writeln!(writer, "{}", hash)?;
+			}
+
+			writer.flush()
+		});
+
+		match written.and_then(|_| fs::rename(&temp, &self.path)) {
+			Ok(()) => Ok(()),
+			Err(e) => {
+				let _ = fs::remove_file(&temp);
+				Err(e)
+			}
+		}
+	}
+}
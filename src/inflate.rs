@@ -0,0 +1,358 @@
+/// The base lengths for length codes 257 to 285.
+const LENGTH_BASE: [u16; 29] = [
+	3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+	163, 195, 227, 258,
+];
+
+/// The number of extra bits for length codes 257 to 285.
+const LENGTH_EXTRA: [u8; 29] = [
+	0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+
+/// The base distances for distance codes 0 to 29.
+const DIST_BASE: [u16; 30] = [
+	1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537, 2049,
+	3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+
+/// The number of extra bits for distance codes 0 to 29.
+const DIST_EXTRA: [u8; 30] = [
+	0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13, 13,
+];
+
+/// The order in which code length code lengths are given in dynamic blocks.
+const CODE_LENGTH_ORDER: [usize; 19] = [
+	16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+
+/// Decompresses raw DEFLATE data, as described by RFC 1951, returning the
+/// decompressed bytes and the number of compressed bytes read, or `None` if
+/// the data is invalid or decompresses to more than `limit` bytes.
+pub(crate) fn inflate(data: &[u8], limit: usize) -> Option<(Vec<u8>, usize)> {
+	let mut input = BitReader::new(data);
+	let mut output = vec![];
+
+	loop {
+		let last = input.bits(1)? == 1;
+
+		match input.bits(2)? {
+			0 => stored_block(&mut input, &mut output, limit)?,
+			1 => {
+				let (lengths, distances) = fixed_codes();
+				codes_block(&mut input, &mut output, limit, &lengths, &distances)?;
+			}
+			2 => {
+				let (lengths, distances) = dynamic_codes(&mut input)?;
+				codes_block(&mut input, &mut output, limit, &lengths, &distances)?;
+			}
+			_ => return None,
+		};
+
+		if last {
+			return Some((output, input.bytes_read()));
+		}
+	}
+}
+
+/// Reads bits from a byte slice, least significant bit first.
+struct BitReader<'a> {
+	/// The data being read.
+	data: &'a [u8],
+
+	/// The index of the next byte to load.
+	pos: usize,
+
+	/// Loaded bits that haven't been read.
+	buf: u32,
+
+	/// The number of loaded bits that haven't been read.
+	count: u32,
+}
+
+impl<'a> BitReader<'a> {
+	/// Creates a new `BitReader` reading from the start of the given data.
+	fn new(data: &'a [u8]) -> BitReader<'a> {
+		BitReader {
+			data,
+			pos: 0,
+			buf: 0,
+			count: 0,
+		}
+	}
+
+	/// Returns the next `n` bits, up to 16, as a number.
+	fn bits(&mut self, n: u32) -> Option<u32> {
+		while self.count < n {
+			self.buf |= (*self.data.get(self.pos)? as u32) << self.count;
+			self.pos += 1;
+			self.count += 8;
+		}
+
+		let value = self.buf & ((1 << n) - 1);
+		self.buf >>= n;
+		self.count -= n;
+
+		Some(value)
+	}
+
+	/// Discards any bits left in the current byte.
+	fn align(&mut self) {
+		self.buf = 0;
+		self.count = 0;
+	}
+
+	/// Returns the number of whole bytes read.
+	fn bytes_read(&self) -> usize {
+		self.pos - (self.count / 8) as usize
+	}
+}
+
+/// A canonical Huffman code, stored as the number of codes of each length
+/// and the symbols in order of their codes.
+struct Huffman {
+	/// The number of codes of each length.
+	counts: [u16; 16],
+
+	/// The symbols, ordered by their codes.
+	symbols: Vec<u16>,
+}
+
+impl Huffman {
+	/// Builds a code from the code length of each symbol, where 0 means the
+	/// symbol is unused.
+	fn new(lengths: &[u8]) -> Option<Huffman> {
+		let mut counts = [0; 16];
+
+		for length in lengths {
+			counts[*length as usize] += 1;
+		}
+
+		counts[0] = 0;
+		let mut offsets = [0; 16];
+
+		for len in 1..15 {
+			offsets[len + 1] = offsets[len] + counts[len];
+		}
+
+		let mut symbols = vec![0; lengths.len()];
+
+		for (symbol, length) in lengths.iter().enumerate() {
+			if *length != 0 {
+				let offset = &mut offsets[*length as usize];
+				*symbols.get_mut(*offset as usize)? = symbol as u16;
+				*offset += 1;
+			}
+		}
+
+		Some(Huffman { counts, symbols })
+	}
+
+	/// Reads and returns the next symbol.
+	fn decode(&self, input: &mut BitReader) -> Option<u16> {
+		let mut code: i32 = 0;
+		let mut first: i32 = 0;
+		let mut index: i32 = 0;
+
+		for len in 1..16 {
+			code |= input.bits(1)? as i32;
+			let count = self.counts[len] as i32;
+
+			if code - first < count {
+				return self.symbols.get((index + code - first) as usize).copied();
+			}
+
+			index += count;
+			first += count;
+			first <<= 1;
+			code <<= 1;
+		}
+
+		None
+	}
+}
+
+/// Copies an uncompressed block to the output, as long as the output stays
+/// within `limit` bytes.
+fn stored_block(input: &mut BitReader, output: &mut Vec<u8>, limit: usize) -> Option<()> {
+	input.align();
+	let start = input.pos;
+	let header = input.data.get(start..start + 4)?;
+	let len = u16::from_le_bytes([header[0], header[1]]);
+	let nlen = u16::from_le_bytes([header[2], header[3]]);
+
+	if len != !nlen || output.len() + len as usize > limit {
+		return None;
+	}
+
+	let end = start + 4 + len as usize;
+	output.extend_from_slice(input.data.get(start + 4..end)?);
+	input.pos = end;
+
+	Some(())
+}
+
+/// Returns the fixed literal/length and distance codes.
+fn fixed_codes() -> (Huffman, Huffman) {
+	let mut lengths = [0; 288];
+	lengths[..144].fill(8);
+	lengths[144..256].fill(9);
+	lengths[256..280].fill(7);
+	lengths[280..].fill(8);
+
+	// These code lengths are always valid.
+	(
+		Huffman::new(&lengths).unwrap(),
+		Huffman::new(&[5; 30]).unwrap(),
+	)
+}
+
+/// Reads the literal/length and distance codes at the start of a dynamic
+/// block.
+fn dynamic_codes(input: &mut BitReader) -> Option<(Huffman, Huffman)> {
+	let literal_count = input.bits(5)? as usize + 257;
+	let distance_count = input.bits(5)? as usize + 1;
+	let code_length_count = input.bits(4)? as usize + 4;
+
+	if literal_count > 286 || distance_count > 30 {
+		return None;
+	}
+
+	let mut code_lengths = [0; 19];
+
+	for i in CODE_LENGTH_ORDER.iter().take(code_length_count) {
+		code_lengths[*i] = input.bits(3)? as u8;
+	}
+
+	let code_length_code = Huffman::new(&code_lengths)?;
+	let mut lengths = vec![];
+
+	while lengths.len() < literal_count + distance_count {
+		let symbol = code_length_code.decode(input)?;
+		let (length, repeat) = match symbol {
+			0..=15 => (symbol as u8, 1),
+			16 => (*lengths.last()?, 3 + input.bits(2)?),
+			17 => (0, 3 + input.bits(3)?),
+			18 => (0, 11 + input.bits(7)?),
+			_ => return None,
+		};
+
+		for _ in 0..repeat {
+			lengths.push(length);
+		}
+	}
+
+	if lengths.len() != literal_count + distance_count || lengths[256] == 0 {
+		return None;
+	}
+
+	Some((
+		Huffman::new(&lengths[..literal_count])?,
+		Huffman::new(&lengths[literal_count..])?,
+	))
+}
+
+/// Decodes a block compressed with the given codes, as long as the output
+/// stays within `limit` bytes.
+fn codes_block(
+	input: &mut BitReader,
+	output: &mut Vec<u8>,
+	limit: usize,
+	lengths: &Huffman,
+	distances: &Huffman,
+) -> Option<()> {
+	loop {
+		let symbol = lengths.decode(input)? as usize;
+
+		if symbol < 256 {
+			if output.len() >= limit {
+				return None;
+			}
+
+			output.push(symbol as u8);
+			continue;
+		}
+
+		if symbol == 256 {
+			return Some(());
+		}
+
+		let i = symbol - 257;
+		let length = *LENGTH_BASE.get(i)? as usize + input.bits(*LENGTH_EXTRA.get(i)? as u32)? as usize;
+		let d = distances.decode(input)? as usize;
+		let distance = *DIST_BASE.get(d)? as usize + input.bits(*DIST_EXTRA.get(d)? as u32)? as usize;
+
+		if distance > output.len() || output.len() + length > limit {
+			return None;
+		}
+
+		let start = output.len() - distance;
+
+		for j in 0..length {
+			output.push(output[start + j]);
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::inflate;
+
+	/// "hello hello hello" compressed with fixed codes.
+	const FIXED: [u8; 10] = [203, 72, 205, 201, 201, 87, 200, 64, 144, 0];
+
+	/// Two pangrams compressed with dynamic codes.
+	const DYNAMIC: [u8; 79] = [
+		181, 203, 199, 1, 128, 32, 16, 5, 209, 86, 126, 5, 212, 226, 193, 6, 64, 73, 6, 86, 178, 80, 189,
+		219, 132, 231, 121, 179, 58, 141, 88, 253, 118, 66, 37, 234, 1, 134, 94, 28, 245, 126, 50, 168,
+		233, 132, 194, 249, 146, 115, 96, 39, 43, 176, 254, 134, 23, 201, 238, 30, 80, 140, 186, 47, 14,
+		198, 55, 205, 105, 234, 128, 203, 199, 74, 137, 95, 155, 197, 7,
+	];
+
+	#[test]
+	fn stored_block() {
+		let data = [1, 5, 0, 0xFA, 0xFF, b'h', b'e', b'l', b'l', b'o', 0xAA];
+		assert_eq!(inflate(&data, 100), Some((b"hello".to_vec(), 10)));
+	}
+
+	#[test]
+	fn stored_block_with_bad_nlen() {
+		let data = [1, 5, 0, 0xFB, 0xFF, b'h', b'e', b'l', b'l', b'o'];
+		assert_eq!(inflate(&data, 100), None);
+	}
+
+	#[test]
+	fn fixed_block() {
+		let expected = b"hello hello hello".to_vec();
+		assert_eq!(inflate(&FIXED, 100), Some((expected, FIXED.len())));
+	}
+
+	#[test]
+	fn dynamic_block() {
+		let mut expected = b"The quick brown fox jumps over the lazy dog. ".repeat(3);
+		expected.extend_from_slice(b"Pack my box with five dozen liquor jugs.");
+		assert_eq!(inflate(&DYNAMIC, 1000), Some((expected, DYNAMIC.len())));
+	}
+
+	#[test]
+	fn truncated_stream() {
+		assert_eq!(inflate(&FIXED[..FIXED.len() - 2], 100), None);
+		assert_eq!(inflate(&DYNAMIC[..40], 1000), None);
+		assert_eq!(inflate(&[], 100), None);
+	}
+
+	#[test]
+	fn output_limit() {
+		assert!(inflate(&FIXED, 17).is_some());
+		assert_eq!(inflate(&FIXED, 16), None);
+		assert_eq!(inflate(&FIXED, 8), None);
+
+		let stored = [1, 5, 0, 0xFA, 0xFF, b'h', b'e', b'l', b'l', b'o'];
+		assert_eq!(inflate(&stored, 4), None);
+	}
+
+	#[test]
+	fn distance_before_start() {
+		// A fixed block whose first symbol is a length of 3 at distance 1.
+		assert_eq!(inflate(&[3, 2], 100), None);
+	}
+}
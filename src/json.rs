@@ -0,0 +1,31 @@
+use std::fmt::Write;
+
+/// Returns a string as a quoted JSON string, with any characters that need
+/// to be escaped escaped.
+pub(crate) fn string(s: &str) -> String {
+	let mut quoted = String::with_capacity(s.len() + 2);
+	quoted.push('"');
+
+	for c in s.chars() {
+		match c {
+			'"' => quoted.push_str("\\\""),
+			'\\' => quoted.push_str("\\\\"),
+			'\n' => quoted.push_str("\\n"),
+			'\r' => quoted.push_str("\\r"),
+			'\t' => quoted.push_str("\\t"),
+			c if (c as u32) < 0x20 => {
+				let _ = write!(quoted, "\\u{:04x}", c as u32);
+			}
+			c => quoted.push(c),
+		};
+	}
+
+	quoted.push('"');
+	quoted
+}
+
+/// Returns a JSON object with an error message, used as a response to failed
+/// requests.
+pub(crate) fn error(message: &str) -> String {
+	format!("{{\"error\":{}}}", string(message))
+}
@@ -0,0 +1,339 @@
+use crate::utilities::absolute;
+use crate::DupGroup;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::SystemTime;
+
+/// Which file in a group of duplicates is kept when acting on the group.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum KeepPolicy {
+	/// Keeps the first file in the group.
+	First,
+
+	/// Keeps the file with the earliest modification time.
+	Oldest,
+
+	/// Keeps the file with the latest modification time.
+	Newest,
+
+	/// Keeps the file with the shortest path.
+	ShortestPath,
+
+	/// Keeps the file with the longest path.
+	LongestPath,
+
+	/// Keeps the file selected by the given rules.
+	Rules(KeepRules),
+}
+
+impl FromStr for KeepPolicy {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<KeepPolicy, String> {
+		match s {
+			"first" => Ok(KeepPolicy::First),
+			"oldest" => Ok(KeepPolicy::Oldest),
+			"newest" => Ok(KeepPolicy::Newest),
+			"shortest" => Ok(KeepPolicy::ShortestPath),
+			"longest" => Ok(KeepPolicy::LongestPath),
+			_ => Err(format!("unknown keep policy {}", s)),
+		}
+	}
+}
+
+impl KeepPolicy {
+	/// Returns the index of the file to keep from the given group's files.
+	///
+	/// # Errors
+	///
+	/// Returns an error if the policy depends on file metadata that can't be
+	/// read.
+	pub(crate) fn select(&self, group: &DupGroup) -> io::Result<usize> {
+		let rule = match self {
+			KeepPolicy::First => return Ok(0),
+			KeepPolicy::Oldest => KeepRule::Oldest,
+			KeepPolicy::Newest => KeepRule::Newest,
+			KeepPolicy::ShortestPath => KeepRule::ShortestPath,
+			KeepPolicy::LongestPath => KeepRule::LongestPath,
+			KeepPolicy::Rules(rules) => return rules.select(group),
+		};
+
+		KeepRules::new(vec![rule]).select(group)
+	}
+}
+
+/// A rule expressing which files are preferred when choosing the file to
+/// keep from a group of duplicates.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum KeepRule {
+	/// Prefers files within the given directory.
+	Under(PathBuf),
+
+	/// Prefers files not within the given directory.
+	NotUnder(PathBuf),
+
+	/// Prefers the file with the shortest path.
+	ShortestPath,
+
+	/// Prefers the file with the longest path.
+	LongestPath,
+
+	/// Prefers the file with the earliest modification time.
+	Oldest,
+
+	/// Prefers the file with the latest modification time.
+	Newest,
+
+	/// Prefers the file with the earliest creation or modification time,
+	/// whichever is earlier, where creation times are available.
+	Earliest,
+
+	/// Prefers files whose names don't look like those given to copies, such
+	/// as `foo (1).txt`, `foo - Copy.txt`, `Copy of foo.txt` or `foo.txt~`.
+	OriginalName,
+}
+
+impl FromStr for KeepRule {
+	type Err = String;
+
+	/// Parses a rule from `under:<dir>`, `not-under:<dir>`, `shortest`,
+	/// `longest`, `oldest`, `newest`, `earliest` or `original-name`.
+	fn from_str(s: &str) -> Result<KeepRule, String> {
+		if let Some(dir) = s.strip_prefix("under:") {
+			return Ok(KeepRule::Under(PathBuf::from(dir)));
+		}
+
+		if let Some(dir) = s.strip_prefix("not-under:") {
+			return Ok(KeepRule::NotUnder(PathBuf::from(dir)));
+		}
+
+		match s {
+			"shortest" => Ok(KeepRule::ShortestPath),
+			"longest" => Ok(KeepRule::LongestPath),
+			"oldest" => Ok(KeepRule::Oldest),
+			"newest" => Ok(KeepRule::Newest),
+			"earliest" => Ok(KeepRule::Earliest),
+			"original-name" => Ok(KeepRule::OriginalName),
+			_ => Err(format!("unknown keep rule {}", s)),
+		}
+	}
+}
+
+/// An ordered list of rules for choosing the file to keep from a group of
+/// duplicates.
+///
+/// Each rule narrows down the files that could be kept to those it prefers,
+/// with any files it can't choose between passed on to the next rule.  If a
+/// rule prefers none of the remaining files, such as a rule preferring files
+/// under a directory that none of them are in, all of them are passed on.
+/// If more than one file remains after all rules, the first is kept.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct KeepRules {
+	/// The rules, in priority order.
+	rules: Vec<KeepRule>,
+}
+
+impl KeepRules {
+	/// Creates a new `KeepRules` from rules in priority order.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use dupcheck::{Action, KeepPolicy, KeepRule, KeepRules};
+	/// use std::path::PathBuf;
+	///
+	/// let rules = KeepRules::new(vec![
+	///     KeepRule::Under(PathBuf::from("/archive")),
+	///     KeepRule::ShortestPath,
+	///     KeepRule::Oldest,
+	/// ]);
+	/// let dirs = vec![PathBuf::from("foo")];
+	/// let mut dup_result = dupcheck::DupResults::new();
+	///
+	/// if let Err(dup_error) = dup_result.within(&dirs) {
+	///     // Error handling
+	/// }
+	///
+	/// let (plans, errors) = dup_result.plan_actions(&KeepPolicy::Rules(rules), Action::Delete);
+	/// ```
+	pub fn new(rules: Vec<KeepRule>) -> KeepRules {
+		KeepRules { rules }
+	}
+
+	/// Returns a reference to the rules, in priority order.
+	pub fn rules(&self) -> &[KeepRule] {
+		&self.rules
+	}
+
+	/// Adds a rule with a lower priority than all existing rules.
+	pub fn add(&mut self, rule: KeepRule) {
+		self.rules.push(rule);
+	}
+
+	/// Returns the index of the file to keep from the given group's files.
+	///
+	/// Modification times are taken from the metadata recorded in the group
+	/// where possible, rather than being read again.
+	///
+	/// # Errors
+	///
+	/// Returns an error if a rule depends on file metadata that can't be
+	/// read.
+	pub(crate) fn select(&self, group: &DupGroup) -> io::Result<usize> {
+		let mut candidates: Vec<usize> = (0..group.files.len()).collect();
+
+		for rule in &self.rules {
+			if candidates.len() < 2 {
+				break;
+			}
+
+			// Each candidate is scored so that lower scores are preferred.
+			let mut scores = vec![];
+
+			for i in &candidates {
+				scores.push(rule.score(group, *i)?);
+			}
+
+			let best = scores.iter().min().copied().unwrap_or_default();
+			candidates = candidates
+				.into_iter()
+				.zip(scores)
+				.filter(|(_, score)| *score == best)
+				.map(|(i, _)| i)
+				.collect();
+		}
+
+		Ok(candidates.first().copied().unwrap_or(0))
+	}
+}
+
+impl KeepRule {
+	/// Returns the score for this rule of the file at the given index in a
+	/// group, where files with lower scores are preferred.
+	fn score(&self, group: &DupGroup, i: usize) -> io::Result<i128> {
+		let file = &group.files[i];
+
+		Ok(match self {
+			KeepRule::Under(dir) => !is_under(file, dir) as i128,
+			KeepRule::NotUnder(dir) => is_under(file, dir) as i128,
+			KeepRule::ShortestPath => file.as_os_str().len() as i128,
+			KeepRule::LongestPath => -(file.as_os_str().len() as i128),
+			KeepRule::Oldest => mtime_nanos(group, i)?,
+			KeepRule::Newest => -mtime_nanos(group, i)?,
+			KeepRule::Earliest => earliest_nanos(file)?,
+			KeepRule::OriginalName => looks_like_copy(file) as i128,
+		})
+	}
+}
+
+/// Returns whether a file is within the given directory.
+fn is_under(file: &Path, dir: &Path) -> bool {
+	absolute(file).starts_with(absolute(dir))
+}
+
+/// Returns whether a file's name looks like one given to a copy of another
+/// file.
+fn looks_like_copy(file: &Path) -> bool {
+	let name = file.file_name().unwrap_or_default().to_string_lossy();
+
+	if name.ends_with('~') {
+		return true;
+	}
+
+	let stem = file
+		.file_stem()
+		.unwrap_or_default()
+		.to_string_lossy()
+		.to_lowercase();
+
+	if stem.starts_with("copy of ") {
+		return true;
+	}
+
+	// Numbered copies, e.g. "foo (1)" or "foo - Copy (2)".  Only small
+	// numbers are counted, so years such as "Report (2023)" aren't.
+	let stem = match stem.strip_suffix(')').and_then(|s| s.rsplit_once('(')) {
+		Some((rest, n)) if (1..=3).contains(&n.len()) && n.chars().all(|c| c.is_ascii_digit()) => {
+			if rest.ends_with(' ') || rest.is_empty() {
+				return true;
+			}
+			rest.trim_end().to_string()
+		}
+		_ => stem,
+	};
+	let stem = stem
+		.trim_end_matches(|c: char| c.is_ascii_digit())
+		.trim_end();
+
+	[" copy", "-copy", "_copy", " - copy"]
+		.iter()
+		.any(|s| stem.ends_with(s))
+}
+
+/// Returns the modification time of the file at the given index in a group
+/// as nanoseconds relative to the Unix epoch, preferring the time recorded
+/// in the group.
+fn mtime_nanos(group: &DupGroup, i: usize) -> io::Result<i128> {
+	match group.metadata_at(i).and_then(|m| m.modified()) {
+		Some(modified) => Ok(nanos(modified)),
+		None => Ok(nanos(group.files[i].metadata()?.modified()?)),
+	}
+}
+
+/// Returns the earlier of a file's creation and modification times as
+/// nanoseconds relative to the Unix epoch.
+fn earliest_nanos(file: &Path) -> io::Result<i128> {
+	let metadata = file.metadata()?;
+	let modified = nanos(metadata.modified()?);
+
+	Ok(match metadata.created() {
+		Ok(created) => modified.min(nanos(created)),
+		Err(_) => modified,
+	})
+}
+
+/// Returns a time as nanoseconds relative to the Unix epoch.
+fn nanos(time: SystemTime) -> i128 {
+	match time.duration_since(SystemTime::UNIX_EPOCH) {
+		Ok(d) => d.as_nanos() as i128,
+		Err(e) => -(e.duration().as_nanos() as i128),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn copies_are_recognised() {
+		for name in [
+			"foo (1).txt",
+			"foo (12).txt",
+			"foo - Copy.txt",
+			"foo - Copy (2).txt",
+			"foo copy.txt",
+			"foo_copy3.txt",
+			"Copy of foo.txt",
+			"foo.txt~",
+			"(1).txt",
+		] {
+			assert!(looks_like_copy(Path::new(name)), "{}", name);
+		}
+	}
+
+	#[test]
+	fn originals_are_recognised() {
+		for name in [
+			"foo.txt",
+			"Blade Runner (1982).mkv",
+			"Report (2023).pdf",
+			"foo (bar).txt",
+			"foo ().txt",
+			"copycat.txt",
+			"foo1.txt",
+		] {
+			assert!(!looks_like_copy(Path::new(name)), "{}", name);
+		}
+	}
+}
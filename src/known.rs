@@ -0,0 +1,82 @@
+use crate::events::ScanEvent;
+use crate::{DupGroup, DupResults};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+impl DupResults {
+	/// Adds a group of files already known to be duplicates, such as from
+	/// another tool's report or a check run elsewhere, to the results.  The
+	/// files aren't read, so `hash` should be their BLAKE3 hash if the group
+	/// is to be merged with groups found by checks.
+	///
+	/// If a group with the same hash is already in the results, the files
+	/// are added to it.  Otherwise, a new group is added if there are at
+	/// least two files.  Files already in another group are skipped.  The
+	/// group is then kept or removed according to
+	/// `DupOptions::min_group_size()` and `DupOptions::min_redundant_bytes()`,
+	/// and sent to the group sender and as an event, as if it had been found
+	/// by a check.  Later checks with these `DupResults` add any duplicates
+	/// of its files to it, comparing them with the first of its files that
+	/// exists, or only by their hash if none of its files exist here.
+	///
+	/// Returns whether the group is in the results.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use dupcheck::{Action, KeepPolicy};
+	///
+	/// let mut dup_result = dupcheck::DupResults::new();
+	/// dup_result.add_known_group(
+	///     "af1349b9f5f9a1a6a0404dea36dcc9499bcb25c9adc112b7cc9a93cae41f3262",
+	///     &["foo/empty.txt", "bar/empty.txt"],
+	/// );
+	///
+	/// let (plans, errors) = dup_result.plan_actions(&KeepPolicy::First, Action::Delete);
+	/// ```
+	pub fn add_known_group<T: AsRef<Path>>(&mut self, hash: &str, files: &[T]) -> bool {
+		let existing = self.duplicates.iter().position(|g| g.hash == hash);
+		let mut new_files: Vec<PathBuf> = vec![];
+
+		for file in files.iter().map(|f| f.as_ref().to_path_buf()) {
+			if !self.contains(&file) && !new_files.contains(&file) {
+				new_files.push(file);
+			}
+		}
+
+		let i = match existing {
+			Some(i) => {
+				for file in new_files {
+					self.duplicates[i].push_file(Arc::from(file), None, None);
+				}
+
+				i
+			}
+			None if new_files.len() > 1 => {
+				self.duplicates.push(DupGroup::new(
+					hash.to_string(),
+					new_files.into_iter().map(Arc::from).collect(),
+				));
+				self.duplicates.len() - 1
+			}
+			None => return false,
+		};
+
+		if !self.check_thresholds(i) {
+			return false;
+		}
+
+		self.set_roots(i);
+		self.set_metadata(i);
+
+		if let Some(sender) = self.options.group_sender() {
+			let _ = sender.send(self.duplicates[i].clone());
+		}
+
+		self
+			.options
+			.emit(|| ScanEvent::GroupUpdated(Box::new(self.duplicates[i].clone())));
+
+		true
+	}
+}
@@ -1,14 +1,111 @@
 //! Duplicate file checker.
 #![deny(missing_docs)]
 
+mod actions;
+mod benchmark;
+mod between;
+mod binary;
+mod blocklist;
+mod cache;
+mod canonical;
+mod category;
+mod concurrent;
+mod config;
+mod content_type;
+mod daemon;
+mod diff;
 mod duperror;
+mod events;
+mod explain;
+mod export;
+mod extents;
+mod fadvise;
+mod handle;
+mod hardlink;
+mod hash;
+mod ignore;
+mod inflate;
+mod json;
+mod keep;
+mod known;
+mod linked;
+mod manifest;
+mod metadata;
+mod metrics;
+mod mmap;
+mod normalize;
+mod options;
+mod original;
+mod paths;
+mod pipeline;
+mod placeholder;
+mod pool;
+mod priority;
+mod progress;
+mod queried;
+mod query;
+mod recheck;
+mod reflink;
+mod repeated;
+mod retry;
+mod schedule;
+mod script;
+mod server;
+mod signature;
+mod similar;
+mod skipped;
+mod sql;
+mod stats;
+mod tags;
+mod template;
+mod threshold;
+mod trash;
 mod utilities;
+mod walker;
+mod webhook;
+
+pub use crate::actions::{Action, ActionPlan, ActionResults, Decision};
+pub use crate::benchmark::{Benchmark, ReadMethod};
+pub use crate::binary::ResultsWriter;
+pub use crate::cache::HashCache;
+pub use crate::category::Category;
+pub use crate::config::{Config, Profile};
+pub use crate::daemon::Daemon;
+pub use crate::events::{ScanEvent, ScanStats, SkipReason};
+pub use crate::explain::Explanation;
+pub use crate::handle::ScanHandle;
+pub use crate::hash::{
+	hash_file, hash_file_normalized, hash_reader, hash_reader_normalized, DEFAULT_BUFFER_SIZE,
+};
+pub use crate::ignore::IgnoreStore;
+pub use crate::keep::{KeepPolicy, KeepRule, KeepRules};
+pub use crate::manifest::{Manifest, VerifyResults};
+pub use crate::metadata::FileMetadata;
+pub use crate::normalize::Normalization;
+pub use crate::options::DupOptions;
+pub use crate::pipeline::{Pipeline, Stage, PREFIX_LEN};
+pub use crate::progress::{Progress, ScanPhase, ScanStatus};
+pub use crate::retry::RetryPolicy;
+pub use crate::schedule::Schedule;
+pub use crate::script::ScriptFormat;
+pub use crate::server::Server;
+pub use crate::signature::Signature;
+pub use crate::similar::SimilarGroup;
+pub use crate::stats::{ExtensionStats, OwnerStats, RootStats};
+pub use crate::template::Template;
+pub use crate::walker::{BuiltinWalker, WalkVisitor, Walker};
 
 use crate::duperror::DupError;
+use crate::normalize::normalized_hash;
+use crate::paths::{FilePath, PathInterner};
+use crate::pool::BufferPool;
 use crate::utilities::PathUtilities;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::io;
+use std::io::{BufRead, Write};
 use std::path::Path;
 use std::path::PathBuf;
+use std::sync::Arc;
 
 /// Results of a duplicate file check, containing any duplicate file groups
 /// found and any errors encountered.
@@ -18,21 +115,94 @@ pub struct DupResults {
 
 	/// Errors encountered while checking for duplicate files.
 	errors: Vec<DupError>,
+
+	/// Warnings for paths given more than once to a check.
+	warnings: Vec<DupError>,
+
+	/// Paths skipped by checks and why, if `DupOptions::record_skipped()` is
+	/// enabled.
+	skipped: Vec<(PathBuf, SkipReason)>,
+
+	/// Files found to have no duplicates and the comparison stage at which
+	/// they were found to be unique, if `DupOptions::explainable()` is
+	/// enabled.
+	unique: Vec<(PathBuf, Stage)>,
+
+	/// Options controlling how checks are run.
+	options: DupOptions,
+
+	/// Hashes of all checked files, if `DupOptions::hash_all()` is enabled.
+	scanned: Manifest,
+
+	/// Groups of similar files found by `similar_videos()` and
+	/// `similar_texts()`.
+	similar: Vec<SimilarGroup>,
+
+	/// Hashes and paths of files found by `check_blocklist()` to match a
+	/// blocked hash.
+	blocked: Vec<(String, PathBuf)>,
+
+	/// The directories checked by `within()`, with the number of files found
+	/// in each.
+	roots: Vec<(Arc<Path>, usize)>,
+
+	/// The files given to the running `of()` check, if they're to be kept
+	/// apart from the files in their groups.
+	queried: Vec<PathBuf>,
+
+	/// Buffers reused for reading files when hashing or comparing them.
+	buffers: BufferPool,
+}
+
+impl Default for DupResults {
+	fn default() -> DupResults {
+		DupResults::new()
+	}
 }
 
 impl DupResults {
 	/// Creates a new, empty `DupResults`.
 	pub fn new() -> DupResults {
+		DupResults::with_options(DupOptions::new())
+	}
+
+	/// Creates a new, empty `DupResults` that will run checks using the given
+	/// options.
+	pub fn with_options(options: DupOptions) -> DupResults {
 		DupResults {
 			duplicates: vec![],
 			errors: vec![],
+			warnings: vec![],
+			skipped: vec![],
+			unique: vec![],
+			options,
+			scanned: Manifest::new(),
+			similar: vec![],
+			blocked: vec![],
+			roots: vec![],
+			queried: vec![],
+			buffers: BufferPool::new(),
 		}
 	}
 
+	/// Returns a reference to the options used when running checks.
+	pub fn options(&self) -> &DupOptions {
+		&self.options
+	}
+
+	/// Returns a mutable reference to the options used when running checks,
+	/// allowing them to be changed between checks.
+	pub fn options_mut(&mut self) -> &mut DupOptions {
+		&mut self.options
+	}
+
 	/// Checks for any duplicates of the specified files within their parent
 	/// directories, or optionally within other specified directories, and
 	/// returns the results.
 	///
+	/// The parent directory of a file given by its name alone is the current
+	/// directory.
+	///
 	/// # Errors
 	///
 	/// The returned `DupResults` will contain errors if any `files` are not
@@ -68,19 +238,24 @@ impl DupResults {
 	/// }
 	/// ```
 	pub fn of<T: AsRef<Path>>(&mut self, files: &[T], dirs_opt: Option<&[T]>) -> io::Result<()> {
-		let file_paths = self.convert_to_path_buf(files);
 		self.check_valid_paths(Some(files), dirs_opt)?;
+		let file_paths = self.convert_to_path_buf(files);
+		let file_paths = self.remove_repeated(file_paths);
 
 		let mut check_files = vec![];
 
+		let retry = self.options.retry_policy();
+		self.started();
+
 		if let Some(dirs) = dirs_opt {
 			let dir_paths = self.convert_to_path_buf(dirs);
+			let dir_paths = self.remove_repeated(dir_paths);
 			let mut sizes = vec![];
 
 			for file in &file_paths {
-				match file.metadata() {
+				match retry.run(|| file.metadata()) {
 					Ok(metadata) => sizes.push(metadata.len()),
-					Err(e) => self.errors.push(DupError::new(file.to_path_buf(), e)),
+					Err(e) => self.push_error(DupError::new(file.to_path_buf(), e)),
 				};
 			}
 
@@ -99,33 +274,64 @@ impl DupResults {
 			for file in &file_paths {
 				if !check_files.contains(file) {
 					check_files.push(file.to_path_buf());
+					self.options.record(Progress::add_file_found);
 				}
 			}
 		} else {
 			// Check only a file's parent directory for other files of its size.
 			for file in &file_paths {
-				let parent = file.parent().unwrap().to_path_buf();
-				let sizes = match file.metadata() {
+				let sizes = match retry.run(|| file.metadata()) {
 					Ok(metadata) => vec![metadata.len()],
 					Err(e) => {
-						self.errors.push(DupError::new(file.to_path_buf(), e));
+						self.push_error(DupError::new(file.to_path_buf(), e));
 						continue;
 					}
 				};
 
-				let (mut p_files, mut p_errors) = parent.files_within(Some(&sizes));
+				// A file given by its name alone has an empty parent, meaning
+				// the current directory, whose files are then given by their
+				// names alone too.
+				let (parent, in_current_dir) = match file.parent() {
+					Some(p) if p.as_os_str().is_empty() => (Some(PathBuf::from(".")), true),
+					p => (p.map(Path::to_path_buf), false),
+				};
 
-				if !p_files.is_empty() {
-					check_files.append(&mut p_files);
+				if let Some(parent) = parent {
+					let (p_files, mut p_errors) =
+						parent.files_within(Some(&sizes), &self.options, &mut HashSet::new());
+
+					check_files.extend(p_files.into_iter().map(|f| match in_current_dir {
+						true => f.strip_prefix(".").map(Path::to_path_buf).unwrap_or(f),
+						false => f,
+					}));
+
+					if !p_errors.is_empty() {
+						self.errors.append(&mut p_errors);
+					}
 				}
 
-				if !p_errors.is_empty() {
-					self.errors.append(&mut p_errors);
+				// The file itself isn't found if it has no parent directory or
+				// its parent couldn't be read, but it's still checked.
+				if !check_files.contains(file) {
+					check_files.push(file.to_path_buf());
+					self.options.record(Progress::add_file_found);
 				}
 			}
+
+			// Files sharing a parent directory are found once for each of
+			// them.
+			let mut found = HashSet::new();
+			check_files.retain(|f| found.insert(f.clone()));
 		}
 
-		self._files(&check_files)
+		if self.options.exclude_queried() {
+			self.queried = file_paths;
+		}
+
+		let checked = self._files(check_files);
+		self.queried.clear();
+
+		checked
 	}
 
 	/// Checks for any duplicate files within the specified directories and
@@ -183,14 +389,32 @@ impl DupResults {
 	/// ```
 	pub fn within<T: AsRef<Path>>(&mut self, dirs: &[T]) -> io::Result<()> {
 		self.check_valid_paths(None, Some(dirs))?;
+		self.started();
+
+		let dirs = self.convert_to_path_buf(dirs);
+		let dirs = self.remove_repeated(dirs);
+
+		if self.is_concurrent() {
+			let found = self.find_concurrently(&dirs);
+			self.add_roots(dirs, &found.files);
+			self.errors.extend(found.search_errors);
+
+			for dup_error in found.read_errors {
+				self.push_error(dup_error);
+			}
+
+			return self.compare(found.files, found.hashes, found.sizes);
+		}
 
-		let (files, mut errors) = self.files_within(&self.convert_to_path_buf(dirs), None);
+		let (files, mut errors) = self.files_within(&dirs, None);
+		let files = PathInterner::new().intern_all(files);
+		self.add_roots(dirs, &files);
 
 		if !errors.is_empty() {
 			self.errors.append(&mut errors);
 		}
 
-		self._files(&files)
+		self.compare(files, HashMap::new(), HashMap::new())
 	}
 
 	/// Checks for any duplicates among the specified files and returns the
@@ -221,65 +445,730 @@ impl DupResults {
 	/// ```
 	pub fn files<T: AsRef<Path>>(&mut self, files: &[T]) -> io::Result<()> {
 		self.check_valid_paths(Some(files), None)?;
+		self.started();
+
+		let files = self.convert_to_path_buf(files);
+		let files = self.remove_repeated(files);
+
+		for _ in &files {
+			self.options.record(Progress::add_file_found);
+		}
 
-		self._files(&self.convert_to_path_buf(files))
+		self._files(files)
 	}
 
-	fn _files(&mut self, files: &[PathBuf]) -> io::Result<()> {
-		// Organise the file paths according to file sizes.  Any file with a
-		// unique size within the check list can't be a duplicate, so this will
-		// ensure we don't waste time on hash checks of those files later.
-		let mut sizes: Vec<(u64, Vec<PathBuf>)> = vec![];
+	/// Checks for any files within the specified directories that are
+	/// duplicates of files listed in a manifest, and returns the results.
+	///
+	/// The files listed in the manifest don't need to exist; only their
+	/// hashes are compared with the hashes of the files within `dirs`.  Any
+	/// file with the same hash as a manifest entry is added to a `DupGroup`
+	/// whose baseline files are the paths of the manifest entries with that
+	/// hash, so groups found this way may contain only one file.
+	///
+	/// # Errors
+	///
+	/// The returned `DupResults` will contain errors if any paths within `dirs`
+	/// are not directories or if I/O errors occur while trying to read files
+	/// or directories.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use std::path::PathBuf;
+	///
+	/// let mut manifest = dupcheck::Manifest::new();
+	/// manifest.add(
+	///     "af1349b9f5f9a1a6a0404dea36dcc9499bcb25c9adc112b7cc9a93cae41f3262".to_string(),
+	///     PathBuf::from("archive/empty.txt"),
+	/// );
+	/// let dirs = vec![PathBuf::from("foo")];
+	/// let mut dup_result = dupcheck::DupResults::new();
+	///
+	/// if let Err(dup_error) = dup_result.against(&manifest, &dirs) {
+	///     // Error handling
+	/// }
+	/// ```
+	pub fn against<T: AsRef<Path>>(&mut self, manifest: &Manifest, dirs: &[T]) -> io::Result<()> {
+		self.check_valid_paths(None, Some(dirs))?;
+		self.started();
+
+		let dirs = self.convert_to_path_buf(dirs);
+		let dirs = self.remove_repeated(dirs);
+		let (files, mut errors) = self.files_within(&dirs, None);
+
+		if !errors.is_empty() {
+			self.errors.append(&mut errors);
+		}
+
+		let retry = self.options.retry_policy();
+		let files = self.not_yet_checked(PathInterner::new().intern_all(files));
+		let files_checked = files.len();
+		self.options.record(|p| p.set_phase(ScanPhase::Comparing));
+		self.add_total(&files);
+
+		// The manifest and groups are indexed by hash once, rather than being
+		// searched for each file.
+		let mut baselines: HashMap<&str, Vec<Arc<Path>>> = HashMap::new();
+
+		for (hash, path) in manifest.entries() {
+			baselines
+				.entry(hash.as_str())
+				.or_default()
+				.push(Arc::from(path.as_path()));
+		}
+
+		let mut groups: HashMap<String, usize> = self
+			.duplicates
+			.iter()
+			.enumerate()
+			.map(|(i, g)| (g.hash.clone(), i))
+			.collect();
+		let mut merged = HashSet::new();
 
 		for file in files {
-			let size = match file.metadata() {
-				Ok(metadata) => metadata.len(),
+			self.options.checkpoint();
+			let file = file.to_path_buf();
+			let hash = match retry.run(|| self.hash(&file)) {
+				Ok(h) => h,
 				Err(e) => {
-					self.errors.push(DupError::new(file.to_path_buf(), e));
+					self.push_error(DupError::new(file, e));
 					continue;
 				}
 			};
 
-			match sizes.iter().position(|s| s.0 == size) {
-				Some(i) => sizes[i].1.push(file.clone()),
-				None => sizes.push((size, vec![file.clone()])),
+			if self.options.hash_all() {
+				self.scanned.add(hash.clone(), file.clone());
+			}
+
+			if self.options.is_ignored_hash(&hash) {
+				continue;
+			}
+
+			let baseline = match baselines.get(hash.as_str()) {
+				Some(b) => b,
+				None => continue,
+			};
+
+			let i = match groups.get(&hash) {
+				Some(&i) => {
+					let group = &mut self.duplicates[i];
+					group.push_file(Arc::from(file), None, None);
+
+					// Every file with this hash has the same baseline, so
+					// it only needs adding to the group once.
+					if merged.insert(i) {
+						for path in baseline {
+							if !group.baseline.contains(path) {
+								group.baseline.push(Arc::clone(path));
+							}
+						}
+					}
+
+					i
+				}
+				None => {
+					let mut group = DupGroup::new(hash.clone(), vec![Arc::from(file)]);
+					group.baseline = baseline.clone();
+					self.duplicates.push(group);
+					let i = self.duplicates.len() - 1;
+					groups.insert(hash, i);
+					merged.insert(i);
+					i
+				}
 			};
+
+			self.set_metadata(i);
+			self
+				.options
+				.emit(|| ScanEvent::GroupUpdated(Box::new(self.duplicates[i].clone())));
 		}
 
-		// Check hashes of files where more than one file of its size was found.
-		let mut hashes: Vec<(String, PathBuf)> = vec![];
-		let mut new_errors: Vec<DupError> = vec![];
-		let files = sizes
+		self.finished(files_checked);
+
+		Ok(())
+	}
+
+	/// Loads duplicate file groups from the output of fdupes or jdupes.
+	///
+	/// The output is expected to list each group's file paths on separate
+	/// lines, with groups separated by blank lines, as fdupes and jdupes do
+	/// by default.  Lines giving group sizes, as added by their `--size`
+	/// options, are ignored.
+	///
+	/// The files aren't read, so the loaded groups are given identifiers in
+	/// place of BLAKE3 hashes.  Any paths already in this `DupResults` are
+	/// skipped.
+	///
+	/// # Errors
+	///
+	/// Returns an error if reading fails.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// let output = "/foo/a.txt\n/foo/b.txt\n\n/foo/c.jpg\n/bar/c.jpg\n";
+	/// let mut dup_result = dupcheck::DupResults::new();
+	///
+	/// if let Err(import_error) = dup_result.import_fdupes(output.as_bytes()) {
+	///     // Error handling
+	/// }
+	///
+	/// assert_eq!(dup_result.duplicates().len(), 2);
+	/// ```
+	pub fn import_fdupes<R: BufRead>(&mut self, reader: R) -> io::Result<()> {
+		let mut group_files = vec![];
+
+		for line in reader.lines() {
+			let line = line?;
+
+			if line.is_empty() {
+				self.add_imported_group(&mut group_files);
+			} else if !is_fdupes_size_line(&line) {
+				group_files.push(PathBuf::from(line));
+			}
+		}
+
+		self.add_imported_group(&mut group_files);
+
+		Ok(())
+	}
+
+	/// Adds a group of imported files that aren't already in this
+	/// `DupResults`, if there are at least two of them, and clears the given
+	/// file list.
+	fn add_imported_group(&mut self, files: &mut Vec<PathBuf>) {
+		let mut new_files = vec![];
+
+		for file in files.drain(..) {
+			if !self.contains(&file) && !new_files.contains(&file) {
+				new_files.push(file);
+			}
+		}
+
+		if new_files.len() < 2 {
+			return;
+		}
+
+		let mut n = self.duplicates.len() + 1;
+		let mut hash = format!("import-{}", n);
+
+		while self.duplicates.iter().any(|g| g.hash == hash) {
+			n += 1;
+			hash = format!("import-{}", n);
+		}
+
+		let new_files = new_files.into_iter().map(Arc::from).collect();
+		self.duplicates.push(DupGroup::new(hash, new_files));
+	}
+
+	fn _files(&mut self, files: Vec<PathBuf>) -> io::Result<()> {
+		let files = PathInterner::new().intern_all(files);
+		self.compare(files, HashMap::new(), HashMap::new())
+	}
+
+	/// Checks for duplicates among the given files and the existing groups,
+	/// using any of the given hashes and sizes that are known rather than
+	/// reading the files again.  Files whose hashes or sizes are known have
+	/// already been counted in the progress counters.
+	fn compare(
+		&mut self,
+		files: Vec<FilePath>,
+		mut hashes: HashMap<FilePath, String>,
+		sizes: HashMap<FilePath, u64>,
+	) -> io::Result<()> {
+		let pipeline = self.options.pipeline().clone();
+		pipeline.check_valid()?;
+
+		// The first file of each existing group that's here to be read goes
+		// through the pipeline alongside the files being checked, so any
+		// files found to be duplicates of it are added to its group rather
+		// than forming a new one.  Groups added without any such files, such
+		// as by `add_known_group()`, can only be matched by their hash.
+		let mut candidates: Vec<Candidate> = self
+			.duplicates
 			.iter()
-			.filter(|size| size.1.len() > 1)
-			.flat_map(|size| &size.1)
-			.filter(|file| !self.contains(file));
+			.enumerate()
+			.filter_map(|(i, g)| {
+				g.files.iter().find(|f| f.is_file()).map(|f| Candidate {
+					path: FilePath::from(f.to_path_buf()),
+					group: Some(i),
+				})
+			})
+			.collect();
+
+		let files = self.not_yet_checked(files);
+		let files_checked = files.len();
 
-		// If this isn't the first check for these `DupResults`, ensure
-		// this file is only checked if its path hasn't been added in a
-		// previous check.
 		for file in files {
-			match file.blake3() {
-				Ok(h) => hashes.push((h, file.clone())),
-				Err(e) => new_errors.push(DupError::new(file.to_path_buf(), e)),
+			candidates.push(Candidate {
+				path: file,
+				group: None,
+			});
+		}
+
+		// Signatures are checked after the size stage if there is one.
+		if pipeline.stages().first() != Some(&Stage::Size) {
+			candidates = self.without_excluded_signatures(candidates);
+		}
+
+		self.options.record(|p| p.set_phase(ScanPhase::Comparing));
+
+		let sizes = match self.options.prescan() {
+			true => self.prescan(&pipeline, &mut candidates),
+			false => sizes,
+		};
+
+		// If all files are to be hashed, do that first so the hashes can be
+		// reused by the full hash stage.
+		if self.options.hash_all() {
+			let retry = self.options.retry_policy();
+
+			if sizes.is_empty() {
+				let new_files: Vec<FilePath> = candidates
+					.iter()
+					.filter(|c| c.group.is_none())
+					.map(|c| c.path.clone())
+					.collect();
+				self.add_total(&new_files);
+			}
+
+			for candidate in candidates.iter().filter(|c| c.group.is_none()) {
+				self.options.checkpoint();
+				let path = candidate.path.to_path_buf();
+
+				match retry.run(|| self.hash(&path)) {
+					Ok(h) => {
+						self.scanned.add(h.clone(), path);
+						hashes.insert(candidate.path.clone(), h);
+					}
+					Err(e) => self.push_error(DupError::new(path, e)),
+				};
+			}
+
+			candidates.retain(|c| c.group.is_some() || hashes.contains_key(&c.path));
+		}
+
+		self.run_stages(
+			&pipeline,
+			pipeline.stages(),
+			vec![],
+			candidates,
+			(&hashes, &sizes),
+		);
+		self.finished(files_checked);
+
+		Ok(())
+	}
+
+	/// Reads the sizes of the files to be compared, removing any whose size
+	/// couldn't be read, and records the number and total size of the files
+	/// that may need to be hashed in the progress counters.
+	///
+	/// Unless all files are to be hashed, files whose size no other file
+	/// has are left out of the totals if the pipeline compares sizes.
+	fn prescan(
+		&mut self,
+		pipeline: &Pipeline,
+		candidates: &mut Vec<Candidate>,
+	) -> HashMap<FilePath, u64> {
+		let retry = self.options.retry_policy();
+		let mut sizes = HashMap::new();
+		let mut errors = vec![];
+
+		candidates.retain(|c| {
+			self.options.checkpoint();
+			let path = c.path.to_path_buf();
+			self.options.record(|p| p.set_current_path(Some(&path)));
+
+			match retry.run(|| path.metadata()) {
+				Ok(metadata) => {
+					sizes.insert(c.path.clone(), metadata.len());
+					true
+				}
+				Err(e) => {
+					errors.push(DupError::new(path, e));
+					false
+				}
+			}
+		});
+
+		for dup_error in errors {
+			self.push_error(dup_error);
+		}
+
+		let mut new_by_size: HashMap<u64, (usize, bool)> = HashMap::new();
+
+		for candidate in candidates.iter() {
+			let entry = new_by_size.entry(sizes[&candidate.path]).or_default();
+			entry.0 += 1;
+			entry.1 |= candidate.group.is_none();
+		}
+
+		let hash_all = self.options.hash_all();
+		let compares_sizes = pipeline.contains(Stage::Size);
+		let (mut files, mut len) = (0, 0);
+
+		for candidate in candidates.iter() {
+			let size = sizes[&candidate.path];
+			let (count, any_new) = new_by_size[&size];
+			let counted = match hash_all {
+				true => candidate.group.is_none(),
+				false => !compares_sizes || (count > 1 && any_new),
+			};
+
+			if counted {
+				files += 1;
+				len += size;
+			}
+		}
+
+		self.options.record(|p| p.add_total(files, len));
+
+		sizes
+	}
+
+	/// Returns the given files that haven't been added to a group by a
+	/// previous check with these `DupResults`, so they aren't checked again.
+	fn not_yet_checked(&self, files: Vec<FilePath>) -> Vec<FilePath> {
+		if self.duplicates.is_empty() {
+			return files;
+		}
+
+		files
+			.into_iter()
+			.filter(|f| {
+				let path = f.to_path_buf();
+				let checked = self.contains(&path);
+
+				if checked {
+					self.options.skip(&path, SkipReason::AlreadyChecked);
+				}
+
+				!checked
+			})
+			.collect()
+	}
+
+	/// Prepares for a check to start, lowering the priority of the current
+	/// thread if needed and recording that files are being found.
+	fn started(&self) {
+		if self.options.low_priority() {
+			priority::lower_thread_priority();
+		}
+
+		self.options.record(|p| p.set_phase(ScanPhase::Discovering));
+	}
+
+	/// Sends the event for a finished check that checked the given number of
+	/// files, and adds the paths it skipped to the results.
+	fn finished(&mut self, files_checked: usize) {
+		self.skipped.append(&mut self.options.take_skipped());
+		self.options.record(|p| {
+			p.set_phase(ScanPhase::Finished);
+			p.set_current_path(None);
+		});
+		self.options.emit(|| ScanEvent::Finished {
+			stats: ScanStats::new(
+				files_checked,
+				self.duplicates.len(),
+				self.file_count(),
+				self.errors.len(),
+			),
+		});
+	}
+
+	/// Adds an error to the results and sends it as an event.
+	fn push_error(&mut self, dup_error: DupError) {
+		self.options.record(Progress::add_error);
+		self
+			.options
+			.emit(|| ScanEvent::Error(dup_error.to_string()));
+		self.errors.push(dup_error);
+	}
+
+	/// Runs a set of files through the given comparison stages, adding the
+	/// files in each set that matches at every stage to a group.
+	///
+	/// Each subset is run through all of the remaining stages before the
+	/// next subset, so groups are found as early as possible.  `keys` are the
+	/// keys identifying the set at each stage it has already been through.
+	fn run_stages(
+		&mut self,
+		pipeline: &Pipeline,
+		stages: &[Stage],
+		keys: Vec<String>,
+		set: Vec<Candidate>,
+		known: (&HashMap<FilePath, String>, &HashMap<FilePath, u64>),
+	) {
+		let (stage, next_stages) = match stages.split_first() {
+			Some(s) => s,
+			None => return self.add_group(pipeline, &keys, set),
+		};
+
+		// The files that may need to be hashed are counted before the first
+		// stage, or after it if it's the size stage so that files with
+		// unique sizes aren't counted.  Files that were all hashed first, or
+		// whose sizes were read first, have already been counted.
+		let (hashes, sizes) = known;
+		let count_bytes = keys.is_empty() && hashes.is_empty() && sizes.is_empty();
+
+		if count_bytes && *stage != Stage::Size {
+			let paths: Vec<FilePath> = set.iter().map(|c| c.path.clone()).collect();
+			self.add_total(&paths);
+		}
+
+		for (key, subset) in self.split(*stage, set, known) {
+			// Files are only read for their signatures once they're known
+			// to have the same size as another file.
+			let subset = match *stage {
+				Stage::Size => self.without_excluded_signatures(subset),
+				_ => subset,
 			};
+
+			if subset.len() == 1 && subset[0].group.is_none() && self.options.explainable() {
+				self.unique.push((subset[0].path.to_path_buf(), *stage));
+			}
+
+			// Any file left alone in its subset can't be a duplicate, so this
+			// ensures we don't waste time on later stages for those files, or
+			// for subsets of only existing groups' files.
+			if subset.len() > 1 && subset.iter().any(|c| c.group.is_none()) {
+				if count_bytes && *stage == Stage::Size {
+					let files = subset.len() as u64;
+					let len = key.parse::<u64>().unwrap_or(0) * files;
+					self.options.record(|p| p.add_total(files, len));
+				}
+
+				let mut subset_keys = keys.clone();
+				subset_keys.push(key);
+				self.run_stages(pipeline, next_stages, subset_keys, subset, known);
+			}
 		}
+	}
+
+	/// Adds a set of files that matched at every comparison stage to the
+	/// existing group in the set or with the same identifier, or otherwise
+	/// to a new group, and sends the group to the group sender if one is set.
+	fn add_group(&mut self, pipeline: &Pipeline, keys: &[String], set: Vec<Candidate>) {
+		let new_files = set
+			.iter()
+			.filter(|c| c.group.is_none())
+			.map(|c| Arc::from(c.path.to_path_buf()));
+
+		let i = match set.iter().find_map(|c| c.group) {
+			Some(i) => {
+				self.duplicates[i].files.extend(new_files);
+				i
+			}
+			None => {
+				let hash = self.group_hash(pipeline, keys);
+
+				match self.duplicates.iter().position(|g| g.hash == hash) {
+					Some(i) => {
+						self.duplicates[i].files.extend(new_files);
+						i
+					}
+					None => {
+						let mut group = DupGroup::new(hash, new_files.collect());
 
-		for (hash, file) in &hashes {
-			match self.duplicates.iter().position(|h| h.hash == *hash) {
-				Some(i) => self.duplicates[i].add_file(file.clone()),
-				None => self.duplicates.push(DupGroup {
-					hash: hash.clone(),
-					files: vec![file.clone()],
-				}),
+						if self.is_ignored(pipeline, &group) {
+							return;
+						}
+
+						if pipeline.contains(Stage::NormalizedHash) {
+							group.normalizations = self.options.normalizations().to_vec();
+						}
+
+						self.duplicates.push(group);
+						self.duplicates.len() - 1
+					}
+				}
+			}
+		};
+
+		if !self.separate_queried(i) || !self.check_thresholds(i) {
+			return;
+		}
+
+		self.set_roots(i);
+		self.set_metadata(i);
+
+		if let Some(sender) = self.options.group_sender() {
+			// The receiver may have been dropped if it's no longer interested
+			// in groups, which shouldn't stop the check.
+			let _ = sender.send(self.duplicates[i].clone());
+		}
+
+		self
+			.options
+			.emit(|| ScanEvent::GroupUpdated(Box::new(self.duplicates[i].clone())));
+	}
+
+	/// Splits a set of files into subsets of files that match at the given
+	/// comparison stage, each with the key identifying the subset at that
+	/// stage, and records errors for any files that couldn't be compared.
+	///
+	/// The full hash and size stages use any of the given hashes and sizes
+	/// that are known rather than reading the files again.
+	fn split(
+		&mut self,
+		stage: Stage,
+		set: Vec<Candidate>,
+		known: (&HashMap<FilePath, String>, &HashMap<FilePath, u64>),
+	) -> Vec<(String, Vec<Candidate>)> {
+		let (hashes, sizes) = known;
+		let retry = self.options.retry_policy();
+		let mut subsets: Vec<(String, Vec<Candidate>)> = vec![];
+
+		for candidate in set {
+			let path = &candidate.path.to_path_buf();
+			self.options.checkpoint();
+			self.options.record(|p| p.set_current_path(Some(path)));
+			let key = match stage {
+				Stage::Size => match sizes.get(&candidate.path) {
+					Some(s) => Ok(s.to_string()),
+					None => retry.run(|| path.metadata()).map(|m| m.len().to_string()),
+				},
+				Stage::PrefixHash => retry.run(|| path.blake3_prefix(PREFIX_LEN)),
+				Stage::FullHash => match hashes.get(&candidate.path) {
+					Some(h) => Ok(h.clone()),
+					None => retry.run(|| self.hash(path)),
+				},
+				Stage::ByteCompare => retry.run(|| self.byte_compare_key(path, &subsets)),
+				Stage::NormalizedHash => {
+					let hash = retry.run(|| normalized_hash(path, self.options.normalizations()));
+
+					if let Ok(h) = &hash {
+						self.hashed(path, h);
+					}
+
+					hash
+				}
+			};
+
+			match key {
+				Ok(k) => match subsets.iter().position(|s| s.0 == k) {
+					Some(i) => subsets[i].1.push(candidate),
+					None => subsets.push((k, vec![candidate])),
+				},
+				Err(e) => self.push_error(DupError::new(path.clone(), e)),
 			};
 		}
 
-		// Keep only the groups with more than one file.
-		self.duplicates.retain(|h| h.file_count() > 1);
-		self.errors.append(&mut new_errors);
+		subsets
+	}
 
-		Ok(())
+	/// Returns the key of the subset whose first file has the same contents
+	/// as the given file, or a new key if there is no such subset.
+	fn byte_compare_key(
+		&self,
+		path: &PathBuf,
+		subsets: &[(String, Vec<Candidate>)],
+	) -> io::Result<String> {
+		for (key, subset) in subsets {
+			let mut buffer = self.buffers.take(self.options.buffer_size());
+			let mut other_buffer = self.buffers.take(self.options.buffer_size());
+
+			if path.same_contents(
+				&subset[0].path.to_path_buf(),
+				&mut buffer,
+				&mut other_buffer,
+			)? {
+				return Ok(key.clone());
+			}
+		}
+
+		Ok(subsets.len().to_string())
+	}
+
+	/// Returns the identifier for a new group, given the keys of the subsets
+	/// its files were in at each comparison stage.
+	///
+	/// This is the files' BLAKE3 hash if the pipeline includes the full hash
+	/// stage.  Otherwise, the keys are joined and, since byte comparison keys
+	/// are only unique within a single check, numbered if already in use.
+	fn group_hash(&self, pipeline: &Pipeline, keys: &[String]) -> String {
+		if let Some(i) = pipeline.stages().iter().position(|s| *s == Stage::FullHash) {
+			return keys[i].clone();
+		}
+
+		let base = keys.join("-");
+		let mut hash = base.clone();
+		let mut n = 1;
+
+		while self.duplicates.iter().any(|g| g.hash == hash) {
+			n += 1;
+			hash = format!("{}-{}", base, n);
+		}
+
+		hash
+	}
+
+	/// Returns whether a new group's files have a hash whose duplicates are
+	/// left out of the results.
+	///
+	/// The group's identifier is used if it's a hash of the files' contents,
+	/// and otherwise the first file is hashed.
+	fn is_ignored(&self, pipeline: &Pipeline, group: &DupGroup) -> bool {
+		if self.options.ignored_hashes().is_empty() {
+			return false;
+		}
+
+		if pipeline.contains(Stage::FullHash) || pipeline.contains(Stage::NormalizedHash) {
+			return self.options.is_ignored_hash(&group.hash);
+		}
+
+		let retry = self.options.retry_policy();
+
+		match retry.run(|| self.hash(&group.files[0])) {
+			Ok(hash) => self.options.is_ignored_hash(&hash),
+			Err(_) => false,
+		}
+	}
+
+	/// Returns a file's BLAKE3 hash, using the hash cache if one is set.
+	fn hash(&self, path: &Path) -> io::Result<String> {
+		let read_hash = |path: &Path| match self.options.fadvise() {
+			true => fadvise::blake3_uncached(path),
+			false => {
+				let mut buffer = self.buffers.take(self.options.buffer_size());
+				self.options.read_method().hash(path, &mut buffer)
+			}
+		};
+		let hash = match self.options.hash_cache() {
+			Some(cache) => cache.hash(path, read_hash),
+			None => read_hash(path),
+		}?;
+		self.hashed(path, &hash);
+
+		Ok(hash)
+	}
+
+	/// Records the number and total size of files that may need to be hashed
+	/// in the progress counters.
+	fn add_total(&self, files: &[FilePath]) {
+		self.options.record(|p| {
+			let len = files
+				.iter()
+				.filter_map(|f| f.to_path_buf().metadata().ok())
+				.map(|m| m.len())
+				.sum();
+			p.add_total(files.len() as u64, len);
+		});
+	}
+
+	/// Records that a file's hash was found in the progress counters, and
+	/// sends it as an event.
+	fn hashed(&self, path: &Path, hash: &str) {
+		self
+			.options
+			.record(|p| p.add_file_hashed(path.metadata().map_or(0, |m| m.len())));
+
+		self.options.emit(|| ScanEvent::FileHashed {
+			path: path.to_path_buf(),
+			hash: hash.to_string(),
+		});
 	}
 
 	/// Returns a reference to the duplicate file groups.
@@ -292,6 +1181,81 @@ impl DupResults {
 		&self.errors
 	}
 
+	/// Returns a mutable reference to the errors.
+	pub fn errors_mut(&mut self) -> &mut Vec<DupError> {
+		&mut self.errors
+	}
+
+	/// Removes and returns all errors encountered so far.
+	///
+	/// This allows errors to be handled as they occur when running several
+	/// checks with the same `DupResults`, rather than collecting the errors
+	/// from all checks.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use std::path::PathBuf;
+	///
+	/// let mut dup_result = dupcheck::DupResults::new();
+	/// let dirs = [
+	///     [PathBuf::from("foo")],
+	///     [PathBuf::from("bar")],
+	/// ];
+	///
+	/// for dir in &dirs {
+	///     if let Err(dup_error) = dup_result.within(dir) {
+	///         // Error handling
+	///     }
+	///
+	///     for error in dup_result.take_errors() {
+	///         eprintln!("{}", error);
+	///     }
+	/// }
+	/// ```
+	pub fn take_errors(&mut self) -> Vec<DupError> {
+		std::mem::take(&mut self.errors)
+	}
+
+	/// Writes a manifest of the hashes of all checked files to the given
+	/// writer, in a format that can be checked with `b3sum --check`.
+	///
+	/// This requires `DupOptions::hash_all()` to have been enabled for the
+	/// checks; otherwise, nothing will be written.
+	///
+	/// # Errors
+	///
+	/// Returns an error if writing fails.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use std::path::PathBuf;
+	///
+	/// let mut options = dupcheck::DupOptions::new();
+	/// options.set_hash_all(true);
+	///
+	/// let mut dup_result = dupcheck::DupResults::with_options(options);
+	/// let dirs = vec![PathBuf::from("foo")];
+	///
+	/// if let Err(dup_error) = dup_result.within(&dirs) {
+	///     // Error handling
+	/// }
+	///
+	/// if let Err(write_error) = dup_result.write_manifest(&mut std::io::stdout()) {
+	///     // Error handling
+	/// }
+	/// ```
+	pub fn write_manifest<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+		self.scanned.write(writer)
+	}
+
+	/// Returns a reference to the manifest of the hashes of all checked files,
+	/// if `DupOptions::hash_all()` was enabled for the checks.
+	pub fn manifest(&self) -> &Manifest {
+		&self.scanned
+	}
+
 	/// Returns the total number of all paths within all duplicate groups.
 	pub fn file_count(&self) -> usize {
 		self
@@ -306,9 +1270,10 @@ impl DupResults {
 	fn files_within(&self, dirs: &[PathBuf], sizes: Option<&[u64]>) -> (Vec<PathBuf>, Vec<DupError>) {
 		let mut files = vec![];
 		let mut errors = vec![];
+		let mut searched = HashSet::new();
 
 		for dir in dirs {
-			let (mut dir_files, mut dir_errors) = dir.files_within(sizes);
+			let (mut dir_files, mut dir_errors) = dir.files_within(sizes, &self.options, &mut searched);
 
 			if !dir_files.is_empty() {
 				files.append(&mut dir_files);
@@ -323,7 +1288,7 @@ impl DupResults {
 	}
 
 	/// Returns whether any `DupGroup`s contain the given file path.
-	fn contains(&self, path: &PathBuf) -> bool {
+	fn contains(&self, path: &Path) -> bool {
 		self.duplicates.iter().any(|g| g.contains(path))
 	}
 
@@ -363,29 +1328,141 @@ impl DupResults {
 }
 
 /// A group of duplicate files.
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct DupGroup {
-	/// The BLAKE3 hash of the files in this group.
+	/// The BLAKE3 hash of the files in this group, or another identifier if
+	/// the comparison pipeline didn't include the full hash stage.
 	hash: String,
 
-	/// The paths to the duplicate files.
-	files: Vec<PathBuf>,
+	/// The paths to the duplicate files.  Each path is stored once, and
+	/// other details of a file are kept by its index.
+	files: Vec<Arc<Path>>,
+
+	/// The paths of manifest entries with the same hash, if this group was
+	/// found by checking against a manifest.
+	baseline: Vec<Arc<Path>>,
+
+	/// The files given to `DupResults::of()` that were found to be in this
+	/// group, if they're kept apart from its files.
+	queried: Vec<Arc<Path>>,
+
+	/// The index of the file designated as the probable original, if any.
+	original: Option<usize>,
+
+	/// The index of the file designated as the copy to keep, if any.
+	canonical: Option<usize>,
+
+	/// The normalizations applied to the files' contents when comparing
+	/// them, if the group was found by the normalized hash stage.
+	normalizations: Vec<Normalization>,
+
+	/// The directory given to `DupResults::within()` that each file was
+	/// found in, by the file's index, for files found that way.  Files past
+	/// the end have no directory recorded.  The directories are shared with
+	/// the `DupResults` and its other groups.
+	roots: Vec<Option<Arc<Path>>>,
+
+	/// Key/value pairs attached to the group, such as while reviewing it.
+	tags: BTreeMap<String, String>,
+
+	/// The metadata of each file, by the file's index, as read when it was
+	/// added to the group.  Files past the end have no metadata recorded.
+	metadata: Vec<Option<FileMetadata>>,
 }
 
 impl DupGroup {
+	/// Creates a new `DupGroup` of the given files.
+	fn new(hash: String, files: Vec<Arc<Path>>) -> DupGroup {
+		DupGroup {
+			hash,
+			files,
+			baseline: vec![],
+			queried: vec![],
+			original: None,
+			canonical: None,
+			normalizations: vec![],
+			roots: vec![],
+			tags: BTreeMap::new(),
+			metadata: vec![],
+		}
+	}
+
 	/// Returns the BLAKE3 hash of the files in this group.
+	///
+	/// If the comparison pipeline used to find this group didn't include
+	/// `Stage::FullHash`, this is instead an identifier derived from the keys
+	/// of the stages that were run.  Groups loaded from other tools' output
+	/// are also given identifiers.
 	pub fn get_hash(&self) -> String {
 		self.hash.clone()
 	}
 
-	/// Returns a reference to the group's file paths.
-	pub fn get_files(&self) -> &[PathBuf] {
+	/// Returns a reference to the group's file paths, each of which derefs to
+	/// a `Path`.
+	pub fn get_files(&self) -> &[Arc<Path>] {
 		&self.files
 	}
 
-	/// Adds a file path.
-	fn add_file(&mut self, file: PathBuf) {
-		self.files.push(file);
+	/// Returns a reference to the paths of the manifest entries that the
+	/// group's files are duplicates of, if the group was found by checking
+	/// against a manifest.
+	pub fn get_baseline_files(&self) -> &[Arc<Path>] {
+		&self.baseline
+	}
+
+	/// Returns how similar the group's files are, from 0 to 1, which is always
+	/// 1 since duplicate files have the same contents.  Groups of files that
+	/// are only nearly the same are instead given by `DupResults::similar()`,
+	/// with `SimilarGroup::similarity()` giving their similarity on the same
+	/// scale.
+	pub fn similarity(&self) -> f64 {
+		1.0
+	}
+
+	/// Returns a reference to the normalizations applied to the files'
+	/// contents when comparing them, which is empty unless the group was
+	/// found by `Stage::NormalizedHash`.
+	pub fn normalizations(&self) -> &[Normalization] {
+		&self.normalizations
+	}
+
+	/// Returns the directory given to `DupResults::within()` that a file in
+	/// this group was found in, or `None` if the file isn't in the group or
+	/// wasn't found by `within()`.  If the directories overlap, this is the
+	/// first of them that contains the file.
+	pub fn root(&self, file: &Path) -> Option<&Path> {
+		self.root_at(self.position(file)?)
+	}
+
+	/// Returns whether this group's files were found in more than one of the
+	/// directories given to `DupResults::within()`, rather than all being
+	/// within the same one.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use std::path::PathBuf;
+	///
+	/// let dirs = vec![PathBuf::from("foo"), PathBuf::from("bar")];
+	/// let mut dup_result = dupcheck::DupResults::new();
+	///
+	/// if let Err(dup_error) = dup_result.within(&dirs) {
+	///     // Error handling
+	/// }
+	///
+	/// for group in dup_result.duplicates().iter().filter(|g| g.is_cross_root()) {
+	///     for file in group.get_files() {
+	///         println!("{} (in {:?})", file.display(), group.root(file));
+	///     }
+	/// }
+	/// ```
+	pub fn is_cross_root(&self) -> bool {
+		let mut roots = self.roots.iter().flatten();
+
+		match roots.next() {
+			Some(first) => roots.any(|r| r != first),
+			None => false,
+		}
 	}
 
 	/// Returns the number of file paths in this group.
@@ -393,7 +1470,119 @@ impl DupGroup {
 		self.files.len()
 	}
 
-	fn contains(&self, path: &PathBuf) -> bool {
-		self.files.contains(path)
+	fn contains(&self, path: &Path) -> bool {
+		self.position(path).is_some() || self.queried.iter().any(|f| **f == *path)
+	}
+
+	/// Returns the index of a file in this group, if it's in the group.
+	pub(crate) fn position(&self, file: &Path) -> Option<usize> {
+		self.files.iter().position(|f| **f == *file)
+	}
+
+	/// Returns the directory given to `DupResults::within()` that the file
+	/// at the given index was found in, if it's known.
+	pub(crate) fn root_at(&self, i: usize) -> Option<&Path> {
+		self.roots.get(i)?.as_deref()
+	}
+
+	/// Records the directory that the file at the given index was found in.
+	pub(crate) fn set_root(&mut self, i: usize, root: Arc<Path>) {
+		if self.roots.len() <= i {
+			self.roots.resize(i + 1, None);
+		}
+
+		self.roots[i] = Some(root);
+	}
+
+	/// Returns the metadata recorded for the file at the given index, if
+	/// any.
+	pub(crate) fn metadata_at(&self, i: usize) -> Option<&FileMetadata> {
+		self.metadata.get(i)?.as_ref()
+	}
+
+	/// Records the metadata of the file at the given index.
+	pub(crate) fn set_metadata_at(&mut self, i: usize, metadata: FileMetadata) {
+		if self.metadata.len() <= i {
+			self.metadata.resize(i + 1, None);
+		}
+
+		self.metadata[i] = Some(metadata);
+	}
+
+	/// Adds a file to this group, with the directory it was found in and its
+	/// metadata, if they're known.
+	pub(crate) fn push_file(
+		&mut self,
+		file: Arc<Path>,
+		root: Option<Arc<Path>>,
+		metadata: Option<FileMetadata>,
+	) {
+		let i = self.files.len();
+		self.files.push(file);
+
+		if let Some(r) = root {
+			self.set_root(i, r);
+		}
+
+		if let Some(m) = metadata {
+			self.set_metadata_at(i, m);
+		}
+	}
+
+	/// Removes the files for which `keep` returns false from this group,
+	/// along with their directories and metadata, and returns them.  The
+	/// files designated as the original and canonical copies keep their
+	/// designations if they're kept.
+	pub(crate) fn remove_files<F: FnMut(&Path) -> bool>(&mut self, mut keep: F) -> Vec<Arc<Path>> {
+		let mut removed = vec![];
+		let mut kept = vec![];
+		let mut roots = vec![];
+		let mut metadata = vec![];
+		let (original, canonical) = (self.original.take(), self.canonical.take());
+
+		for (i, file) in std::mem::take(&mut self.files).into_iter().enumerate() {
+			if !keep(&file) {
+				removed.push(file);
+				continue;
+			}
+
+			if original == Some(i) {
+				self.original = Some(kept.len());
+			}
+
+			if canonical == Some(i) {
+				self.canonical = Some(kept.len());
+			}
+
+			kept.push(file);
+			roots.push(self.roots.get_mut(i).and_then(Option::take));
+			metadata.push(self.metadata.get_mut(i).and_then(Option::take));
+		}
+
+		self.files = kept;
+		self.roots = roots;
+		self.metadata = metadata;
+
+		removed
+	}
+}
+
+/// Returns whether a line of fdupes or jdupes output gives the size of the
+/// files in the following group, e.g. "1024 bytes each:".
+fn is_fdupes_size_line(line: &str) -> bool {
+	match line.split_once(' ') {
+		Some((size, rest)) => {
+			size.chars().all(|c| c.is_ascii_digit()) && (rest == "byte each:" || rest == "bytes each:")
+		}
+		None => false,
 	}
 }
+
+/// A file going through the comparison pipeline.
+struct Candidate {
+	/// The path to the file.
+	path: FilePath,
+
+	/// The index of the existing group this file belongs to, if any.
+	group: Option<usize>,
+}
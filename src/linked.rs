@@ -0,0 +1,164 @@
+use crate::extents::{shares_all_storage, Spans};
+use crate::utilities::file_id;
+use crate::{DupGroup, DupResults};
+
+impl DupResults {
+	/// Returns the duplicate file groups that can be acted on to reclaim
+	/// space, leaving out groups that are already deduplicated.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use std::path::PathBuf;
+	///
+	/// let dirs = vec![PathBuf::from("foo")];
+	/// let mut dup_result = dupcheck::DupResults::new();
+	///
+	/// if let Err(dup_error) = dup_result.within(&dirs) {
+	///     // Error handling
+	/// }
+	///
+	/// for group in dup_result.actionable() {
+	///     println!("{} bytes reclaimable", group.reclaimable_bytes());
+	/// }
+	///
+	/// for group in dup_result.already_deduplicated() {
+	///     println!("{} is already deduplicated", group.get_hash());
+	/// }
+	/// ```
+	pub fn actionable(&self) -> Vec<&DupGroup> {
+		self
+			.duplicates
+			.iter()
+			.filter(|g| !g.is_already_deduplicated())
+			.collect()
+	}
+
+	/// Returns the duplicate file groups whose files are all hard links to
+	/// the same file or already share all of their storage, so no space can
+	/// be reclaimed from them.
+	pub fn already_deduplicated(&self) -> Vec<&DupGroup> {
+		self
+			.duplicates
+			.iter()
+			.filter(|g| g.is_already_deduplicated())
+			.collect()
+	}
+
+	/// Returns the total number of bytes that could be reclaimed from all of
+	/// the duplicate file groups, as given by `DupGroup::reclaimable_bytes()`.
+	pub fn reclaimable_bytes(&self) -> u64 {
+		self.duplicates.iter().map(|g| g.reclaimable_bytes()).sum()
+	}
+}
+
+impl DupGroup {
+	/// Returns whether all of the group's files, including any queried
+	/// files, are hard links to the same file or are already reflinked, as
+	/// given by `is_already_reflinked()`, with no baseline files, so no space
+	/// can be reclaimed from it.
+	///
+	/// Groups with files whose metadata can't be read, or on platforms where
+	/// hard links can't be identified, aren't considered deduplicated.
+	pub fn is_already_deduplicated(&self) -> bool {
+		self.baseline.is_empty() && (self.is_hard_linked() || self.is_already_reflinked())
+	}
+
+	/// Returns whether the group's files, including any queried files,
+	/// aren't all hard links to the same file but already share all of their
+	/// storage, such as copies reflinked to each other on Btrfs or XFS, with
+	/// no baseline files.  Reflink actions leave these groups unchanged.
+	///
+	/// This is only found on Linux, from the files' physical extents on
+	/// filesystems that report them.  Groups of empty files aren't
+	/// considered reflinked.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use std::path::PathBuf;
+	///
+	/// let dirs = vec![PathBuf::from("foo")];
+	/// let mut dup_result = dupcheck::DupResults::new();
+	///
+	/// if let Err(dup_error) = dup_result.within(&dirs) {
+	///     // Error handling
+	/// }
+	///
+	/// for group in dup_result.already_deduplicated() {
+	///     if group.is_already_reflinked() {
+	///         println!("{} is already reflinked", group.get_hash());
+	///     }
+	/// }
+	/// ```
+	pub fn is_already_reflinked(&self) -> bool {
+		if !self.baseline.is_empty() || self.is_hard_linked() {
+			return false;
+		}
+
+		let mut files = self.files.iter().chain(&self.queried);
+
+		match files.next() {
+			Some(first) => files.all(|f| shares_all_storage(first, f)),
+			None => false,
+		}
+	}
+
+	/// Returns whether all of the group's files, including any queried
+	/// files, are hard links to the same file.
+	fn is_hard_linked(&self) -> bool {
+		let mut ids = self
+			.files
+			.iter()
+			.chain(&self.queried)
+			.map(|f| f.metadata().ok().as_ref().and_then(file_id));
+
+		match ids.next() {
+			Some(Some(first)) => ids.all(|id| id == Some(first)),
+			_ => false,
+		}
+	}
+
+	/// Returns the number of bytes that could be reclaimed by replacing all
+	/// but one of the group's files, including any queried files, keeping
+	/// its canonical copy or otherwise its first file.
+	///
+	/// Storage that the files already share isn't counted, so this is the
+	/// space that would be freed on disk.  Files that are hard links to
+	/// another file in the group share all of their storage.  On Linux, the
+	/// files' physical extents are compared where the filesystem reports
+	/// them, so reflinked copies sharing some or all of their data are also
+	/// accounted for.  Sizes are the space allocated to the files, which may
+	/// differ from their lengths.
+	///
+	/// Files whose metadata can't be read aren't counted, and if the file to
+	/// keep can't be read, the first file that can be is kept instead.
+	pub fn reclaimable_bytes(&self) -> u64 {
+		let keep = self.canonical_index();
+		let mut files: Vec<(usize, Spans)> = vec![];
+
+		for (i, file) in self.files.iter().chain(&self.queried).enumerate() {
+			let mut spans = Spans::new();
+
+			if spans.add_file(file).is_ok() {
+				files.push((i, spans));
+			}
+		}
+
+		let kept = match files.iter().position(|(i, _)| *i == keep) {
+			Some(k) => files.remove(k).1,
+			None if !files.is_empty() => files.remove(0).1,
+			None => return 0,
+		};
+
+		// Storage shared by several of the files to remove is only freed
+		// once.
+		let mut removed = Spans::new();
+
+		for (_, spans) in files {
+			removed.extend(spans);
+		}
+
+		removed.len_excluding(&kept)
+	}
+}
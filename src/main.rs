@@ -1,99 +1,2072 @@
-use clap::{arg, command, ArgGroup, Values};
+mod messages;
+
+use crate::messages::{tr, trn, Locale};
+use clap::{arg, command, ArgGroup, Command, PossibleValue, Values};
+use std::collections::HashSet;
+use std::env;
+use std::ffi::OsString;
+use std::fs;
+use std::fs::File;
 use std::io;
-use std::path::PathBuf;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::Duration;
 
 fn values_to_paths(values: Option<Values>) -> Vec<PathBuf> {
 	match values {
-		Some(v) => v.map(|p| PathBuf::from(p)).collect::<Vec<PathBuf>>(),
+		Some(v) => v.map(PathBuf::from).collect::<Vec<PathBuf>>(),
+		None => vec![],
+	}
+}
+
+fn values_to_strings(values: Option<Values>) -> Vec<String> {
+	match values {
+		Some(v) => v.map(String::from).collect::<Vec<String>>(),
 		None => vec![],
 	}
 }
 
-fn get_dup_result(files: &[PathBuf], dirs: &[PathBuf]) -> io::Result<dupcheck::DupResults> {
-	let mut dup_result = dupcheck::DupResults::new();
+fn get_dup_result(
+	files: &[PathBuf],
+	dirs: &[PathBuf],
+	baseline: Option<&str>,
+	loaded: Option<&str>,
+	options: dupcheck::DupOptions,
+) -> io::Result<dupcheck::DupResults> {
+	let mut dup_result = dupcheck::DupResults::with_options(options);
 
-	if files.is_empty() {
-		dup_result.within(&dirs)?;
+	if let Some(results_path) = loaded {
+		if !dup_result.read_binary(BufReader::new(File::open(results_path)?))? {
+			eprintln!(
+				"{}",
+				tr!(
+					"Warning: {0} is incomplete; only the duplicates saved before its check stopped are included.",
+					results_path
+				)
+			);
+		}
+	} else if let Some(manifest_path) = baseline {
+		let manifest = dupcheck::Manifest::from_file(manifest_path)?;
+		dup_result.against(&manifest, dirs)?;
+	} else if files.is_empty() {
+		dup_result.within(dirs)?;
 	} else {
 		let dirs_opt = match dirs.is_empty() {
 			true => None,
-			false => Some(&dirs[..]),
+			false => Some(dirs),
 		};
-		dup_result.of(&files, dirs_opt)?;
+		dup_result.of(files, dirs_opt)?;
 	}
 
 	Ok(dup_result)
 }
 
-fn print_duplicates(dup_list: &dupcheck::DupGroup) {
-	println!();
-	println!("Duplicates of file {}:", dup_list.get_hash());
-	for file in dup_list.get_files() {
-		println!("{}", file.display());
+fn write_manifest(dup_results: &dupcheck::DupResults, path: &str) -> io::Result<()> {
+	let mut writer = BufWriter::new(File::create(path)?);
+	dup_results.write_manifest(&mut writer)
+}
+
+fn read_previous_results(path: &str, binary: bool) -> io::Result<dupcheck::Manifest> {
+	let read = match binary {
+		true => File::open(path).and_then(|file| {
+			let mut previous = dupcheck::DupResults::new();
+			previous.read_binary(BufReader::new(file))?;
+			Ok(previous.duplicates_manifest())
+		}),
+		false => dupcheck::Manifest::from_file(path),
+	};
+
+	// There are no previous results before the first check.
+	match read {
+		Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(dupcheck::Manifest::new()),
+		result => result,
 	}
 }
 
-fn main() {
-	let matches = command!()
-		.arg(
-			arg!(-o --of <files> "Files to check.")
-				.required(false)
-				.multiple_values(true),
-		)
-		.arg(
-			arg!(-w --within <directories> "Directories to check.")
-				.required(false)
-				.multiple_values(true),
+fn read_saved_results(
+	path: &str,
+	binary: bool,
+	options: dupcheck::DupOptions,
+) -> io::Result<dupcheck::DupResults> {
+	let mut dup_results = dupcheck::DupResults::with_options(options);
+
+	if binary {
+		dup_results.read_binary(BufReader::new(File::open(path)?))?;
+		return Ok(dup_results);
+	}
+
+	let manifest = dupcheck::Manifest::from_file(path)?;
+	let mut hashes = HashSet::new();
+
+	for (hash, _) in manifest.entries() {
+		if hashes.insert(hash) {
+			dup_results.add_known_group(hash, &manifest.files_with_hash(hash));
+		}
+	}
+
+	Ok(dup_results)
+}
+
+fn save_results(dup_results: &dupcheck::DupResults, path: &str, binary: bool) -> io::Result<()> {
+	let mut writer = BufWriter::new(File::create(path)?);
+
+	match binary {
+		true => dup_results.write_binary(writer),
+		false => dup_results.duplicates_manifest().write(&mut writer),
+	}
+}
+
+fn read_hash_cache(path: &Path) -> io::Result<dupcheck::HashCache> {
+	// There's no cache before the first check that uses it.
+	match File::open(path) {
+		Ok(file) => dupcheck::HashCache::read_binary(BufReader::new(file)),
+		Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(dupcheck::HashCache::new()),
+		Err(e) => Err(e),
+	}
+}
+
+fn save_results_as_found(
+	path: &str,
+) -> (
+	mpsc::Sender<dupcheck::DupGroup>,
+	thread::JoinHandle<io::Result<()>>,
+) {
+	let (sender, receiver) = mpsc::channel();
+	let path = path.to_string();
+	let handle = thread::spawn(move || {
+		let mut writer = dupcheck::ResultsWriter::new(BufWriter::new(File::create(path)?))?;
+
+		for group in receiver {
+			writer.write_group(&group)?;
+		}
+
+		Ok(())
+	});
+
+	(sender, handle)
+}
+
+fn stream_events(
+	mut output: Output,
+) -> (
+	mpsc::Sender<dupcheck::ScanEvent>,
+	thread::JoinHandle<(Output, io::Result<()>)>,
+) {
+	let (sender, receiver) = mpsc::channel::<dupcheck::ScanEvent>();
+	let handle = thread::spawn(move || {
+		// Writing stops at the first error, such as a closed pipe.
+		let written = receiver
+			.into_iter()
+			.try_for_each(|event| writeln!(output, "{}", event.to_json()));
+
+		(output, written)
+	});
+
+	(sender, handle)
+}
+
+fn save_hash_cache(cache: &dupcheck::HashCache, path: &Path) -> io::Result<()> {
+	cache.write_binary(BufWriter::new(File::create(path)?))
+}
+
+fn write_rmlint_json(dup_results: &dupcheck::DupResults, path: &str) -> io::Result<()> {
+	let mut writer = BufWriter::new(File::create(path)?);
+	dup_results.write_rmlint_json(&mut writer)
+}
+
+fn write_sql(dup_results: &dupcheck::DupResults, path: &str) -> io::Result<()> {
+	let mut writer = BufWriter::new(File::create(path)?);
+	dup_results.write_sql(&mut writer)
+}
+
+fn read_hashes(path: &str) -> io::Result<Vec<String>> {
+	let reader = BufReader::new(File::open(path)?);
+	let mut hashes = vec![];
+
+	// Lines may be bare hashes or b3sum manifest entries.
+	for line in reader.lines() {
+		if let Some(hash) = line?.split_whitespace().next() {
+			if !hash.starts_with('#') {
+				hashes.push(hash.to_string());
+			}
+		}
+	}
+
+	Ok(hashes)
+}
+
+fn default_config_path() -> Option<PathBuf> {
+	if let Some(dir) = env::var_os("XDG_CONFIG_HOME").filter(|d| !d.is_empty()) {
+		return Some(PathBuf::from(dir).join("dupcheck").join("config"));
+	}
+
+	if let Some(dir) = env::var_os("APPDATA") {
+		return Some(PathBuf::from(dir).join("dupcheck").join("config"));
+	}
+
+	env::var_os("HOME").map(|home| PathBuf::from(home).join(".config/dupcheck/config"))
+}
+
+fn default_cache_path() -> Option<PathBuf> {
+	if let Some(dir) = env::var_os("XDG_CACHE_HOME").filter(|d| !d.is_empty()) {
+		return Some(PathBuf::from(dir).join("dupcheck").join("hashes"));
+	}
+
+	if let Some(dir) = env::var_os("LOCALAPPDATA") {
+		return Some(PathBuf::from(dir).join("dupcheck").join("hashes"));
+	}
+
+	env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache/dupcheck/hashes"))
+}
+
+fn default_ignore_path() -> Option<PathBuf> {
+	default_config_path().map(|config| config.with_file_name("ignored"))
+}
+
+fn open_ignore_store(path: Option<&str>) -> Option<dupcheck::IgnoreStore> {
+	let path = path.map(PathBuf::from).or_else(default_ignore_path)?;
+
+	match dupcheck::IgnoreStore::open(&path) {
+		Ok(store) => Some(store),
+		Err(store_error) => {
+			eprintln!(
+				"{}",
+				tr!("Error reading {0}: {1}", path.display(), store_error)
+			);
+			process::exit(1);
+		}
+	}
+}
+
+fn update_ignore_store(path: Option<&str>, dismiss: &[String], restore: &[String]) {
+	let mut store = match open_ignore_store(path) {
+		Some(s) => s,
+		None => {
+			eprintln!(
+				"{}",
+				tr!("Error: no location for the ignore store; use --ignore-store")
+			);
+			process::exit(1);
+		}
+	};
+	let dismissed = dismiss.iter().filter(|h| store.dismiss(h)).count();
+	let restored = restore.iter().filter(|h| store.restore(h)).count();
+
+	if let Err(store_error) = store.save() {
+		eprintln!(
+			"{}",
+			tr!(
+				"Error writing {0}: {1}",
+				store.path().display(),
+				store_error
+			)
+		);
+		process::exit(1);
+	}
+
+	println!(
+		"{}",
+		trn!(
+			dismissed,
+			"{0} group dismissed, {1} restored.",
+			"{0} groups dismissed, {1} restored.",
+			dismissed,
+			restored
 		)
-		.group(
-			ArgGroup::new("methods")
-				.args(&["of", "within"])
-				.required(true)
-				.multiple(true),
+	);
+}
+
+fn load_profile(config_path: Option<&str>, name: &str) -> dupcheck::Profile {
+	let path = match config_path.map(PathBuf::from).or_else(default_config_path) {
+		Some(p) => p,
+		None => {
+			eprintln!("{}", tr!("Error: no config file found"));
+			process::exit(1);
+		}
+	};
+	let config = match dupcheck::Config::from_file(&path) {
+		Ok(c) => c,
+		Err(config_error) => {
+			eprintln!(
+				"{}",
+				tr!("Error reading {0}: {1}", path.display(), config_error)
+			);
+			process::exit(1);
+		}
+	};
+
+	match config.profile(name) {
+		Some(profile) => profile.clone(),
+		None => {
+			eprintln!(
+				"{}",
+				tr!("Error: no profile named {0} in {1}", name, path.display())
+			);
+			process::exit(1);
+		}
+	}
+}
+
+fn verify_manifest(path: &str) {
+	let manifest = match dupcheck::Manifest::from_file(path) {
+		Ok(m) => m,
+		Err(manifest_error) => {
+			eprintln!("{}", tr!("Error: {0}", manifest_error));
+			process::exit(1);
+		}
+	};
+	let results = manifest.verify(&dupcheck::DupOptions::new());
+
+	println!(
+		"{}",
+		tr!(
+			"{0} of {1} files verified.",
+			results.verified().len(),
+			manifest.len()
 		)
-		.after_help(
-			"Use both --of and --within to check the given directories \
-                    for duplicates of the given files.  If only --of is used, \
-                    the files' parent directories will be checked.  If only \
-                    --within is used, the directories will be checked for any \
-                    duplicate files.",
+	);
+
+	for file in results.mismatched() {
+		println!("{}", tr!("{0} (mismatched)", file.display()));
+	}
+
+	for file in results.missing() {
+		println!("{}", tr!("{0} (missing)", file.display()));
+	}
+
+	for verify_error in results.errors() {
+		println!("{}", tr!("{0} (error)", verify_error));
+	}
+
+	if !results.is_ok() {
+		process::exit(1);
+	}
+}
+
+fn verify_results(options: dupcheck::DupOptions, path: &str, binary: bool) {
+	let dup_results = match read_saved_results(path, binary, options) {
+		Ok(r) => r,
+		Err(results_error) => {
+			eprintln!("{}", tr!("Error reading results: {0}", results_error));
+			process::exit(1);
+		}
+	};
+	let groups = dup_results.duplicates();
+	let results = dup_results.verify();
+	let stale = results.iter().filter(|r| !r.is_ok()).count();
+
+	println!(
+		"{}",
+		tr!(
+			"{0} of {1} groups verified.",
+			groups.len() - stale,
+			groups.len()
 		)
-		.get_matches();
+	);
 
-	let files = values_to_paths(matches.values_of("of"));
-	let dirs = values_to_paths(matches.values_of("within"));
+	for (group, group_results) in groups.iter().zip(&results) {
+		if group_results.is_ok() {
+			continue;
+		}
+
+		println!();
+		println!(
+			"{}",
+			tr!(
+				"Group {0} no longer holds ({1} of {2} files verified):",
+				group.get_hash(),
+				group_results.verified().len(),
+				group.get_files().len()
+			)
+		);
+
+		for line in unverified_files(group_results) {
+			println!("{}", line);
+		}
+	}
+
+	if stale > 0 {
+		process::exit(1);
+	}
+}
+
+/// Removes groups whose files have changed since they were saved from
+/// loaded results, so they aren't acted on.  What's skipped is printed to
+/// stderr, so scripts printed to stdout can still be run.
+fn skip_stale_groups(dup_results: &mut dupcheck::DupResults) {
+	let mut stale = HashSet::new();
+
+	for (group, group_results) in dup_results.duplicates().iter().zip(dup_results.verify()) {
+		if group_results.is_ok() {
+			continue;
+		}
+
+		eprintln!(
+			"{}",
+			tr!(
+				"Skipping group {0}, since its files have changed since it was saved:",
+				group.get_hash()
+			)
+		);
+
+		for line in unverified_files(&group_results) {
+			eprintln!("{}", line);
+		}
+
+		eprintln!();
+		stale.insert(group.get_hash());
+	}
+
+	dup_results.retain(|g| !stale.contains(&g.get_hash()));
+}
+
+fn unverified_files(results: &dupcheck::VerifyResults) -> Vec<String> {
+	let mismatched = results
+		.mismatched()
+		.iter()
+		.map(|f| tr!("{0} (mismatched)", f.display()));
+	let missing = results
+		.missing()
+		.iter()
+		.map(|f| tr!("{0} (missing)", f.display()));
+	let errors = results.errors().iter().map(|e| tr!("{0} (error)", e));
+
+	mismatched.chain(missing).chain(errors).collect()
+}
+
+#[cfg(unix)]
+fn run_daemon(options: dupcheck::DupOptions, socket_path: &str, config_path: Option<&str>) {
+	let mut daemon = dupcheck::Daemon::new(options);
+
+	// Profiles with schedules are checked if the config file exists, and
+	// their results are saved alongside it.
+	if let Some(path) = config_path.map(PathBuf::from).or_else(default_config_path) {
+		if config_path.is_some() || path.exists() {
+			let config = match dupcheck::Config::from_file(&path) {
+				Ok(c) => c,
+				Err(config_error) => {
+					eprintln!(
+						"{}",
+						tr!("Error reading {0}: {1}", path.display(), config_error)
+					);
+					process::exit(1);
+				}
+			};
+			let results_dir = path.with_file_name("results");
+
+			if let Err(schedule_error) = daemon.schedule(config.profiles(), &results_dir) {
+				eprintln!("{}", tr!("Error scheduling checks: {0}", schedule_error));
+				process::exit(1);
+			}
+		}
+	}
+
+	if let Err(daemon_error) = daemon.listen(socket_path) {
+		eprintln!("{}", tr!("Error running daemon: {0}", daemon_error));
+		process::exit(1);
+	}
+}
+
+#[cfg(not(unix))]
+fn run_daemon(_options: dupcheck::DupOptions, _socket_path: &str, _config_path: Option<&str>) {
+	eprintln!("{}", tr!("Error: daemon mode is only supported on Unix"));
+	process::exit(1);
+}
 
-	let dup_result = get_dup_result(&files, &dirs);
+fn run_server(options: dupcheck::DupOptions, address: &str) {
+	let server = dupcheck::Server::new(options);
+	println!("{}", tr!("Listening on http://{0}", address));
 
-	if let Ok(dup_results) = dup_result {
-		let file_count = dup_results.file_count();
-		let group_count = dup_results.duplicates().len();
-		let dup_errors = dup_results.errors();
-		let dup_error_count = dup_errors.len();
+	if let Err(server_error) = server.listen(address) {
+		eprintln!("{}", tr!("Error running server: {0}", server_error));
+		process::exit(1);
+	}
+}
+
+fn print_script(
+	output: Option<&str>,
+	dup_results: &dupcheck::DupResults,
+	format: dupcheck::ScriptFormat,
+	keep: &dupcheck::KeepPolicy,
+	action: dupcheck::Action,
+) {
+	let mut output = open_output(output);
+	let written = dup_results.write_script(&mut output, format, keep, action);
+	finish_output(output, written);
+
+	for dup_error in dup_results.errors() {
+		eprintln!("{}", dup_error);
+	}
+}
+
+fn apply_action(
+	dup_results: &dupcheck::DupResults,
+	keep: &dupcheck::KeepPolicy,
+	action: dupcheck::Action,
+) {
+	let results = dup_results.apply_actions(keep, action.clone());
+
+	for (action, file, kept) in results.completed() {
+		match action {
+			dupcheck::Action::MoveTo(dir) => {
+				println!("{}", tr!("Moved {0} to {1}", file.display(), dir.display()))
+			}
+			dupcheck::Action::Trash => println!("{}", tr!("Trashed {0}", file.display())),
+			dupcheck::Action::Delete => println!("{}", tr!("Deleted {0}", file.display())),
+			dupcheck::Action::Hardlink => {
+				println!(
+					"{}",
+					tr!("Hard linked {0} to {1}", file.display(), kept.display())
+				)
+			}
+			dupcheck::Action::Symlink | dupcheck::Action::RelativeSymlink => {
+				println!(
+					"{}",
+					tr!("Symlinked {0} to {1}", file.display(), kept.display())
+				)
+			}
+			dupcheck::Action::Reflink => {
+				println!(
+					"{}",
+					tr!("Reflinked {0} to {1}", file.display(), kept.display())
+				)
+			}
+		};
+	}
+
+	for warning in results.warnings() {
+		println!("{}", tr!("Warning: {0} not changed", warning));
+	}
 
+	for conflict in results.conflicts() {
+		println!("{}", tr!("Conflict: {0}", conflict));
+	}
+
+	let completed_count = results.completed().len();
+	let errors: Vec<String> = dup_results
+		.errors()
+		.iter()
+		.map(|e| e.to_string())
+		.chain(results.errors().iter().map(|e| e.to_string()))
+		.collect();
+
+	let (singular, plural) = match action {
+		dupcheck::Action::Delete => (
+			"{0} file deleted, {1} bytes reclaimed.",
+			"{0} files deleted, {1} bytes reclaimed.",
+		),
+		dupcheck::Action::MoveTo(_) => (
+			"{0} file moved, {1} bytes reclaimed.",
+			"{0} files moved, {1} bytes reclaimed.",
+		),
+		dupcheck::Action::Trash => (
+			"{0} file trashed, {1} bytes reclaimed.",
+			"{0} files trashed, {1} bytes reclaimed.",
+		),
+		_ => (
+			"{0} file replaced, {1} bytes reclaimed.",
+			"{0} files replaced, {1} bytes reclaimed.",
+		),
+	};
+
+	println!();
+	println!(
+		"{}",
+		trn!(
+			completed_count,
+			singular,
+			plural,
+			completed_count,
+			results.bytes_reclaimed()
+		)
+	);
+
+	if !errors.is_empty() {
+		println!();
 		println!(
-			"{} files found in {} group{}.",
-			file_count,
-			group_count,
-			if group_count != 1 { "s" } else { "" }
+			"{}",
+			trn!(
+				errors.len(),
+				"{0} error occurred.",
+				"{0} errors occurred.",
+				errors.len()
+			)
 		);
 
-		for dup_group in dup_results.duplicates() {
-			print_duplicates(&dup_group);
+		for action_error in errors {
+			println!("{}", action_error);
 		}
+	}
+}
 
-		if dup_error_count > 0 {
-			println!(
-				"\n{} error{} occurred during check.",
-				dup_error_count,
-				if dup_error_count != 1 { "s" } else { "" }
+fn format_bytes(bytes: u64) -> String {
+	let units = ["B", "KiB", "MiB", "GiB", "TiB"];
+	let mut size = bytes as f64;
+	let mut unit = 0;
+
+	while size >= 1024.0 && unit < units.len() - 1 {
+		size /= 1024.0;
+		unit += 1;
+	}
+
+	match unit {
+		0 => format!("{} B", bytes),
+		_ => format!("{:.1} {}", size, units[unit]),
+	}
+}
+
+fn format_duration(duration: Duration) -> String {
+	let secs = duration.as_secs();
+
+	match secs {
+		0..=59 => format!("{}s", secs),
+		60..=3599 => format!("{}m {:02}s", secs / 60, secs % 60),
+		_ => format!("{}h {:02}m", secs / 3600, secs % 3600 / 60),
+	}
+}
+
+fn read_method_name(method: dupcheck::ReadMethod) -> &'static str {
+	match method {
+		dupcheck::ReadMethod::Buffered => "buffered",
+		dupcheck::ReadMethod::Mmap => "mmap",
+	}
+}
+
+fn print_benchmark(benchmark: &dupcheck::Benchmark) {
+	eprintln!(
+		"{}",
+		tr!(
+			"Timed hashing {0} with each read method:",
+			format_bytes(benchmark.bytes())
+		)
+	);
+
+	for method in [dupcheck::ReadMethod::Buffered, dupcheck::ReadMethod::Mmap] {
+		if let Some(throughput) = benchmark.throughput(method) {
+			eprintln!(
+				"{}: {}/s",
+				read_method_name(method),
+				format_bytes(throughput)
+			);
+		}
+	}
+}
+
+fn progress_line(status: &dupcheck::ScanStatus) -> String {
+	if status.phase() != dupcheck::ScanPhase::Comparing {
+		return tr!("Found {0} files", status.files_found());
+	}
+
+	let total = status.bytes_total().max(status.bytes_hashed());
+	let fraction = match total {
+		0 => 0.0,
+		t => status.bytes_hashed() as f64 / t as f64,
+	};
+	let filled = (fraction * 20.0) as usize;
+	let mut line = tr!(
+		"[{0}{1}] {2}% {3} of {4} ({5} of {6} files)",
+		"#".repeat(filled),
+		"-".repeat(20 - filled),
+		format!("{:.0}", fraction * 100.0),
+		format_bytes(status.bytes_hashed()),
+		format_bytes(total),
+		status.files_hashed(),
+		status.files_total().max(status.files_hashed())
+	);
+
+	if let Some(throughput) = status.throughput() {
+		line.push_str(&format!(", {}/s", format_bytes(throughput)));
+	}
+
+	if let Some(eta) = status.eta() {
+		line.push_str(&tr!(", {0} left", format_duration(eta)));
+	}
+
+	line
+}
+
+fn show_progress(progress: dupcheck::Progress) -> (mpsc::Sender<()>, thread::JoinHandle<()>) {
+	let (stop, stopped) = mpsc::channel();
+	let handle = thread::spawn(move || loop {
+		eprint!("\r\x1b[K{}", progress_line(&progress.status()));
+
+		if stopped.recv_timeout(Duration::from_millis(200)) != Err(mpsc::RecvTimeoutError::Timeout) {
+			eprint!("\r\x1b[K");
+			return;
+		}
+	});
+
+	(stop, handle)
+}
+
+/// Where results are printed: standard output, or a temporary file that
+/// replaces the file given to --output once all of the results are written.
+enum Output {
+	Stdout(io::Stdout),
+	File {
+		writer: BufWriter<File>,
+		temp: PathBuf,
+		path: PathBuf,
+	},
+}
+
+impl Write for Output {
+	fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+		match self {
+			Output::Stdout(stdout) => stdout.write(buf),
+			Output::File { writer, .. } => writer.write(buf),
+		}
+	}
+
+	fn flush(&mut self) -> io::Result<()> {
+		match self {
+			Output::Stdout(stdout) => stdout.flush(),
+			Output::File { writer, .. } => writer.flush(),
+		}
+	}
+}
+
+fn open_output(path: Option<&str>) -> Output {
+	let path = match path {
+		Some(p) => PathBuf::from(p),
+		None => return Output::Stdout(io::stdout()),
+	};
+	let mut name = OsString::from(".");
+	name.push(path.file_name().unwrap_or_default());
+	name.push(format!(".dupcheck-{}.tmp", process::id()));
+	let temp = path.with_file_name(name);
+
+	match File::create(&temp) {
+		Ok(file) => Output::File {
+			writer: BufWriter::new(file),
+			temp,
+			path,
+		},
+		Err(output_error) => {
+			eprintln!(
+				"{}",
+				tr!("Error writing {0}: {1}", path.display(), output_error)
 			);
+			process::exit(1);
+		}
+	}
+}
+
+/// Replaces the file given to --output with the results written, or leaves
+/// it unchanged if they couldn't all be written.
+fn finish_output(output: Output, written: io::Result<()>) {
+	let finished = match output {
+		Output::Stdout(mut stdout) => written.and_then(|_| stdout.flush()),
+		Output::File { writer, temp, path } => {
+			let replaced = written
+				.and_then(|_| writer.into_inner().map_err(|e| e.into_error()))
+				.and_then(|_| fs::rename(&temp, path));
 
-			for dup_error in dup_errors {
-				println!("{}", dup_error)
+			if replaced.is_err() {
+				let _ = fs::remove_file(&temp);
 			}
+
+			replaced
+		}
+	};
+
+	if let Err(output_error) = finished {
+		eprintln!("{}", tr!("Error writing results: {0}", output_error));
+		process::exit(1);
+	}
+}
+
+fn print_results(
+	out: &mut impl Write,
+	dup_results: &dupcheck::DupResults,
+	is_dismissed: &dyn Fn(&dupcheck::DupGroup) -> bool,
+	summary_only: bool,
+	stats: bool,
+	show_skipped: bool,
+) -> io::Result<()> {
+	let file_count = dup_results.file_count();
+	let group_count = dup_results.duplicates().len();
+	let dup_error_count = dup_results.errors().len();
+
+	writeln!(
+		out,
+		"{}",
+		trn!(
+			group_count,
+			"{0} files found in {1} group.",
+			"{0} files found in {1} groups.",
+			file_count,
+			group_count
+		)
+	)?;
+
+	if summary_only {
+		writeln!(
+			out,
+			"{}",
+			tr!("{0} bytes reclaimable.", dup_results.reclaimable_bytes())
+		)?;
+		writeln!(
+			out,
+			"{}",
+			trn!(
+				dup_error_count,
+				"{0} error occurred during check.",
+				"{0} errors occurred during check.",
+				dup_error_count
+			)
+		)?;
+
+		if stats {
+			print_stats(out, dup_results)?;
 		}
+
+		return Ok(());
+	}
+
+	for dup_group in dup_results.actionable() {
+		print_duplicates(out, dup_group, is_dismissed(dup_group))?;
+	}
+
+	let deduplicated = dup_results.already_deduplicated();
+
+	if !deduplicated.is_empty() {
+		writeln!(out)?;
+		writeln!(
+			out,
+			"{}",
+			trn!(
+				deduplicated.len(),
+				"{0} group already deduplicated by hard links or reflinks, 0 bytes reclaimable:",
+				"{0} groups already deduplicated by hard links or reflinks, 0 bytes reclaimable:",
+				deduplicated.len()
+			)
+		)?;
+
+		for dup_group in deduplicated {
+			print_duplicates(out, dup_group, is_dismissed(dup_group))?;
+		}
+	}
+
+	if !dup_results.blocked().is_empty() {
+		writeln!(out)?;
+		writeln!(out, "{}", tr!("Blocked files:"))?;
+
+		for (hash, file) in dup_results.blocked() {
+			writeln!(out, "{} ({})", file.display(), hash)?;
+		}
+	}
+
+	if show_skipped {
+		print_skipped(out, dup_results)?;
+	}
+
+	for similar_group in dup_results.similar() {
+		print_similar(out, similar_group)?;
+	}
+
+	if stats {
+		print_stats(out, dup_results)?;
+	}
+
+	if !dup_results.warnings().is_empty() {
+		writeln!(out)?;
+
+		for warning in dup_results.warnings() {
+			writeln!(out, "{}", tr!("Warning: {0}", warning))?;
+		}
+	}
+
+	Ok(())
+}
+
+fn print_check_errors(out: &mut impl Write, dup_results: &dupcheck::DupResults) -> io::Result<()> {
+	let dup_errors = dup_results.errors();
+
+	if !dup_errors.is_empty() {
+		writeln!(out)?;
+		writeln!(
+			out,
+			"{}",
+			trn!(
+				dup_errors.len(),
+				"{0} error occurred during check.",
+				"{0} errors occurred during check.",
+				dup_errors.len()
+			)
+		)?;
+
+		for dup_error in dup_errors {
+			writeln!(out, "{}", dup_error)?;
+		}
+	}
+
+	Ok(())
+}
+
+fn print_skipped(out: &mut impl Write, dup_results: &dupcheck::DupResults) -> io::Result<()> {
+	writeln!(out)?;
+	writeln!(out, "{}", tr!("Skipped files:"))?;
+
+	if dup_results.skipped().is_empty() {
+		writeln!(out, "{}", tr!("(none)"))?;
+	}
+
+	for (path, reason) in dup_results.skipped() {
+		writeln!(out, "{} ({})", path.display(), skip_reason(*reason))?;
+	}
+
+	Ok(())
+}
+
+fn skip_reason(reason: dupcheck::SkipReason) -> String {
+	match reason {
+		dupcheck::SkipReason::Junk => tr!("junk file"),
+		dupcheck::SkipReason::Placeholder => tr!("cloud storage placeholder"),
+		dupcheck::SkipReason::Signature => tr!("excluded signature"),
+		dupcheck::SkipReason::Size => tr!("no file of the same size"),
+		dupcheck::SkipReason::Filtered => tr!("excluded type"),
+		dupcheck::SkipReason::ExcludedDir => tr!("excluded directory"),
+		dupcheck::SkipReason::AlreadySearched => tr!("already searched"),
+		dupcheck::SkipReason::AlreadyChecked => tr!("already checked"),
+	}
+}
+
+fn explain(dup_results: &dupcheck::DupResults, path: &str) {
+	let explanation = match dup_results.explain(path) {
+		dupcheck::Explanation::Duplicate(hash) => {
+			tr!("{0} is a duplicate, in the group of file {1}.", path, hash)
+		}
+		dupcheck::Explanation::Error(message) => {
+			tr!("{0} isn't a duplicate, as it couldn't be read: {1}", path, message)
+		}
+		dupcheck::Explanation::Skipped(reason) => tr!(
+			"{0} isn't a duplicate, as it was skipped: {1}",
+			path,
+			skip_reason(reason)
+		),
+		dupcheck::Explanation::Unique(stage) => {
+			let unique = match stage {
+				dupcheck::Stage::Size => tr!("no other file has the same size"),
+				dupcheck::Stage::PrefixHash => tr!("no other file starts with the same bytes"),
+				dupcheck::Stage::FullHash => tr!("no other file has the same hash"),
+				dupcheck::Stage::ByteCompare => tr!("no other file has the same contents"),
+				dupcheck::Stage::NormalizedHash => tr!("no other file has the same normalized hash"),
+			};
+			tr!("{0} isn't a duplicate, as {1}.", path, unique)
+		}
+		dupcheck::Explanation::Unknown => tr!(
+			"{0} isn't a duplicate.  It wasn't found by the check, or it matched files in a group left out by the ignored hashes or group size thresholds.",
+			path
+		),
+	};
+
+	println!("{}", explanation);
+}
+
+fn print_similar(out: &mut impl Write, similar_group: &dupcheck::SimilarGroup) -> io::Result<()> {
+	writeln!(out)?;
+	writeln!(
+		out,
+		"{}",
+		tr!(
+			"Similar files ({0}% similarity):",
+			format!("{:.0}", similar_group.similarity() * 100.0)
+		)
+	)?;
+	for file in similar_group.get_files() {
+		writeln!(out, "{}", file.display())?;
+	}
+
+	Ok(())
+}
+
+fn print_stats(out: &mut impl Write, dup_results: &dupcheck::DupResults) -> io::Result<()> {
+	writeln!(out)?;
+	writeln!(out, "{}", tr!("Redundant files by extension:"))?;
+
+	for stats in dup_results.extension_stats() {
+		let extension = match stats.extension() {
+			"" => tr!("(none)"),
+			e => format!(".{}", e),
+		};
+		writeln!(
+			out,
+			"{}",
+			trn!(
+				stats.files(),
+				"{0}: {1} of {2} file redundant, {3} bytes",
+				"{0}: {1} of {2} files redundant, {3} bytes",
+				extension,
+				stats.redundant_files(),
+				stats.files(),
+				stats.redundant_bytes()
+			)
+		)?;
+	}
+
+	let root_stats = dup_results.root_stats();
+
+	if root_stats.len() > 1 {
+		writeln!(out)?;
+		writeln!(out, "{}", tr!("Duplicates by directory:"))?;
+
+		for stats in root_stats {
+			writeln!(
+				out,
+				"{}",
+				trn!(
+					stats.files(),
+					"{0}: {1} of {2} file duplicated, {3} redundant ({4} bytes), {5} shared with other directories",
+					"{0}: {1} of {2} files duplicated, {3} redundant ({4} bytes), {5} shared with other directories",
+					stats.root().display(),
+					stats.duplicate_files(),
+					stats.files(),
+					stats.redundant_files(),
+					stats.redundant_bytes(),
+					stats.cross_root_files()
+				)
+			)?;
+		}
+	}
+
+	let owner_stats = dup_results.owner_stats();
+
+	if !owner_stats.is_empty() {
+		writeln!(out)?;
+		writeln!(out, "{}", tr!("Duplicates by owner:"))?;
+
+		for stats in owner_stats {
+			writeln!(
+				out,
+				"{}",
+				trn!(
+					stats.files(),
+					"uid {0}: {1} file duplicated, {2} redundant ({3} bytes)",
+					"uid {0}: {1} files duplicated, {2} redundant ({3} bytes)",
+					stats.uid(),
+					stats.files(),
+					stats.redundant_files(),
+					stats.redundant_bytes()
+				)
+			)?;
+		}
+	}
+
+	Ok(())
+}
+
+fn print_duplicates(
+	out: &mut impl Write,
+	dup_list: &dupcheck::DupGroup,
+	dismissed: bool,
+) -> io::Result<()> {
+	writeln!(out)?;
+	let heading = match dismissed {
+		true => tr!("Duplicates of file {0} (dismissed):", dup_list.get_hash()),
+		false => tr!("Duplicates of file {0}:", dup_list.get_hash()),
+	};
+	writeln!(out, "{}", heading)?;
+	for file in dup_list.get_files() {
+		writeln!(out, "{}", file.display())?;
+	}
+	for file in dup_list.get_baseline_files() {
+		writeln!(out, "{}", tr!("{0} (baseline)", file.display()))?;
+	}
+	for file in dup_list.queried_files() {
+		writeln!(out, "{}", tr!("{0} (queried)", file.display()))?;
+	}
+
+	Ok(())
+}
+
+fn main() {
+	let matches = command!()
+		.arg(
+			arg!(-o --of <files> "Files to check.")
+				.required(false)
+				.multiple_values(true),
+		)
+		.arg(
+			arg!(-w --within <directories> "Directories to check.")
+				.required(false)
+				.multiple_values(true),
+		)
+		.arg(
+			arg!(-b --baseline <manifest> "Manifest to check the directories against.")
+				.required(false)
+				.requires("within")
+				.conflicts_with("of"),
+		)
+		.arg(
+			arg!(--"exclude-queried" "List the files given to --of apart from their copies.")
+				.requires("of")
+				.conflicts_with_all(&["script", "hardlink", "symlink", "reflink", "move-to", "trash"]),
+		)
+		.arg(arg!(--"skip-junk" "Skip system junk files such as .DS_Store and Thumbs.db."))
+		.arg(arg!(--"skip-placeholders" "Skip online-only cloud storage files rather than downloading them."))
+		.arg(
+			arg!(--"exclude-dir" <directory> "Skip a directory within the directories checked.")
+				.required(false)
+				.multiple_occurrences(true),
+		)
+		.arg(arg!(--"scan-virtual-filesystems" "Search /proc, /sys, /dev and /run, which are skipped by default (Linux only)."))
+		.arg(arg!(--fadvise "Advise the kernel not to keep hashed files in the page cache (Linux only)."))
+		.arg(
+			arg!(--"read-method" <method> "How files are read to hash them.  auto times each method on the files being checked and uses the fastest, but never chooses mmap.")
+				.required(false)
+				.possible_values(["buffered", "mmap", "auto"])
+				.default_value("buffered"),
+		)
+		.arg(
+			arg!(--"buffer-size" <bytes> "The number of bytes to read from a file at a time when hashing or comparing it.")
+				.required(false)
+				.validator(|n| match n.parse::<usize>() {
+					Ok(0) => Err(String::from("the buffer size must be at least 1 byte")),
+					Ok(_) => Ok(()),
+					Err(e) => Err(e.to_string()),
+				}),
+		)
+		.arg(arg!(--"show-read-method" "Print the read method used, and how fast each method was if it was chosen automatically."))
+		.arg(arg!(--"low-priority" "Check at a lower CPU and I/O priority than other programs."))
+		.arg(arg!(--sequential "Finish searching directories before hashing any files."))
+		.arg(
+			arg!(--walker <walker> "How to search directories.")
+				.required(false)
+				.possible_values(["recursive", "sorted", "parallel"]),
+		)
+		.arg(
+			arg!(-t --type <category> "Only check files in a category.")
+				.required(false)
+				.multiple_occurrences(true)
+				.possible_values([
+					PossibleValue::new("images").alias("image"),
+					PossibleValue::new("video").alias("videos"),
+					PossibleValue::new("audio"),
+					PossibleValue::new("documents").alias("document"),
+					PossibleValue::new("archives").alias("archive"),
+				]),
+		)
+		.arg(
+			arg!(--mime <type> "Only check files of a MIME type detected from their contents.")
+				.required(false)
+				.multiple_occurrences(true),
+		)
+		.arg(
+			arg!(--"exclude-mime" <type> "Skip files of a MIME type detected from their contents.")
+				.required(false)
+				.multiple_occurrences(true),
+		)
+		.arg(
+			arg!(--"exclude-signature" <hex> "Skip files starting with these bytes, given in hexadecimal.")
+				.required(false)
+				.multiple_occurrences(true)
+				.validator(|s| s.parse::<dupcheck::Signature>().map(|_| ())),
+		)
+		.arg(
+			arg!(--"ignore-exif" "Ignore metadata such as EXIF tags when comparing JPEG and PNG images."),
+		)
+		.arg(arg!(--"archive-contents" "Compare zip and tar archives by the names and contents of their members."))
+		.arg(arg!(--"gzip-contents" "Compare gzip files by their decompressed contents."))
+		.arg(arg!(--"ignore-encoding" "Compare UTF-8 and UTF-16 text files by their decoded text."))
+		.arg(arg!(--"ignore-line-endings" "Compare text files with CRLF and LF line endings as equal."))
+		.arg(
+			arg!(--"similar-texts" "Also check for text files with small differences.")
+				.requires("within"),
+		)
+		.arg(
+			arg!(--similarity <threshold> "The lowest similarity, from 0 to 1, of files reported as similar.")
+				.required(false)
+				.default_value("0.9")
+				.validator(|t| match t.parse::<f64>() {
+					Ok(n) if (0.0..=1.0).contains(&n) => Ok(()),
+					_ => Err("must be a number from 0 to 1"),
+				}),
+		)
+		.arg(
+			arg!(--"similar-videos" "Also check for re-encoded copies of videos (experimental; needs ffmpeg).")
+				.requires("within"),
+		)
+		.arg(
+			arg!(--"ignore-hashes" <file> "Leave out duplicates of files with BLAKE3 hashes listed in a file.")
+				.required(false),
+		)
+		.arg(
+			arg!(--"ignore-store" <file> "The file that dismissed groups are kept in.")
+				.required(false),
+		)
+		.arg(
+			arg!(--dismiss <hash> "Leave the groups with these hashes out of later results.")
+				.required(false)
+				.multiple_values(true)
+				.conflicts_with_all(&["of", "within", "verify", "profile", "daemon"]),
+		)
+		.arg(
+			arg!(--restore <hash> "Show dismissed groups with these hashes in results again.")
+				.required(false)
+				.multiple_values(true)
+				.conflicts_with_all(&["of", "within", "verify", "profile", "daemon"]),
+		)
+		.arg(arg!(--"show-ignored" "Include dismissed groups in the results."))
+		.arg(
+			arg!(--"min-group-size" <files> "Only report groups of at least this many files.")
+				.required(false)
+				.validator(|n| n.parse::<usize>().map(|_| ())),
+		)
+		.arg(
+			arg!(--"min-redundant-bytes" <bytes> "Only report groups with at least this many bytes reclaimable.")
+				.required(false)
+				.validator(|n| n.parse::<u64>().map(|_| ())),
+		)
+		.arg(
+			arg!(--blocklist <file> "Report files matching any BLAKE3 hash listed in a file.")
+				.required(false)
+				.requires("within"),
+		)
+		.arg(
+			arg!(--format <format> "The format to print the results in.  ndjson prints each event of the check as a line of JSON as it happens.")
+				.required(false)
+				.possible_values(["text", "ndjson"])
+				.default_value("text")
+				.conflicts_with_all(&[
+					"script",
+					"hardlink",
+					"symlink",
+					"reflink",
+					"move-to",
+					"trash",
+					"load-results",
+					"stats",
+					"summary-only",
+				]),
+		)
+		.arg(
+			arg!(--output <file> "Write the results or script to a file, which is only replaced once they're complete, instead of printing them.")
+				.required(false)
+				.conflicts_with_all(&["hardlink", "symlink", "reflink", "move-to", "trash"]),
+		)
+		.arg(
+			arg!(--lang <locale> "The language to print messages in, such as de.  Defaults to the language set by LC_ALL, LC_MESSAGES or LANG.")
+				.required(false)
+				.global(true)
+				.validator(|l| l.parse::<Locale>().map(|_| ())),
+		)
+		.arg(
+			arg!(--"format-template" <template> "Print a line for each duplicate file in the given format, such as '{hash}\\t{size}\\t{path}'.")
+				.required(false)
+				.validator(|t| t.parse::<dupcheck::Template>().map(|_| ()))
+				.conflicts_with_all(&[
+					"format",
+					"script",
+					"hardlink",
+					"symlink",
+					"reflink",
+					"move-to",
+					"trash",
+					"stats",
+					"summary-only",
+				]),
+		)
+		.arg(arg!(--stats "Print a summary of the duplicates by file extension."))
+		.arg(arg!(--"summary-only" "Print only the numbers of duplicates, reclaimable bytes and errors."))
+		.arg(arg!(--ownership "Record the owners of duplicates, and summarise them with --stats (Unix only)."))
+		.arg(
+			arg!(--"show-skipped" "List the files and directories that weren't checked, and why.")
+				.conflicts_with_all(&["format", "format-template", "summary-only", "load-results"]),
+		)
+		.arg(
+			arg!(--explainable "Record why files aren't duplicates in binary results saved by --save-results, for explain with --load-results.")
+				.requires("save-results"),
+		)
+		.arg(arg!(--progress "Show the progress of the check on standard error."))
+		.arg(
+			arg!(--prescan "Read the sizes of all files before comparing them, for more accurate progress.")
+				.requires("progress"),
+		)
+		.arg(
+			arg!(--webhook <url> "POST a JSON summary of the results to a URL when the check finishes.")
+				.required(false),
+		)
+		.arg(
+			arg!(--"save-results" <file> "Write the duplicates found to a file, for use with --new-since.")
+				.required(false),
+		)
+		.arg(
+			arg!(--"new-since" <file> "Only report duplicates that aren't in results saved by --save-results.")
+				.required(false),
+		)
+		.arg(
+			arg!(--"results-format" <format> "The format of the files given to --save-results and --new-since.")
+				.required(false)
+				.possible_values(["manifest", "binary"])
+				.default_value("manifest"),
+		)
+		.arg(
+			arg!(--"load-results" <file> "Report or act on duplicates saved by --save-results with --results-format binary instead of checking.")
+				.required(false)
+				.conflicts_with_all(&["of", "within", "baseline", "profile", "save-results"]),
+		)
+		.arg(
+			arg!(--"hash-cache" <file> "Keep the hashes of checked files in a file, so unchanged files aren't read again.  Without a file, the default location is used.")
+				.required(false)
+				.min_values(0)
+				.max_values(1),
+		)
+		.arg(
+			arg!(--wait "Wait for other dupcheck processes using the same hash cache to finish, rather than exiting.")
+				.requires("hash-cache"),
+		)
+		.arg(arg!(-m --manifest <file> "Write a BLAKE3 manifest of all checked files.").required(false))
+		.arg(arg!(--rmlint <file> "Write the results in rmlint's JSON format.").required(false))
+		.arg(arg!(--sql <file> "Export the results as SQL statements that add them to a SQLite database.").required(false))
+		.arg(
+			arg!(--script <format> "Print a script to act on the duplicates instead.")
+				.required(false)
+				.possible_values(["sh", "powershell"]),
+		)
+		.arg(
+			arg!(--"script-action" <action> "The action the script takes on duplicates.")
+				.required(false)
+				.possible_values([
+					"delete",
+					"hardlink",
+					"symlink",
+					"relative-symlink",
+					"reflink",
+				])
+				.default_value("delete")
+				.requires("script"),
+		)
+		.arg(
+			arg!(-k --keep <policy> "Which file in each group to keep.")
+				.required(false)
+				.possible_values(["first", "oldest", "newest", "shortest", "longest"])
+				.default_value("first"),
+		)
+		.arg(
+			arg!(--"keep-rule" <rule> "A rule for choosing which file in each group to keep.")
+				.required(false)
+				.multiple_occurrences(true)
+				.validator(|r| r.parse::<dupcheck::KeepRule>().map(|_| ())),
+		)
+		.arg(
+			arg!(--hardlink "Replace duplicates with hard links to the kept file.")
+				.conflicts_with_all(&["script", "baseline"]),
+		)
+		.arg(
+			arg!(--symlink "Replace duplicates with symbolic links to the kept file.")
+				.conflicts_with_all(&["script", "baseline", "hardlink"]),
+		)
+		.arg(arg!(--relative "Use relative paths for symbolic links.").requires("symlink"))
+		.arg(
+			arg!(--reflink "Replace duplicates with copy-on-write clones of the kept file.")
+				.conflicts_with_all(&["script", "baseline", "hardlink", "symlink"]),
+		)
+		.arg(
+			arg!(--"move-to" <directory> "Move duplicates into a quarantine directory.")
+				.required(false)
+				.conflicts_with_all(&["baseline", "hardlink", "symlink", "reflink"]),
+		)
+		.arg(
+			arg!(--trash "Move duplicates to the trash or recycle bin.").conflicts_with_all(&[
+				"script", "baseline", "hardlink", "symlink", "reflink", "move-to",
+			]),
+		)
+		.arg(arg!(-p --profile <name> "Check using a named profile from the config file.").required(false))
+		.arg(
+			arg!(-c --config <file> "The config file to read profiles from.").required(false),
+		)
+		.arg(
+			arg!(--daemon <socket> "Keep running and check directories requested over a Unix socket.")
+				.required(false)
+				.conflicts_with_all(&[
+					"of",
+					"within",
+					"baseline",
+					"manifest",
+					"rmlint",
+					"sql",
+					"script",
+					"hardlink",
+					"symlink",
+					"reflink",
+					"move-to",
+					"trash",
+					"similar-videos",
+					"similar-texts",
+					"blocklist",
+					"stats",
+					"summary-only",
+					"webhook",
+					"progress",
+					"save-results",
+					"new-since",
+				]),
+		)
+		.arg(
+			arg!(-v --verify <manifest> "Verify files against a BLAKE3 manifest.")
+				.required(false)
+				.conflicts_with_all(&[
+					"of",
+					"within",
+					"baseline",
+					"manifest",
+					"rmlint",
+					"sql",
+					"script",
+					"hardlink",
+					"symlink",
+					"reflink",
+					"move-to",
+					"skip-junk",
+					"skip-placeholders",
+					"exclude-dir",
+					"scan-virtual-filesystems",
+					"type",
+					"mime",
+					"exclude-mime",
+					"exclude-signature",
+					"ignore-exif",
+					"archive-contents",
+					"gzip-contents",
+					"ignore-encoding",
+					"ignore-line-endings",
+					"similar-videos",
+					"similar-texts",
+					"blocklist",
+					"ignore-hashes",
+					"profile",
+					"stats",
+					"summary-only",
+					"min-group-size",
+					"min-redundant-bytes",
+					"ownership",
+					"show-skipped",
+					"webhook",
+					"progress",
+					"save-results",
+					"new-since",
+					"daemon",
+				]),
+		)
+		.group(
+			ArgGroup::new("methods")
+				.args(&[
+					"of",
+					"within",
+					"load-results",
+					"verify",
+					"profile",
+					"daemon",
+					"dismiss",
+					"restore",
+				])
+				.required(true)
+				.multiple(true),
+		)
+		.subcommand(
+			Command::new("serve")
+				.about("Run an HTTP server for starting checks and fetching their results as JSON.")
+				.arg(
+					arg!(-a --address <address> "The address to listen on.")
+						.required(false)
+						.default_value("127.0.0.1:8080"),
+				),
+		)
+		.subcommand(
+			Command::new("verify")
+				.about("Re-hash the files in results saved by --save-results and report any groups that no longer hold.")
+				.arg(arg!(<results> "The saved results."))
+				.arg(
+					arg!(--"results-format" <format> "The format of the saved results.")
+						.required(false)
+						.possible_values(["manifest", "binary"])
+						.default_value("manifest"),
+				),
+		)
+		.subcommand(
+			Command::new("explain")
+				.about("Run the check given by the other options, or read the results given by --load-results, and report why a file did or didn't end up in a duplicate group.")
+				.arg(arg!(<path> "The file to explain.")),
+		)
+		.subcommand_negates_reqs(true)
+		.subcommand_precedence_over_arg(true)
+		.after_help(
+			"Use both --of and --within to check the given directories \
+                    for duplicates of the given files.  If only --of is used, \
+                    the files' parent directories will be checked.  If only \
+                    --within is used, the directories will be checked for any \
+                    duplicate files.  Use --baseline with --within to check \
+                    the directories for duplicates of files listed in a \
+                    BLAKE3 manifest.",
+		)
+		.get_matches();
+
+	messages::set_locale(match matches.value_of("lang") {
+		Some(locale) => locale.parse().unwrap(),
+		None => Locale::from_env(),
+	});
+
+	if let Some(manifest_path) = matches.value_of("verify") {
+		verify_manifest(manifest_path);
+		return;
+	}
+
+	if matches.is_present("dismiss") || matches.is_present("restore") {
+		update_ignore_store(
+			matches.value_of("ignore-store"),
+			&values_to_strings(matches.values_of("dismiss")),
+			&values_to_strings(matches.values_of("restore")),
+		);
+		return;
+	}
+
+	let files = values_to_paths(matches.values_of("of"));
+	let mut dirs = values_to_paths(matches.values_of("within"));
+	let profile = matches
+		.value_of("profile")
+		.map(|name| load_profile(matches.value_of("config"), name));
+
+	// Settings given on the command line override the profile's settings.
+	let mut options = match &profile {
+		Some(p) => {
+			if dirs.is_empty() && files.is_empty() {
+				dirs = p.roots().to_vec();
+			}
+
+			p.options().clone()
+		}
+		None => dupcheck::DupOptions::new(),
+	};
+	options.set_hash_all(matches.is_present("manifest"));
+
+	if matches.is_present("exclude-queried") {
+		options.set_exclude_queried(true);
+	}
+
+	if matches.is_present("skip-junk") {
+		options.set_skip_junk(true);
+	}
+
+	if matches.is_present("skip-placeholders") {
+		options.set_skip_placeholders(true);
+	}
+
+	if matches.is_present("exclude-dir") {
+		let mut excluded_dirs = options.excluded_dirs().to_vec();
+		excluded_dirs.append(&mut values_to_paths(matches.values_of("exclude-dir")));
+		options.set_excluded_dirs(excluded_dirs);
+	}
+
+	if matches.is_present("scan-virtual-filesystems") {
+		options.set_scan_virtual_filesystems(true);
+	}
+
+	if matches.is_present("fadvise") {
+		options.set_fadvise(true);
+	}
+
+	if let Some(buffer_size) = matches.value_of("buffer-size") {
+		options.set_buffer_size(buffer_size.parse().unwrap());
+	}
+
+	if let Some(method) = matches.value_of("read-method").filter(|m| *m != "auto") {
+		options.set_read_method(method.parse().unwrap());
+	}
+
+	if matches.is_present("low-priority") {
+		options.set_low_priority(true);
+	}
+
+	if matches.is_present("sequential") {
+		options.set_sequential(true);
+	}
+
+	if let Some(walker) = matches.value_of("walker") {
+		options.set_walker(Some(Arc::new(
+			walker.parse::<dupcheck::BuiltinWalker>().unwrap(),
+		)));
+	}
+
+	if matches.is_present("ownership") {
+		options.set_record_ownership(true);
+	}
+
+	if matches.is_present("explainable") {
+		options.set_explainable(true);
+	}
+
+	if matches.is_present("show-skipped") {
+		options.set_record_skipped(true);
+	}
+
+	if let Some(min_files) = matches.value_of("min-group-size") {
+		options.set_min_group_size(min_files.parse().unwrap());
+	}
+
+	if let Some(min_bytes) = matches.value_of("min-redundant-bytes") {
+		options.set_min_redundant_bytes(min_bytes.parse().unwrap());
+	}
+
+	let mut normalizations = vec![];
+
+	if matches.is_present("ignore-exif") {
+		normalizations.push(dupcheck::Normalization::ImageMetadata);
+	}
+
+	// Gzip files are decompressed first so compressed archives can then be
+	// normalized.
+	if matches.is_present("gzip-contents") {
+		normalizations.push(dupcheck::Normalization::Gzip);
+	}
+
+	if matches.is_present("archive-contents") {
+		normalizations.push(dupcheck::Normalization::ArchiveMembers);
+	}
+
+	// Text is decoded before its line endings are normalized, since UTF-16
+	// text isn't otherwise recognised as text.
+	if matches.is_present("ignore-encoding") {
+		normalizations.push(dupcheck::Normalization::TextEncoding);
+	}
+
+	if matches.is_present("ignore-line-endings") {
+		normalizations.push(dupcheck::Normalization::LineEndings);
+	}
+
+	if !normalizations.is_empty() {
+		options.set_normalizations(normalizations);
+	}
+
+	if let Some(types) = matches.values_of("type") {
+		options.set_categories(types.map(|t| t.parse().unwrap()).collect());
+	}
+
+	if matches.is_present("mime") {
+		options.set_content_types(values_to_strings(matches.values_of("mime")));
+	}
+
+	if matches.is_present("exclude-mime") {
+		options.set_excluded_content_types(values_to_strings(matches.values_of("exclude-mime")));
+	}
+
+	if let Some(signatures) = matches.values_of("exclude-signature") {
+		options.set_excluded_signatures(signatures.map(|s| s.parse().unwrap()).collect());
+	}
+
+	if let Some(ignore_path) = matches.value_of("ignore-hashes") {
+		match read_hashes(ignore_path) {
+			Ok(hashes) => options.set_ignored_hashes(hashes),
+			Err(ignore_error) => {
+				eprintln!("{}", tr!("Error reading ignored hashes: {0}", ignore_error));
+				process::exit(1);
+			}
+		};
+	}
+
+	// Dismissed groups are left out unless they're to be shown, in which
+	// case they're marked.
+	let ignore_store = open_ignore_store(matches.value_of("ignore-store"));
+
+	if let Some(store) = ignore_store
+		.as_ref()
+		.filter(|_| !matches.is_present("show-ignored"))
+	{
+		store.apply(&mut options);
+	}
+
+	if let Some(serve_matches) = matches.subcommand_matches("serve") {
+		run_server(options, serve_matches.value_of("address").unwrap());
+		return;
+	}
+
+	if let Some(verify_matches) = matches.subcommand_matches("verify") {
+		verify_results(
+			options,
+			verify_matches.value_of("results").unwrap(),
+			verify_matches.value_of("results-format") == Some("binary"),
+		);
+		return;
+	}
+
+	if let Some(socket_path) = matches.value_of("daemon") {
+		run_daemon(options, socket_path, matches.value_of("config"));
+		return;
+	}
+
+	let cache_path = match matches.value_of("hash-cache") {
+		Some(path) => Some(PathBuf::from(path)),
+		None if matches.is_present("hash-cache") => match default_cache_path() {
+			Some(path) => Some(path),
+			None => {
+				eprintln!(
+					"{}",
+					tr!("Error: no location for the hash cache; give --hash-cache a file")
+				);
+				process::exit(1);
+			}
+		},
+		None => None,
+	};
+	let hash_cache = cache_path.map(|cache_path| {
+		// The cache's directory is only created when the default location
+		// is first used.
+		if let Some(dir) = cache_path.parent().filter(|d| !d.as_os_str().is_empty()) {
+			if let Err(dir_error) = fs::create_dir_all(dir) {
+				eprintln!(
+					"{}",
+					tr!("Error creating {0}: {1}", dir.display(), dir_error)
+				);
+				process::exit(1);
+			}
+		}
+
+		// The cache stays locked until it's been saved.
+		let lock = match dupcheck::HashCache::lock_file(&cache_path, matches.is_present("wait")) {
+			Ok(l) => l,
+			Err(e) if e.kind() == io::ErrorKind::WouldBlock => {
+				eprintln!(
+					"{}",
+					tr!(
+						"Error: the hash cache {0} is in use by another dupcheck process; use --wait to wait for it",
+						cache_path.display()
+					)
+				);
+				process::exit(1);
+			}
+			Err(lock_error) => {
+				eprintln!("{}", tr!("Error locking hash cache: {0}", lock_error));
+				process::exit(1);
+			}
+		};
+		let cache = read_hash_cache(&cache_path).unwrap_or_else(|cache_error| {
+			eprintln!("{}", tr!("Error reading hash cache: {0}", cache_error));
+			process::exit(1);
+		});
+		options.set_hash_cache(Some(cache.clone()));
+		(cache_path, cache, lock)
+	});
+
+	// The previous results are read before being replaced, so the same file
+	// can be given to both options.
+	let binary_results = matches.value_of("results-format") == Some("binary");
+	let previous = matches.value_of("new-since").map(|previous_path| {
+		read_previous_results(previous_path, binary_results).unwrap_or_else(|previous_error| {
+			eprintln!(
+				"{}",
+				tr!("Error reading previous results: {0}", previous_error)
+			);
+			process::exit(1);
+		})
+	});
+
+	// Binary results are saved as they're found, so the duplicates found so
+	// far can be loaded if the check doesn't finish.
+	let saving = match (matches.value_of("save-results"), binary_results) {
+		(Some(results_path), true) => {
+			let (sender, handle) = save_results_as_found(results_path);
+			options.set_group_sender(Some(sender));
+			Some(handle)
+		}
+		_ => None,
+	};
+
+	let streaming = match matches.value_of("format") {
+		Some("ndjson") => {
+			let (sender, handle) = stream_events(open_output(matches.value_of("output")));
+			options.set_event_sender(Some(sender));
+			Some(handle)
+		}
+		_ => None,
+	};
+
+	// Read methods are timed on the files to be checked, which are in the
+	// given files' directories if no directories are given.
+	if matches.value_of("read-method") == Some("auto") {
+		let benchmark_dirs: Vec<PathBuf> = match dirs.is_empty() {
+			true => files
+				.iter()
+				.filter_map(|f| f.parent())
+				.map(|d| match d.as_os_str().is_empty() {
+					true => PathBuf::from("."),
+					false => d.to_path_buf(),
+				})
+				.collect(),
+			false => dirs.clone(),
+		};
+
+		match dupcheck::Benchmark::run(&benchmark_dirs) {
+			Ok(benchmark) => {
+				options.set_read_method(benchmark.fastest());
+
+				if matches.is_present("show-read-method") {
+					print_benchmark(&benchmark);
+				}
+			}
+			Err(benchmark_error) => {
+				eprintln!("{}", tr!("Error: {0}", benchmark_error));
+				process::exit(1);
+			}
+		};
+	}
+
+	if matches.is_present("show-read-method") {
+		eprintln!(
+			"{}",
+			tr!("Read method: {0}", read_method_name(options.read_method()))
+		);
+	}
+
+	let explaining = matches.subcommand_matches("explain");
+
+	if explaining.is_some() {
+		options.set_explainable(true);
+	}
+
+	let progress_bar = match matches.is_present("progress") {
+		true => {
+			let progress = dupcheck::Progress::new();
+			options.set_progress(Some(progress.clone()));
+			options.set_prescan(matches.is_present("prescan"));
+			Some(show_progress(progress))
+		}
+		false => None,
+	};
+
+	let mut dup_result = get_dup_result(
+		&files,
+		&dirs,
+		matches.value_of("baseline"),
+		matches.value_of("load-results"),
+		options,
+	);
+
+	if let Some(handle) = streaming {
+		if let Ok(dup_results) = &mut dup_result {
+			dup_results.options_mut().set_event_sender(None);
+		}
+
+		if let Ok((output, written)) = handle.join() {
+			finish_output(output, written);
+		}
+	}
+
+	if let Some(handle) = saving {
+		if let Ok(dup_results) = &mut dup_result {
+			dup_results.options_mut().set_group_sender(None);
+		}
+
+		if let Ok(Err(save_error)) = handle.join() {
+			eprintln!("{}", tr!("Error saving results: {0}", save_error));
+		}
+	}
+
+	if let Some((stop, handle)) = progress_bar {
+		let _ = stop.send(());
+		let _ = handle.join();
+	}
+
+	if let Some((cache_path, cache, lock)) = hash_cache {
+		if let Err(cache_error) = save_hash_cache(&cache, &cache_path) {
+			eprintln!("{}", tr!("Error saving hash cache: {0}", cache_error));
+		}
+
+		drop(lock);
+	}
+
+	if let Some(explain_matches) = explaining {
+		match &dup_result {
+			Ok(dup_results) => explain(dup_results, explain_matches.value_of("path").unwrap()),
+			Err(dup_error) => eprintln!("{}", tr!("Error: {0}", dup_error)),
+		};
+		return;
+	}
+
+	let similarity = matches.value_of("similarity").unwrap().parse().unwrap();
+
+	if let Ok(dup_results) = &mut dup_result {
+		if matches.is_present("similar-videos") {
+			if let Err(similar_error) = dup_results.similar_videos(&dirs, similarity) {
+				eprintln!(
+					"{}",
+					tr!("Error checking for similar videos: {0}", similar_error)
+				);
+			}
+		}
+
+		if let Some(blocklist_path) = matches.value_of("blocklist") {
+			let checked =
+				read_hashes(blocklist_path).and_then(|hashes| dup_results.check_blocklist(&hashes, &dirs));
+
+			if let Err(blocklist_error) = checked {
+				eprintln!("{}", tr!("Error checking blocklist: {0}", blocklist_error));
+			}
+		}
+
+		if matches.is_present("similar-texts") {
+			if let Err(similar_error) = dup_results.similar_texts(&dirs, similarity) {
+				eprintln!(
+					"{}",
+					tr!("Error checking for similar text files: {0}", similar_error)
+				);
+			}
+		}
+	}
+
+	if let Ok(mut dup_results) = dup_result {
+		if let Some(results_path) = matches.value_of("save-results") {
+			if let Err(save_error) = save_results(&dup_results, results_path, binary_results) {
+				eprintln!("{}", tr!("Error saving results: {0}", save_error));
+			}
+		}
+
+		if let Some(previous) = previous {
+			dup_results.retain_new(&previous);
+		}
+
+		if let Some(manifest_path) = matches.value_of("manifest") {
+			if let Err(manifest_error) = write_manifest(&dup_results, manifest_path) {
+				eprintln!("{}", tr!("Error writing manifest: {0}", manifest_error));
+			}
+		}
+
+		let webhook = matches
+			.value_of("webhook")
+			.or_else(|| profile.as_ref().and_then(|p| p.webhook()));
+
+		if let Some(url) = webhook {
+			if let Err(webhook_error) = dup_results.notify_webhook(url) {
+				eprintln!("{}", tr!("Error sending webhook: {0}", webhook_error));
+			}
+		}
+
+		if let Some(rmlint_path) = matches.value_of("rmlint") {
+			if let Err(rmlint_error) = write_rmlint_json(&dup_results, rmlint_path) {
+				eprintln!("{}", tr!("Error writing rmlint output: {0}", rmlint_error));
+			}
+		}
+
+		if let Some(sql_path) = matches.value_of("sql") {
+			if let Err(sql_error) = write_sql(&dup_results, sql_path) {
+				eprintln!("{}", tr!("Error writing SQL: {0}", sql_error));
+			}
+		}
+
+		let keep = match (matches.values_of("keep-rule"), &profile) {
+			(Some(rules), _) => dupcheck::KeepPolicy::Rules(dupcheck::KeepRules::new(
+				rules.map(|r| r.parse().unwrap()).collect(),
+			)),
+			(None, Some(p)) if matches.occurrences_of("keep") == 0 => p.keep().clone(),
+			(None, _) => matches.value_of("keep").unwrap().parse().unwrap(),
+		};
+
+		// Loaded results may be out of date, so they're verified before
+		// anything is done to their files.
+		let acting = [
+			"script", "hardlink", "symlink", "reflink", "trash", "move-to",
+		]
+		.iter()
+		.any(|a| matches.is_present(a));
+
+		if acting && matches.is_present("load-results") {
+			skip_stale_groups(&mut dup_results);
+		}
+
+		if let Some(format) = matches.value_of("script") {
+			let action = match matches.value_of("move-to") {
+				Some(dir) => dupcheck::Action::MoveTo(PathBuf::from(dir)),
+				None => matches.value_of("script-action").unwrap().parse().unwrap(),
+			};
+			print_script(
+				matches.value_of("output"),
+				&dup_results,
+				format.parse().unwrap(),
+				&keep,
+				action,
+			);
+			return;
+		}
+
+		if matches.is_present("hardlink") {
+			apply_action(&dup_results, &keep, dupcheck::Action::Hardlink);
+			return;
+		}
+
+		if matches.is_present("symlink") {
+			let action = match matches.is_present("relative") {
+				true => dupcheck::Action::RelativeSymlink,
+				false => dupcheck::Action::Symlink,
+			};
+			apply_action(&dup_results, &keep, action);
+			return;
+		}
+
+		if matches.is_present("reflink") {
+			apply_action(&dup_results, &keep, dupcheck::Action::Reflink);
+			return;
+		}
+
+		if matches.is_present("trash") {
+			apply_action(&dup_results, &keep, dupcheck::Action::Trash);
+			return;
+		}
+
+		if let Some(dir) = matches.value_of("move-to") {
+			apply_action(
+				&dup_results,
+				&keep,
+				dupcheck::Action::MoveTo(PathBuf::from(dir)),
+			);
+			return;
+		}
+
+		if let Some(action) = profile.as_ref().and_then(|p| p.action()) {
+			apply_action(&dup_results, &keep, action.clone());
+			return;
+		}
+
+		// The results have already been written as events.
+		if matches.value_of("format") == Some("ndjson") {
+			return;
+		}
+
+		let is_dismissed = |group: &dupcheck::DupGroup| {
+			ignore_store
+				.as_ref()
+				.is_some_and(|s| s.is_dismissed(&group.get_hash()))
+		};
+		let summary_only = matches.is_present("summary-only");
+		let template = matches.value_of("format-template");
+		let mut output = open_output(matches.value_of("output"));
+		let written = match template {
+			Some(t) => dup_results.write_template(&mut output, &t.parse().unwrap()),
+			None => print_results(
+				&mut output,
+				&dup_results,
+				&is_dismissed,
+				summary_only,
+				matches.is_present("stats"),
+				matches.is_present("show-skipped"),
+			),
+		};
+
+		// Errors are kept out of results written to a file or in a template's
+		// format, so they can be read by other programs.
+		let written = written.and_then(|_| {
+			match (
+				summary_only,
+				matches.is_present("output") || template.is_some(),
+			) {
+				(true, _) => Ok(()),
+				(false, true) => print_check_errors(&mut io::stderr(), &dup_results),
+				(false, false) => print_check_errors(&mut output, &dup_results),
+			}
+		});
+		finish_output(output, written);
 	} else if let Err(dup_error) = dup_result {
-		println!("Error: {}", dup_error);
+		eprintln!("{}", tr!("Error: {0}", dup_error));
 	}
 }
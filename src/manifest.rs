@@ -0,0 +1,287 @@
+use crate::duperror::DupError;
+use crate::hash::hash_file;
+use crate::options::DupOptions;
+use std::fs::File;
+use std::io;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+/// A list of file paths and their BLAKE3 hashes, in the format used by
+/// `b3sum`.
+///
+/// Each line of a manifest contains a file's hash, followed by two spaces
+/// (or a space and an asterisk) and the file's path.  This is the same line
+/// format used by `sha256sum` and similar tools.  As with `b3sum`, a line
+/// starting with a backslash has backslashes and newlines in its path escaped
+/// as `\\` and `\n`.
+#[derive(Clone, Debug, Default)]
+pub struct Manifest {
+	/// The hashes and paths of the files listed in the manifest.
+	entries: Vec<(String, PathBuf)>,
+}
+
+impl Manifest {
+	/// Creates a new, empty `Manifest`.
+	pub fn new() -> Manifest {
+		Manifest::default()
+	}
+
+	/// Reads a manifest from the file at the given path.
+	///
+	/// # Errors
+	///
+	/// Returns an error if the file can't be read or if any line isn't a
+	/// valid manifest line.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use std::path::PathBuf;
+	///
+	/// let dirs = vec![PathBuf::from("foo")];
+	/// let mut dup_result = dupcheck::DupResults::new();
+	///
+	/// match dupcheck::Manifest::from_file("archive.b3") {
+	///     Ok(manifest) => {
+	///         if let Err(dup_error) = dup_result.against(&manifest, &dirs) {
+	///             // Error handling
+	///         }
+	///     }
+	///     Err(manifest_error) => {
+	///         // Error handling
+	///     }
+	/// }
+	/// ```
+	pub fn from_file<P: AsRef<Path>>(path: P) -> io::Result<Manifest> {
+		Manifest::read(BufReader::new(File::open(path)?))
+	}
+
+	/// Reads a manifest from the given reader.
+	///
+	/// # Errors
+	///
+	/// Returns an error if the reader fails or if any line isn't a valid
+	/// manifest line.
+	pub fn read<R: BufRead>(reader: R) -> io::Result<Manifest> {
+		let mut manifest = Manifest::new();
+
+		for (i, line) in reader.lines().enumerate() {
+			let line = line?;
+
+			if line.is_empty() {
+				continue;
+			}
+
+			match parse_line(&line) {
+				Some((hash, path)) => manifest.add(hash, path),
+				None => {
+					return Err(io::Error::new(
+						io::ErrorKind::InvalidData,
+						format!("invalid manifest line {}", i + 1),
+					))
+				}
+			};
+		}
+
+		Ok(manifest)
+	}
+
+	/// Writes the manifest to the given writer, in a format that can be
+	/// checked with `b3sum --check`.
+	///
+	/// # Errors
+	///
+	/// Returns an error if writing fails.
+	pub fn write<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+		for (hash, path) in &self.entries {
+			let path = path.to_string_lossy();
+
+			if path.contains(['\\', '\n']) {
+				let escaped = path.replace('\\', "\\\\").replace('\n', "\\n");
+				writeln!(writer, "\\{}  {}", hash, escaped)?;
+			} else {
+				writeln!(writer, "{}  {}", hash, path)?;
+			}
+		}
+
+		Ok(())
+	}
+
+	/// Re-hashes the files listed in the manifest and returns which of them
+	/// no longer match their listed hashes or no longer exist.
+	///
+	/// Relative paths in the manifest are resolved against the current
+	/// working directory, as with `b3sum --check`.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// let options = dupcheck::DupOptions::new();
+	///
+	/// match dupcheck::Manifest::from_file("archive.b3") {
+	///     Ok(manifest) => {
+	///         let results = manifest.verify(&options);
+	///
+	///         for path in results.mismatched() {
+	///             println!("{} has changed", path.display());
+	///         }
+	///     }
+	///     Err(manifest_error) => {
+	///         // Error handling
+	///     }
+	/// }
+	/// ```
+	pub fn verify(&self, options: &DupOptions) -> VerifyResults {
+		let files = self
+			.entries
+			.iter()
+			.map(|(hash, path)| (hash.as_str(), path.as_path()));
+
+		verify_files(files, options, |path| hash_file(path))
+	}
+
+	/// Adds a file's hash and path to the manifest.
+	pub fn add(&mut self, hash: String, path: PathBuf) {
+		self.entries.push((hash, path));
+	}
+
+	/// Returns a reference to the hashes and paths of the files listed in
+	/// the manifest.
+	pub fn entries(&self) -> &[(String, PathBuf)] {
+		&self.entries
+	}
+
+	/// Returns the number of entries in the manifest.
+	pub fn len(&self) -> usize {
+		self.entries.len()
+	}
+
+	/// Returns whether the manifest has no entries.
+	pub fn is_empty(&self) -> bool {
+		self.entries.is_empty()
+	}
+
+	/// Returns the paths of all files in the manifest with the given hash.
+	pub fn files_with_hash(&self, hash: &str) -> Vec<PathBuf> {
+		self
+			.entries
+			.iter()
+			.filter(|e| e.0 == hash)
+			.map(|e| e.1.clone())
+			.collect()
+	}
+}
+
+/// Re-hashes files with the given function and returns which of them no
+/// longer match their expected hashes or no longer exist.
+pub(crate) fn verify_files<'a, I, F>(files: I, options: &DupOptions, hash: F) -> VerifyResults
+where
+	I: IntoIterator<Item = (&'a str, &'a Path)>,
+	F: Fn(&Path) -> io::Result<String>,
+{
+	let retry = options.retry_policy();
+	let mut results = VerifyResults {
+		verified: vec![],
+		mismatched: vec![],
+		missing: vec![],
+		errors: vec![],
+	};
+
+	for (expected, path) in files {
+		match retry.run(|| hash(path)) {
+			Ok(h) if h == expected => results.verified.push(path.to_path_buf()),
+			Ok(_) => results.mismatched.push(path.to_path_buf()),
+			Err(e) if e.kind() == io::ErrorKind::NotFound => results.missing.push(path.to_path_buf()),
+			Err(e) => results.errors.push(DupError::new(path.to_path_buf(), e)),
+		};
+	}
+
+	results
+}
+
+/// Returns the hash and path from a manifest line, or `None` if the line
+/// isn't valid.
+fn parse_line(line: &str) -> Option<(String, PathBuf)> {
+	let (escaped, line) = match line.strip_prefix('\\') {
+		Some(rest) => (true, rest),
+		None => (false, line),
+	};
+	let (hash, rest) = line.split_once(' ')?;
+	let path = rest.strip_prefix(' ').or_else(|| rest.strip_prefix('*'))?;
+
+	if hash.is_empty() || !hash.chars().all(|c| c.is_ascii_hexdigit()) || path.is_empty() {
+		return None;
+	}
+
+	let path = match escaped {
+		true => unescape(path)?,
+		false => path.to_string(),
+	};
+
+	Some((hash.to_ascii_lowercase(), PathBuf::from(path)))
+}
+
+/// Returns a path with `\\` and `\n` escapes replaced by the characters they
+/// represent, or `None` if the path contains any other escape.
+fn unescape(path: &str) -> Option<String> {
+	let mut unescaped = String::with_capacity(path.len());
+	let mut chars = path.chars();
+
+	while let Some(c) = chars.next() {
+		match c {
+			'\\' => match chars.next()? {
+				'\\' => unescaped.push('\\'),
+				'n' => unescaped.push('\n'),
+				_ => return None,
+			},
+			_ => unescaped.push(c),
+		};
+	}
+
+	Some(unescaped)
+}
+
+/// Results of verifying the files listed in a manifest.
+#[derive(Debug)]
+pub struct VerifyResults {
+	/// Paths to files that match their listed hashes.
+	verified: Vec<PathBuf>,
+
+	/// Paths to files that don't match their listed hashes.
+	mismatched: Vec<PathBuf>,
+
+	/// Paths to files that no longer exist.
+	missing: Vec<PathBuf>,
+
+	/// Errors encountered while trying to read files.
+	errors: Vec<DupError>,
+}
+
+impl VerifyResults {
+	/// Returns a reference to the paths of files that match their listed
+	/// hashes.
+	pub fn verified(&self) -> &[PathBuf] {
+		&self.verified
+	}
+
+	/// Returns a reference to the paths of files that don't match their
+	/// listed hashes.
+	pub fn mismatched(&self) -> &[PathBuf] {
+		&self.mismatched
+	}
+
+	/// Returns a reference to the paths of files that no longer exist.
+	pub fn missing(&self) -> &[PathBuf] {
+		&self.missing
+	}
+
+	/// Returns a reference to the errors.
+	pub fn errors(&self) -> &[DupError] {
+		&self.errors
+	}
+
+	/// Returns whether every listed file exists and matches its hash.
+	pub fn is_ok(&self) -> bool {
+		self.mismatched.is_empty() && self.missing.is_empty() && self.errors.is_empty()
+	}
+}
@@ -0,0 +1,310 @@
+use std::env;
+use std::fmt::Display;
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+/// Returns a message in the chosen locale, with `{0}`, `{1}` and so on
+/// replaced by the given arguments.
+macro_rules! tr {
+	($message:expr $(, $arg:expr)* $(,)?) => {
+		$crate::messages::message($message, &[$(&$arg as &dyn std::fmt::Display),*])
+	};
+}
+
+/// Returns the singular or plural form of a message for the given count in
+/// the chosen locale, with `{0}`, `{1}` and so on replaced by the given
+/// arguments.
+macro_rules! trn {
+	($count:expr, $singular:expr, $plural:expr $(, $arg:expr)* $(,)?) => {
+		$crate::messages::plural_message(
+			$count as u64,
+			$singular,
+			$plural,
+			&[$(&$arg as &dyn std::fmt::Display),*],
+		)
+	};
+}
+
+pub(crate) use {tr, trn};
+
+/// The languages messages can be shown in.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) enum Locale {
+	#[default]
+	English,
+	German,
+}
+
+impl FromStr for Locale {
+	type Err = String;
+
+	/// Parses a language code, such as `de`, or a POSIX locale name, such as
+	/// `de_DE.UTF-8`.
+	fn from_str(s: &str) -> Result<Locale, String> {
+		let language = s.split(['_', '-', '.', '@']).next().unwrap_or_default();
+
+		match language {
+			"en" | "C" | "POSIX" => Ok(Locale::English),
+			"de" => Ok(Locale::German),
+			_ => Err(format!("unsupported locale {}", s)),
+		}
+	}
+}
+
+impl Locale {
+	/// Returns the locale set by the first of the `LC_ALL`, `LC_MESSAGES` and
+	/// `LANG` environment variables that's set, or English if it isn't a
+	/// supported language.
+	pub(crate) fn from_env() -> Locale {
+		["LC_ALL", "LC_MESSAGES", "LANG"]
+			.iter()
+			.filter_map(|name| env::var(name).ok())
+			.find(|value| !value.is_empty())
+			.and_then(|value| value.parse().ok())
+			.unwrap_or_default()
+	}
+
+	/// Returns whether the singular form of a message is used for a count.
+	fn is_singular(&self, count: u64) -> bool {
+		count == 1
+	}
+}
+
+/// The locale messages are shown in, which is English until it's set.
+static LOCALE: OnceLock<Locale> = OnceLock::new();
+
+/// Sets the locale messages are shown in.  Only the first locale set is
+/// used.
+pub(crate) fn set_locale(locale: Locale) {
+	let _ = LOCALE.set(locale);
+}
+
+/// Returns a message in the chosen locale, or in English if it hasn't been
+/// translated, with its arguments filled in.
+pub(crate) fn message(message: &'static str, args: &[&dyn Display]) -> String {
+	fill(translate(message), args)
+}
+
+/// Returns the singular or plural form of a message for a count in the
+/// chosen locale, or in English if it hasn't been translated, with its
+/// arguments filled in.
+pub(crate) fn plural_message(
+	count: u64,
+	singular: &'static str,
+	plural: &'static str,
+	args: &[&dyn Display],
+) -> String {
+	match LOCALE.get().copied().unwrap_or_default().is_singular(count) {
+		true => message(singular, args),
+		false => message(plural, args),
+	}
+}
+
+/// Returns a message with each `{n}` replaced by the `n`th argument.
+/// Arguments are inserted as they are, so they can contain braces.
+fn fill(message: &str, args: &[&dyn Display]) -> String {
+	let mut filled = String::with_capacity(message.len());
+	let mut rest = message;
+
+	while let Some(start) = rest.find('{') {
+		filled.push_str(&rest[..start]);
+		rest = &rest[start..];
+
+		let arg = rest
+			.find('}')
+			.and_then(|end| Some((end, args.get(rest[1..end].parse::<usize>().ok()?)?)));
+
+		match arg {
+			Some((end, arg)) => {
+				filled.push_str(&arg.to_string());
+				rest = &rest[end + 1..];
+			}
+			None => {
+				filled.push('{');
+				rest = &rest[1..];
+			}
+		};
+	}
+
+	filled.push_str(rest);
+	filled
+}
+
+/// Returns the translation of an English message into the chosen locale.
+fn translate(message: &'static str) -> &'static str {
+	let translation = match LOCALE.get().copied().unwrap_or_default() {
+		Locale::English => None,
+		Locale::German => german(message),
+	};
+
+	translation.unwrap_or(message)
+}
+
+/// Returns the German translation of an English message, if there is one.
+fn german(message: &str) -> Option<&'static str> {
+	let translation = match message {
+		// Check results.
+		"{0} files found in {1} group." => "{0} Dateien in {1} Gruppe gefunden.",
+		"{0} files found in {1} groups." => "{0} Dateien in {1} Gruppen gefunden.",
+		"{0} bytes reclaimable." => "{0} Bytes können freigegeben werden.",
+		"{0} error occurred during check." => "{0} Fehler bei der Prüfung aufgetreten.",
+		"{0} errors occurred during check." => "{0} Fehler bei der Prüfung aufgetreten.",
+		"{0} group already deduplicated by hard links or reflinks, 0 bytes reclaimable:" => {
+			"{0} Gruppe bereits durch harte Links oder Reflinks dedupliziert, 0 Bytes können freigegeben werden:"
+		}
+		"{0} groups already deduplicated by hard links or reflinks, 0 bytes reclaimable:" => {
+			"{0} Gruppen bereits durch harte Links oder Reflinks dedupliziert, 0 Bytes können freigegeben werden:"
+		}
+		"Duplicates of file {0}:" => "Duplikate der Datei {0}:",
+		"Duplicates of file {0} (dismissed):" => "Duplikate der Datei {0} (ausgeblendet):",
+		"{0} (baseline)" => "{0} (Referenz)",
+		"{0} (queried)" => "{0} (abgefragt)",
+		"Blocked files:" => "Gesperrte Dateien:",
+		"Similar files ({0}% similarity):" => "Ähnliche Dateien ({0} % Ähnlichkeit):",
+		"Warning: {0}" => "Warnung: {0}",
+		"Skipped files:" => "Übersprungene Dateien:",
+		"junk file" => "Systemdatei",
+		"cloud storage placeholder" => "Cloud-Platzhalter",
+		"excluded signature" => "ausgeschlossene Signatur",
+		"no file of the same size" => "keine Datei gleicher Größe",
+		"excluded type" => "ausgeschlossener Typ",
+		"excluded directory" => "ausgeschlossenes Verzeichnis",
+		"already searched" => "bereits durchsucht",
+		"already checked" => "bereits geprüft",
+
+		// Explanations.
+		"{0} is a duplicate, in the group of file {1}." => "{0} ist ein Duplikat, in der Gruppe der Datei {1}.",
+		"{0} isn't a duplicate, as it couldn't be read: {1}" => {
+			"{0} ist kein Duplikat, da die Datei nicht gelesen werden konnte: {1}"
+		}
+		"{0} isn't a duplicate, as it was skipped: {1}" => "{0} ist kein Duplikat, da die Datei übersprungen wurde: {1}",
+		"{0} isn't a duplicate, as {1}." => "{0} ist kein Duplikat, da {1}.",
+		"no other file has the same size" => "keine andere Datei die gleiche Größe hat",
+		"no other file starts with the same bytes" => "keine andere Datei mit den gleichen Bytes beginnt",
+		"no other file has the same hash" => "keine andere Datei den gleichen Hash hat",
+		"no other file has the same contents" => "keine andere Datei den gleichen Inhalt hat",
+		"no other file has the same normalized hash" => {
+			"keine andere Datei den gleichen normalisierten Hash hat"
+		}
+		"{0} isn't a duplicate.  It wasn't found by the check, or it matched files in a group left out by the ignored hashes or group size thresholds." => {
+			"{0} ist kein Duplikat.  Die Datei wurde bei der Prüfung nicht gefunden, oder sie gleicht Dateien in einer Gruppe, die durch die ignorierten Hashes oder die Mindestgrößen von Gruppen ausgelassen wurde."
+		}
+
+		// Statistics.
+		"Redundant files by extension:" => "Redundante Dateien nach Dateiendung:",
+		"(none)" => "(keine)",
+		"{0}: {1} of {2} file redundant, {3} bytes" => "{0}: {1} von {2} Datei redundant, {3} Bytes",
+		"{0}: {1} of {2} files redundant, {3} bytes" => "{0}: {1} von {2} Dateien redundant, {3} Bytes",
+		"Duplicates by directory:" => "Duplikate nach Verzeichnis:",
+		"{0}: {1} of {2} file duplicated, {3} redundant ({4} bytes), {5} shared with other directories" => {
+			"{0}: {1} von {2} Datei dupliziert, {3} redundant ({4} Bytes), {5} mit anderen Verzeichnissen geteilt"
+		}
+		"{0}: {1} of {2} files duplicated, {3} redundant ({4} bytes), {5} shared with other directories" => {
+			"{0}: {1} von {2} Dateien dupliziert, {3} redundant ({4} Bytes), {5} mit anderen Verzeichnissen geteilt"
+		}
+		"Duplicates by owner:" => "Duplikate nach Eigentümer:",
+		"uid {0}: {1} file duplicated, {2} redundant ({3} bytes)" => {
+			"UID {0}: {1} Datei dupliziert, {2} redundant ({3} Bytes)"
+		}
+		"uid {0}: {1} files duplicated, {2} redundant ({3} bytes)" => {
+			"UID {0}: {1} Dateien dupliziert, {2} redundant ({3} Bytes)"
+		}
+
+		// Read methods.
+		"Read method: {0}" => "Lesemethode: {0}",
+		"Timed hashing {0} with each read method:" => "Hashen von {0} mit jeder Lesemethode gemessen:",
+
+		// Progress.
+		"Found {0} files" => "{0} Dateien gefunden",
+		"[{0}{1}] {2}% {3} of {4} ({5} of {6} files)" => "[{0}{1}] {2} % {3} von {4} ({5} von {6} Dateien)",
+		", {0} left" => ", noch {0}",
+
+		// Actions.
+		"Moved {0} to {1}" => "{0} nach {1} verschoben",
+		"Trashed {0}" => "{0} in den Papierkorb verschoben",
+		"Deleted {0}" => "{0} gelöscht",
+		"Hard linked {0} to {1}" => "{0} durch harten Link auf {1} ersetzt",
+		"Symlinked {0} to {1}" => "{0} durch symbolischen Link auf {1} ersetzt",
+		"Reflinked {0} to {1}" => "{0} durch Reflink von {1} ersetzt",
+		"Warning: {0} not changed" => "Warnung: {0} nicht geändert",
+		"Conflict: {0}" => "Konflikt: {0}",
+		"{0} file deleted, {1} bytes reclaimed." => "{0} Datei gelöscht, {1} Bytes freigegeben.",
+		"{0} files deleted, {1} bytes reclaimed." => "{0} Dateien gelöscht, {1} Bytes freigegeben.",
+		"{0} file moved, {1} bytes reclaimed." => "{0} Datei verschoben, {1} Bytes freigegeben.",
+		"{0} files moved, {1} bytes reclaimed." => "{0} Dateien verschoben, {1} Bytes freigegeben.",
+		"{0} file trashed, {1} bytes reclaimed." => {
+			"{0} Datei in den Papierkorb verschoben, {1} Bytes freigegeben."
+		}
+		"{0} files trashed, {1} bytes reclaimed." => {
+			"{0} Dateien in den Papierkorb verschoben, {1} Bytes freigegeben."
+		}
+		"{0} file replaced, {1} bytes reclaimed." => "{0} Datei ersetzt, {1} Bytes freigegeben.",
+		"{0} files replaced, {1} bytes reclaimed." => "{0} Dateien ersetzt, {1} Bytes freigegeben.",
+		"{0} error occurred." => "{0} Fehler aufgetreten.",
+		"{0} errors occurred." => "{0} Fehler aufgetreten.",
+
+		// Verifying and loading results.
+		"{0} of {1} files verified." => "{0} von {1} Dateien überprüft.",
+		"{0} of {1} groups verified." => "{0} von {1} Gruppen überprüft.",
+		"Group {0} no longer holds ({1} of {2} files verified):" => {
+			"Gruppe {0} ist nicht mehr gültig ({1} von {2} Dateien überprüft):"
+		}
+		"{0} (mismatched)" => "{0} (abweichend)",
+		"{0} (missing)" => "{0} (fehlt)",
+		"{0} (error)" => "{0} (Fehler)",
+		"Skipping group {0}, since its files have changed since it was saved:" => {
+			"Gruppe {0} wird übersprungen, da sich ihre Dateien seit dem Speichern geändert haben:"
+		}
+		"Warning: {0} is incomplete; only the duplicates saved before its check stopped are included." => {
+			"Warnung: {0} ist unvollständig; nur die Duplikate, die vor dem Abbruch der Prüfung gespeichert wurden, sind enthalten."
+		}
+
+		// The ignore store, profiles, the daemon and the server.
+		"{0} group dismissed, {1} restored." => "{0} Gruppe ausgeblendet, {1} wiederhergestellt.",
+		"{0} groups dismissed, {1} restored." => "{0} Gruppen ausgeblendet, {1} wiederhergestellt.",
+		"Listening on http://{0}" => "Warte auf Verbindungen unter http://{0}",
+
+		// Errors.
+		"Error: {0}" => "Fehler: {0}",
+		"Error reading {0}: {1}" => "Fehler beim Lesen von {0}: {1}",
+		"Error writing {0}: {1}" => "Fehler beim Schreiben von {0}: {1}",
+		"Error creating {0}: {1}" => "Fehler beim Erstellen von {0}: {1}",
+		"Error: no config file found" => "Fehler: keine Konfigurationsdatei gefunden",
+		"Error: no profile named {0} in {1}" => "Fehler: kein Profil namens {0} in {1}",
+		"Error: no location for the ignore store; use --ignore-store" => {
+			"Fehler: kein Speicherort für die Ignorierliste; verwenden Sie --ignore-store"
+		}
+		"Error: no location for the hash cache; give --hash-cache a file" => {
+			"Fehler: kein Speicherort für den Hash-Cache; geben Sie --hash-cache eine Datei an"
+		}
+		"Error: the hash cache {0} is in use by another dupcheck process; use --wait to wait for it" => {
+			"Fehler: der Hash-Cache {0} wird von einem anderen dupcheck-Prozess verwendet; verwenden Sie --wait, um darauf zu warten"
+		}
+		"Error: daemon mode is only supported on Unix" => {
+			"Fehler: der Daemon-Modus wird nur unter Unix unterstützt"
+		}
+		"Error reading results: {0}" => "Fehler beim Lesen der Ergebnisse: {0}",
+		"Error reading previous results: {0}" => "Fehler beim Lesen der vorherigen Ergebnisse: {0}",
+		"Error reading ignored hashes: {0}" => "Fehler beim Lesen der ignorierten Hashes: {0}",
+		"Error reading hash cache: {0}" => "Fehler beim Lesen des Hash-Caches: {0}",
+		"Error locking hash cache: {0}" => "Fehler beim Sperren des Hash-Caches: {0}",
+		"Error saving hash cache: {0}" => "Fehler beim Speichern des Hash-Caches: {0}",
+		"Error saving results: {0}" => "Fehler beim Speichern der Ergebnisse: {0}",
+		"Error writing results: {0}" => "Fehler beim Schreiben der Ergebnisse: {0}",
+		"Error writing manifest: {0}" => "Fehler beim Schreiben des Manifests: {0}",
+		"Error writing rmlint output: {0}" => "Fehler beim Schreiben der rmlint-Ausgabe: {0}",
+		"Error writing SQL: {0}" => "Fehler beim Schreiben der SQL-Anweisungen: {0}",
+		"Error sending webhook: {0}" => "Fehler beim Senden des Webhooks: {0}",
+		"Error checking for similar videos: {0}" => "Fehler bei der Suche nach ähnlichen Videos: {0}",
+		"Error checking for similar text files: {0}" => {
+			"Fehler bei der Suche nach ähnlichen Textdateien: {0}"
+		}
+		"Error checking blocklist: {0}" => "Fehler beim Prüfen der Sperrliste: {0}",
+		"Error scheduling checks: {0}" => "Fehler beim Planen der Prüfungen: {0}",
+		"Error running daemon: {0}" => "Fehler beim Ausführen des Daemons: {0}",
+		"Error running server: {0}" => "Fehler beim Ausführen des Servers: {0}",
+		_ => return None,
+	};
+
+	Some(translation)
+}
@@ -0,0 +1,176 @@
+use crate::{DupGroup, DupResults};
+use std::fs::Metadata;
+use std::path::Path;
+use std::time::SystemTime;
+
+/// The size and times of a file in a duplicate group, as read when the file
+/// was added to the group.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FileMetadata {
+	/// The file's size in bytes.
+	size: u64,
+
+	/// The file's modification time, if the platform provides it.
+	modified: Option<SystemTime>,
+
+	/// The time the file's metadata last changed, if the platform provides
+	/// it.
+	changed: Option<SystemTime>,
+
+	/// The file's user ID, group ID and mode, if they were recorded.
+	ownership: Option<(u32, u32, u32)>,
+}
+
+impl FileMetadata {
+	/// Creates a new `FileMetadata` from a file's metadata, optionally
+	/// including its owner and permissions.
+	pub(crate) fn new(metadata: &Metadata, record_ownership: bool) -> FileMetadata {
+		FileMetadata {
+			size: metadata.len(),
+			modified: metadata.modified().ok(),
+			changed: changed(metadata),
+			ownership: ownership(metadata).filter(|_| record_ownership),
+		}
+	}
+
+	/// Creates a new `FileMetadata` from its parts, such as when reading
+	/// saved results.
+	pub(crate) fn from_parts(
+		size: u64,
+		modified: Option<SystemTime>,
+		changed: Option<SystemTime>,
+		ownership: Option<(u32, u32, u32)>,
+	) -> FileMetadata {
+		FileMetadata {
+			size,
+			modified,
+			changed,
+			ownership,
+		}
+	}
+
+	/// Returns the file's size in bytes.
+	pub fn size(&self) -> u64 {
+		self.size
+	}
+
+	/// Returns the file's modification time, or `None` if the platform
+	/// doesn't provide it.
+	pub fn modified(&self) -> Option<SystemTime> {
+		self.modified
+	}
+
+	/// Returns the time the file's metadata or contents last changed (its
+	/// ctime), or `None` on platforms other than Unix.
+	pub fn changed(&self) -> Option<SystemTime> {
+		self.changed
+	}
+
+	/// Returns the ID of the user that owns the file, or `None` unless
+	/// `DupOptions::record_ownership()` was enabled on Unix.
+	pub fn uid(&self) -> Option<u32> {
+		self.ownership.map(|(uid, _, _)| uid)
+	}
+
+	/// Returns the ID of the group that owns the file, or `None` unless
+	/// `DupOptions::record_ownership()` was enabled on Unix.
+	pub fn gid(&self) -> Option<u32> {
+		self.ownership.map(|(_, gid, _)| gid)
+	}
+
+	/// Returns the file's type and permission bits, as given by `stat()`,
+	/// or `None` unless `DupOptions::record_ownership()` was enabled on
+	/// Unix.
+	pub fn mode(&self) -> Option<u32> {
+		self.ownership.map(|(_, _, mode)| mode)
+	}
+}
+
+impl DupResults {
+	/// Records the metadata of each file in the group at the given index,
+	/// for files not already recorded.  Files whose metadata can't be read
+	/// aren't recorded.
+	pub(crate) fn set_metadata(&mut self, i: usize) {
+		let record_ownership = self.options.record_ownership();
+		let group = &mut self.duplicates[i];
+
+		for j in 0..group.files.len() {
+			if group.metadata_at(j).is_some() {
+				continue;
+			}
+
+			if let Ok(m) = group.files[j].metadata() {
+				group.set_metadata_at(j, FileMetadata::new(&m, record_ownership));
+			}
+		}
+	}
+}
+
+impl DupGroup {
+	/// Returns the metadata of a file in this group, as read when the file
+	/// was added to the group, or `None` if the file isn't in the group or
+	/// its metadata couldn't be read.  Groups loaded by
+	/// `DupResults::import_fdupes()` have no metadata.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use std::path::PathBuf;
+	///
+	/// let dirs = vec![PathBuf::from("foo")];
+	/// let mut dup_result = dupcheck::DupResults::new();
+	///
+	/// if let Err(dup_error) = dup_result.within(&dirs) {
+	///     // Error handling
+	/// }
+	///
+	/// for group in dup_result.duplicates() {
+	///     for file in group.get_files() {
+	///         if let Some(metadata) = group.metadata(file) {
+	///             println!("{}: {} bytes", file.display(), metadata.size());
+	///         }
+	///     }
+	/// }
+	/// ```
+	pub fn metadata(&self, file: &Path) -> Option<&FileMetadata> {
+		self.metadata_at(self.position(file)?)
+	}
+}
+
+/// Returns the time a file's metadata last changed.
+#[cfg(unix)]
+fn changed(metadata: &Metadata) -> Option<SystemTime> {
+	use std::os::unix::fs::MetadataExt;
+	use std::time::Duration;
+
+	let nanos = Duration::from_nanos(metadata.ctime_nsec() as u64);
+
+	match metadata.ctime() {
+		secs if secs >= 0 => SystemTime::UNIX_EPOCH.checked_add(Duration::from_secs(secs as u64) + nanos),
+		secs => SystemTime::UNIX_EPOCH
+			.checked_sub(Duration::from_secs(secs.unsigned_abs()))
+			.and_then(|t| t.checked_add(nanos)),
+	}
+}
+
+/// Returns the time a file's metadata last changed, which isn't available on
+/// this platform.
+#[cfg(not(unix))]
+fn changed(_metadata: &Metadata) -> Option<SystemTime> {
+	None
+}
+
+/// Returns a file's user ID, group ID and mode.
+#[cfg(unix)]
+fn ownership(metadata: &Metadata) -> Option<(u32, u32, u32)> {
+	use std::os::unix::fs::MetadataExt;
+
+	Some((metadata.uid(), metadata.gid(), metadata.mode()))
+}
+
+/// Returns a file's user ID, group ID and mode, which aren't available on
+/// this platform.
+#[cfg(not(unix))]
+fn ownership(_metadata: &Metadata) -> Option<(u32, u32, u32)> {
+	None
+}
@@ -0,0 +1,147 @@
+use crate::progress::Progress;
+use crate::DupResults;
+use std::fmt::Write;
+use std::path::PathBuf;
+
+/// Totals recorded over the checks run by a `Daemon` or `Server`, and a
+/// summary of the latest results for each set of directories checked.
+#[derive(Debug, Default)]
+pub(crate) struct Metrics {
+	/// The number of checks that have finished, including failed checks.
+	scans: u64,
+
+	/// The number of files found to check.
+	files_scanned: u64,
+
+	/// The number of files whose hashes were found.
+	files_hashed: u64,
+
+	/// The total size of the files whose hashes were found.
+	bytes_hashed: u64,
+
+	/// The numbers of duplicate groups and redundant files, and the
+	/// reclaimable bytes, found by the latest successful check of each set
+	/// of directories.
+	latest: Vec<(Vec<PathBuf>, [u64; 3])>,
+}
+
+impl Metrics {
+	/// Records a finished check of the given directories, with its results
+	/// if it succeeded.
+	pub(crate) fn record(
+		&mut self,
+		dirs: &[PathBuf],
+		progress: &Progress,
+		results: Option<&DupResults>,
+	) {
+		self.scans += 1;
+		self.files_scanned += progress.files_found();
+		self.files_hashed += progress.files_hashed();
+		self.bytes_hashed += progress.bytes_hashed();
+
+		if let Some(results) = results {
+			let groups = results.duplicates().len() as u64;
+			let summary = [
+				groups,
+				results.file_count() as u64 - groups,
+				results.reclaimable_bytes(),
+			];
+
+			match self.latest.iter().position(|(d, _)| d == dirs) {
+				Some(i) => self.latest[i].1 = summary,
+				None => self.latest.push((dirs.to_vec(), summary)),
+			};
+		}
+	}
+
+	/// Returns the metrics as a JSON object.
+	pub(crate) fn json(&self) -> String {
+		let (groups, files, bytes) = self.latest.iter().fold((0, 0, 0), |acc, (_, s)| {
+			(acc.0 + s[0], acc.1 + s[1], acc.2 + s[2])
+		});
+
+		format!(
+			"{{\"scans\":{},\"files_scanned\":{},\"files_hashed\":{},\"bytes_hashed\":{},\"duplicate_groups\":{},\"duplicate_files\":{},\"reclaimable_bytes\":{}}}",
+			self.scans,
+			self.files_scanned,
+			self.files_hashed,
+			self.bytes_hashed,
+			groups,
+			files,
+			bytes,
+		)
+	}
+
+	/// Returns the metrics in the Prometheus text exposition format.  The
+	/// duplicate and reclaimable byte gauges are labelled with the
+	/// directories each was last found in.
+	pub(crate) fn prometheus(&self) -> String {
+		let mut text = String::new();
+		let counters = [
+			("scans_total", "Checks finished.", self.scans),
+			(
+				"files_scanned_total",
+				"Files found to check.",
+				self.files_scanned,
+			),
+			("files_hashed_total", "Files hashed.", self.files_hashed),
+			(
+				"bytes_hashed_total",
+				"Bytes of files hashed.",
+				self.bytes_hashed,
+			),
+		];
+
+		for (name, help, value) in counters {
+			let _ = writeln!(text, "# HELP dupcheck_{} {}", name, help);
+			let _ = writeln!(text, "# TYPE dupcheck_{} counter", name);
+			let _ = writeln!(text, "dupcheck_{} {}", name, value);
+		}
+
+		let gauges = [
+			(
+				"duplicate_groups",
+				"Duplicate groups found by the latest check.",
+			),
+			(
+				"duplicate_files",
+				"Redundant duplicate files found by the latest check.",
+			),
+			(
+				"reclaimable_bytes",
+				"Bytes reclaimable from duplicates found by the latest check.",
+			),
+		];
+
+		for (i, (name, help)) in gauges.iter().enumerate() {
+			let _ = writeln!(text, "# HELP dupcheck_{} {}", name, help);
+			let _ = writeln!(text, "# TYPE dupcheck_{} gauge", name);
+
+			for (dirs, summary) in &self.latest {
+				let _ = writeln!(
+					text,
+					"dupcheck_{}{{directories=\"{}\"}} {}",
+					name,
+					label(dirs),
+					summary[i],
+				);
+			}
+		}
+
+		text
+	}
+}
+
+/// Returns directories as a Prometheus label value, separated by commas.
+fn label(dirs: &[PathBuf]) -> String {
+	let dirs: Vec<String> = dirs
+		.iter()
+		.map(|d| d.to_string_lossy().into_owned())
+		.collect();
+
+	dirs
+		.join(",")
+		.replace('\\', "\\\\")
+		.replace('"', "\\\"")
+		.replace('\n', "\\n")
+}
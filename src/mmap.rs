@@ -0,0 +1,53 @@
+use std::io;
+use std::path::Path;
+
+/// Returns a file's BLAKE3 hash, reading it by mapping it into memory rather
+/// than copying it into a buffer.
+///
+/// If another process truncates the file while it's mapped, reading the
+/// missing pages raises `SIGBUS`, which ends the process.
+#[cfg(unix)]
+pub(crate) fn blake3_mapped(path: &Path) -> io::Result<String> {
+	use std::fs::File;
+	use std::os::unix::io::AsRawFd;
+	use std::ptr;
+	use std::slice;
+
+	let file = File::open(path)?;
+	let len = usize::try_from(file.metadata()?.len())
+		.map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "file too large to map"))?;
+
+	// Empty files can't be mapped.
+	if len == 0 {
+		return Ok(format!("{}", blake3::hash(&[])));
+	}
+
+	let map = unsafe {
+		libc::mmap(
+			ptr::null_mut(),
+			len,
+			libc::PROT_READ,
+			libc::MAP_PRIVATE,
+			file.as_raw_fd(),
+			0,
+		)
+	};
+
+	if map == libc::MAP_FAILED {
+		return Err(io::Error::last_os_error());
+	}
+
+	let hash = blake3::hash(unsafe { slice::from_raw_parts(map as *const u8, len) });
+
+	unsafe {
+		libc::munmap(map, len);
+	}
+
+	Ok(format!("{}", hash))
+}
+
+/// Files are read into a buffer on this platform.
+#[cfg(not(unix))]
+pub(crate) fn blake3_mapped(path: &Path) -> io::Result<String> {
+	crate::hash::hash_file(path)
+}
@@ -0,0 +1,648 @@
+use crate::inflate::inflate;
+use std::fs::File;
+use std::io;
+use std::io::Read;
+use std::path::Path;
+use std::str::FromStr;
+
+/// The most bytes normalized at once, both of a file's contents and of the
+/// contents decompressed from it, so normalizing a file can't use more
+/// memory than this however large the file is or however much an archive in
+/// it expands.
+pub(crate) const MAX_NORMALIZED_LEN: usize = 256 * 1024 * 1024;
+
+/// A way of normalizing file contents before they're compared by the
+/// `Stage::NormalizedHash` comparison stage, so that files differing only in
+/// ways that don't matter are found to be duplicates.
+///
+/// Each normalization only applies to files of the kind it describes, and
+/// other files are compared by their full contents.  So are files larger
+/// than 256 MiB, and archives and gzip files whose contents would
+/// decompress to more than that.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Normalization {
+	/// Ignores metadata in JPEG and PNG images, such as EXIF tags, comments
+	/// and text chunks, comparing only the image data.
+	ImageMetadata,
+
+	/// Compares zip and tar archives by the names and contents of their
+	/// members, ignoring their order, timestamps, compression and other
+	/// container metadata.
+	ArchiveMembers,
+
+	/// Compares gzip files by their decompressed contents, ignoring the file
+	/// names, timestamps and compression settings in their headers.
+	Gzip,
+
+	/// Compares text files with Windows (CRLF) line endings converted to
+	/// Unix (LF) line endings.  Files containing zero bytes aren't treated
+	/// as text.
+	LineEndings,
+
+	/// Compares text files with a byte order mark by their decoded text, so
+	/// files saved as UTF-8 with or without a byte order mark, or as UTF-16,
+	/// are found to be duplicates.
+	TextEncoding,
+}
+
+impl FromStr for Normalization {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Normalization, String> {
+		match s {
+			"image-metadata" => Ok(Normalization::ImageMetadata),
+			"archive-members" => Ok(Normalization::ArchiveMembers),
+			"gzip" => Ok(Normalization::Gzip),
+			"line-endings" => Ok(Normalization::LineEndings),
+			"text-encoding" => Ok(Normalization::TextEncoding),
+			_ => Err(format!("unknown normalization {}", s)),
+		}
+	}
+}
+
+impl Normalization {
+	/// Returns the normalized form of the given file contents, or `None` if
+	/// the normalization doesn't apply to them.
+	fn apply(&self, bytes: &[u8]) -> Option<Vec<u8>> {
+		match self {
+			Normalization::ImageMetadata => jpeg_payload(bytes).or_else(|| png_payload(bytes)),
+			Normalization::ArchiveMembers => zip_members(bytes)
+				.or_else(|| tar_members(bytes))
+				.map(members_digest),
+			Normalization::Gzip => gunzip(bytes),
+			Normalization::LineEndings => unix_line_endings(bytes),
+			Normalization::TextEncoding => decode_text(bytes),
+		}
+	}
+}
+
+/// Returns the BLAKE3 hash of a file's contents after applying the given
+/// normalizations in order.
+pub(crate) fn normalized_hash(file: &Path, normalizations: &[Normalization]) -> io::Result<String> {
+	normalized_reader_hash(File::open(file)?, normalizations)
+}
+
+/// Returns the BLAKE3 hash of everything read from a reader after applying
+/// the given normalizations in order.
+///
+/// Data longer than `MAX_NORMALIZED_LEN` isn't normalized, and is hashed as
+/// it's read rather than being held in memory.
+pub(crate) fn normalized_reader_hash<R: Read>(
+	mut reader: R,
+	normalizations: &[Normalization],
+) -> io::Result<String> {
+	let mut bytes = vec![];
+	(&mut reader)
+		.take(MAX_NORMALIZED_LEN as u64 + 1)
+		.read_to_end(&mut bytes)?;
+
+	if bytes.len() <= MAX_NORMALIZED_LEN {
+		return Ok(normalized_bytes_hash(bytes, normalizations));
+	}
+
+	let mut hasher = blake3::Hasher::new();
+	hasher.update(&bytes);
+	drop(bytes);
+	io::copy(&mut reader, &mut hasher)?;
+
+	Ok(format!("{}", hasher.finalize()))
+}
+
+/// Returns the BLAKE3 hash of the given bytes after applying the given
+/// normalizations in order.
+fn normalized_bytes_hash(mut bytes: Vec<u8>, normalizations: &[Normalization]) -> String {
+	for normalization in normalizations {
+		if let Some(normalized) = normalization.apply(&bytes) {
+			bytes = normalized;
+		}
+	}
+
+	format!("{}", blake3::hash(&bytes))
+}
+
+/// Returns the contents of a JPEG image without its application-specific
+/// segments, such as EXIF and XMP metadata, or its comments.
+fn jpeg_payload(bytes: &[u8]) -> Option<Vec<u8>> {
+	if !bytes.starts_with(&[0xFF, 0xD8]) {
+		return None;
+	}
+
+	let mut payload = vec![0xFF, 0xD8];
+	let mut pos = 2;
+
+	loop {
+		if *bytes.get(pos)? != 0xFF {
+			return None;
+		}
+
+		let marker = *bytes.get(pos + 1)?;
+
+		// Fill bytes and standalone markers have no length.
+		if marker == 0xFF {
+			pos += 1;
+			continue;
+		}
+
+		if marker == 0x01 || (0xD0..=0xD7).contains(&marker) {
+			payload.extend_from_slice(&bytes[pos..pos + 2]);
+			pos += 2;
+			continue;
+		}
+
+		let len = u16::from_be_bytes([*bytes.get(pos + 2)?, *bytes.get(pos + 3)?]) as usize;
+		let end = pos + 2 + len;
+
+		if len < 2 || end > bytes.len() {
+			return None;
+		}
+
+		// The image data follows the start of scan segment to the end.
+		if marker == 0xDA {
+			payload.extend_from_slice(&bytes[pos..]);
+			return Some(payload);
+		}
+
+		if !(0xE0..=0xEF).contains(&marker) && marker != 0xFE {
+			payload.extend_from_slice(&bytes[pos..end]);
+		}
+
+		pos = end;
+	}
+}
+
+/// Returns the contents of a PNG image without its text, EXIF and
+/// modification time chunks.
+fn png_payload(bytes: &[u8]) -> Option<Vec<u8>> {
+	const SIGNATURE: &[u8] = b"\x89PNG\r\n\x1A\n";
+
+	if !bytes.starts_with(SIGNATURE) {
+		return None;
+	}
+
+	let mut payload = SIGNATURE.to_vec();
+	let mut pos = SIGNATURE.len();
+
+	while pos < bytes.len() {
+		let len = u32::from_be_bytes(bytes.get(pos..pos + 4)?.try_into().ok()?) as usize;
+		let chunk_type = bytes.get(pos + 4..pos + 8)?;
+		let end = pos.checked_add(12)?.checked_add(len)?;
+
+		if end > bytes.len() {
+			return None;
+		}
+
+		if !matches!(chunk_type, b"tEXt" | b"zTXt" | b"iTXt" | b"eXIf" | b"tIME") {
+			payload.extend_from_slice(&bytes[pos..end]);
+		}
+
+		pos = end;
+	}
+
+	Some(payload)
+}
+
+/// Returns the decompressed contents of a gzip file, joining the contents of
+/// all of its members.
+fn gunzip(bytes: &[u8]) -> Option<Vec<u8>> {
+	if !bytes.starts_with(&[0x1F, 0x8B]) {
+		return None;
+	}
+
+	let mut contents = vec![];
+	let mut pos = 0;
+
+	while pos < bytes.len() {
+		let header = bytes.get(pos..pos + 10)?;
+
+		if header[..3] != [0x1F, 0x8B, 8] {
+			return None;
+		}
+
+		let flags = header[3];
+		pos += 10;
+
+		if flags & 0x04 != 0 {
+			pos += 2 + u16::from_le_bytes(bytes.get(pos..pos + 2)?.try_into().ok()?) as usize;
+		}
+
+		// The file name and comment are zero-terminated.
+		for flag in [0x08, 0x10] {
+			if flags & flag != 0 {
+				pos += bytes.get(pos..)?.iter().position(|b| *b == 0)? + 1;
+			}
+		}
+
+		if flags & 0x02 != 0 {
+			pos += 2;
+		}
+
+		let (member, read) = inflate(bytes.get(pos..)?, MAX_NORMALIZED_LEN - contents.len())?;
+		pos += read;
+		let size = u32::from_le_bytes(bytes.get(pos + 4..pos + 8)?.try_into().ok()?);
+
+		if size != member.len() as u32 {
+			return None;
+		}
+
+		contents.extend_from_slice(&member);
+		pos += 8;
+	}
+
+	Some(contents)
+}
+
+/// Returns the contents of a text file starting with a UTF-8 or UTF-16 byte
+/// order mark as UTF-8 without a byte order mark.  UTF-16 text with an odd
+/// number of bytes isn't valid, so isn't decoded.
+fn decode_text(bytes: &[u8]) -> Option<Vec<u8>> {
+	if let Some(text) = bytes.strip_prefix(&[0xEF, 0xBB, 0xBF]) {
+		return Some(text.to_vec());
+	}
+
+	let from_bytes: fn([u8; 2]) -> u16 = match bytes.get(..2)? {
+		[0xFF, 0xFE] => u16::from_le_bytes,
+		[0xFE, 0xFF] => u16::from_be_bytes,
+		_ => return None,
+	};
+
+	if !bytes.len().is_multiple_of(2) {
+		return None;
+	}
+
+	let units = bytes[2..].chunks_exact(2).map(|c| from_bytes([c[0], c[1]]));
+	let text = char::decode_utf16(units)
+		.collect::<Result<String, _>>()
+		.ok()?;
+
+	Some(text.into_bytes())
+}
+
+/// Returns the contents of a text file with CRLF line endings converted to
+/// LF line endings.
+fn unix_line_endings(bytes: &[u8]) -> Option<Vec<u8>> {
+	if bytes.contains(&0) || !bytes.windows(2).any(|w| w == b"\r\n") {
+		return None;
+	}
+
+	let mut normalized = Vec::with_capacity(bytes.len());
+
+	for (i, byte) in bytes.iter().enumerate() {
+		if *byte != b'\r' || bytes.get(i + 1) != Some(&b'\n') {
+			normalized.push(*byte);
+		}
+	}
+
+	Some(normalized)
+}
+
+/// An archive member's name and the BLAKE3 hash of its contents.
+type Member = (Vec<u8>, blake3::Hash);
+
+/// Returns a digest listing the names and content hashes of an archive's
+/// members, sorted by name.
+fn members_digest(mut members: Vec<Member>) -> Vec<u8> {
+	members.sort_by(|a, b| (&a.0, a.1.as_bytes()).cmp(&(&b.0, b.1.as_bytes())));
+	let mut digest = vec![];
+
+	for (name, hash) in members {
+		digest.extend_from_slice(&name);
+		digest.push(0);
+		digest.extend_from_slice(hash.as_bytes());
+	}
+
+	digest
+}
+
+/// Returns the names and content hashes of the members of a zip archive,
+/// read from its central directory.  Each member is hashed as it's
+/// decompressed, and no more than `MAX_NORMALIZED_LEN` bytes are
+/// decompressed in all.
+fn zip_members(bytes: &[u8]) -> Option<Vec<Member>> {
+	let u16_at = |pos: usize| {
+		Some(u16::from_le_bytes(
+			bytes.get(pos..pos + 2)?.try_into().ok()?,
+		))
+	};
+	let u32_at = |pos: usize| {
+		Some(u32::from_le_bytes(
+			bytes.get(pos..pos + 4)?.try_into().ok()?,
+		))
+	};
+
+	if !bytes.starts_with(b"PK\x03\x04") || bytes.len() < 22 {
+		return None;
+	}
+
+	// The end of central directory record is at the end of the archive,
+	// followed by a comment of up to 65535 bytes.
+	let end = (bytes.len().saturating_sub(22 + 65535)..=bytes.len() - 22)
+		.rev()
+		.find(|pos| bytes[*pos..].starts_with(b"PK\x05\x06"))?;
+	let count = u16_at(end + 10)? as usize;
+	let mut pos = u32_at(end + 16)? as usize;
+	let mut members = vec![];
+	let mut budget = MAX_NORMALIZED_LEN;
+
+	for _ in 0..count {
+		if u32_at(pos)? != 0x02014B50 {
+			return None;
+		}
+
+		let method = u16_at(pos + 10)?;
+		let compressed_size = u32_at(pos + 20)? as usize;
+		let name_len = u16_at(pos + 28)? as usize;
+		let extra_len = u16_at(pos + 30)? as usize;
+		let comment_len = u16_at(pos + 32)? as usize;
+		let offset = u32_at(pos + 42)? as usize;
+		let name = bytes.get(pos + 46..pos + 46 + name_len)?.to_vec();
+		pos += 46 + name_len + extra_len + comment_len;
+
+		if u32_at(offset)? != 0x04034B50 {
+			return None;
+		}
+
+		let start = offset + 30 + u16_at(offset + 26)? as usize + u16_at(offset + 28)? as usize;
+		let data = bytes.get(start..start.checked_add(compressed_size)?)?;
+		// Stored members count towards the limit too, so an archive of many
+		// members sharing the same data can't take long to hash.
+		let hash = match method {
+			0 => {
+				budget = budget.checked_sub(data.len())?;
+				blake3::hash(data)
+			}
+			8 => {
+				let (contents, _) = inflate(data, budget)?;
+				budget -= contents.len();
+				blake3::hash(&contents)
+			}
+			_ => return None,
+		};
+
+		members.push((name, hash));
+	}
+
+	Some(members)
+}
+
+/// Returns the names and content hashes of the files and directories in a
+/// tar archive.
+fn tar_members(bytes: &[u8]) -> Option<Vec<Member>> {
+	if bytes.get(257..262) != Some(b"ustar") {
+		return None;
+	}
+
+	let mut members = vec![];
+	let mut long_name = None;
+	let mut pos = 0;
+
+	loop {
+		let header = bytes.get(pos..pos + 512)?;
+
+		if header.iter().all(|b| *b == 0) {
+			return Some(members);
+		}
+
+		let field = |start: usize, len: usize| {
+			let f = &header[start..start + len];
+			&f[..f.iter().position(|b| *b == 0).unwrap_or(len)]
+		};
+		let size_field = String::from_utf8_lossy(field(124, 12));
+		let size = usize::from_str_radix(size_field.trim(), 8).ok()?;
+		let contents = bytes.get(pos + 512..(pos + 512).checked_add(size)?)?;
+		pos += 512 + size.div_ceil(512) * 512;
+
+		let name = match long_name.take() {
+			Some(name) => name,
+			None => {
+				let mut name = field(345, 155).to_vec();
+
+				if !name.is_empty() {
+					name.push(b'/');
+				}
+
+				name.extend_from_slice(field(0, 100));
+				name
+			}
+		};
+
+		match header[156] {
+			b'0' | b'\0' | b'7' => members.push((name, blake3::hash(contents))),
+			b'5' => members.push((name, blake3::hash(&[]))),
+			b'1' | b'2' => members.push((name, blake3::hash(field(157, 100)))),
+			// GNU tar gives long names in a member before the one they name.
+			b'L' => {
+				let end = contents.iter().position(|b| *b == 0).unwrap_or(size);
+				long_name = Some(contents[..end].to_vec());
+			}
+			_ => {}
+		};
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// "hello hello hello" compressed with fixed codes.
+	const DEFLATED: [u8; 10] = [203, 72, 205, 201, 201, 87, 200, 64, 144, 0];
+
+	/// Returns a zip archive of the given members, as their names,
+	/// compression methods, uncompressed sizes and data, in order.
+	fn zip(members: &[(&str, u16, u32, &[u8])]) -> Vec<u8> {
+		let mut archive = vec![];
+		let mut directory = vec![];
+
+		for (name, method, size, data) in members {
+			let offset = archive.len() as u32;
+			let mut header = vec![];
+			header.extend_from_slice(&[0; 4]);
+			header.extend_from_slice(&method.to_le_bytes());
+			header.extend_from_slice(&[0; 8]);
+			header.extend_from_slice(&(data.len() as u32).to_le_bytes());
+			header.extend_from_slice(&size.to_le_bytes());
+			header.extend_from_slice(&(name.len() as u16).to_le_bytes());
+
+			archive.extend_from_slice(b"PK\x03\x04");
+			archive.extend_from_slice(&header);
+			archive.extend_from_slice(&[0; 2]);
+			archive.extend_from_slice(name.as_bytes());
+			archive.extend_from_slice(data);
+
+			directory.extend_from_slice(b"PK\x01\x02");
+			directory.extend_from_slice(&[0; 2]);
+			directory.extend_from_slice(&header);
+			directory.extend_from_slice(&[0; 12]);
+			directory.extend_from_slice(&offset.to_le_bytes());
+			directory.extend_from_slice(name.as_bytes());
+		}
+
+		let directory_offset = archive.len() as u32;
+		archive.extend_from_slice(&directory);
+		archive.extend_from_slice(b"PK\x05\x06");
+		archive.extend_from_slice(&[0; 4]);
+		archive.extend_from_slice(&(members.len() as u16).to_le_bytes());
+		archive.extend_from_slice(&(members.len() as u16).to_le_bytes());
+		archive.extend_from_slice(&(directory.len() as u32).to_le_bytes());
+		archive.extend_from_slice(&directory_offset.to_le_bytes());
+		archive.extend_from_slice(&[0; 2]);
+
+		archive
+	}
+
+	/// Returns a ustar header block for a member.
+	fn tar_header(name: &[u8], size: usize, kind: u8) -> Vec<u8> {
+		let mut header = vec![0; 512];
+		header[..name.len()].copy_from_slice(name);
+		header[124..135].copy_from_slice(format!("{:011o}", size).as_bytes());
+		header[156] = kind;
+		header[257..262].copy_from_slice(b"ustar");
+		header
+	}
+
+	/// Returns a tar member's contents padded to a whole number of blocks.
+	fn tar_contents(contents: &[u8]) -> Vec<u8> {
+		let mut block = contents.to_vec();
+		block.resize(contents.len().div_ceil(512) * 512, 0);
+		block
+	}
+
+	#[test]
+	fn zip_members_ignore_order() {
+		let a = zip(&[("a.txt", 0, 2, b"aa"), ("b.txt", 0, 2, b"bb")]);
+		let b = zip(&[("b.txt", 0, 2, b"bb"), ("a.txt", 0, 2, b"aa")]);
+		let c = zip(&[("a.txt", 0, 2, b"aa"), ("b.txt", 0, 2, b"bc")]);
+		let digest = |bytes: &[u8]| Normalization::ArchiveMembers.apply(bytes);
+
+		assert!(digest(&a).is_some());
+		assert_eq!(digest(&a), digest(&b));
+		assert_ne!(digest(&a), digest(&c));
+	}
+
+	#[test]
+	fn zip_members_ignore_compression() {
+		let stored = zip(&[("a.txt", 0, 17, b"hello hello hello")]);
+		let deflated = zip(&[("a.txt", 8, 17, &DEFLATED)]);
+		let digest = |bytes: &[u8]| Normalization::ArchiveMembers.apply(bytes);
+
+		assert!(digest(&stored).is_some());
+		assert_eq!(digest(&stored), digest(&deflated));
+	}
+
+	#[test]
+	fn zip_with_bad_member() {
+		let truncated = zip(&[("a.txt", 8, 17, &DEFLATED[..5])]);
+		let unknown_method = zip(&[("a.txt", 12, 2, b"aa")]);
+
+		assert_eq!(zip_members(&truncated), None);
+		assert_eq!(zip_members(&unknown_method), None);
+	}
+
+	#[test]
+	fn tar_gnu_long_name() {
+		let long_name = "long-directory-name/".repeat(6) + "file.txt";
+		let mut archive = tar_header(b"././@LongLink", long_name.len() + 1, b'L');
+		archive.extend(tar_contents(format!("{}\0", long_name).as_bytes()));
+		archive.extend(tar_header(&long_name.as_bytes()[..100], 5, b'0'));
+		archive.extend(tar_contents(b"hello"));
+		archive.extend([0; 1024]);
+
+		let members = tar_members(&archive).unwrap();
+		assert_eq!(
+			members,
+			vec![(long_name.into_bytes(), blake3::hash(b"hello"))]
+		);
+	}
+
+	#[test]
+	fn gzip_ignores_header() {
+		let a = [
+			31, 139, 8, 8, 1, 0, 0, 0, 2, 255, 97, 46, 116, 120, 116, 0, 203, 72, 205, 201, 201, 231, 2, 0,
+			32, 48, 58, 54, 6, 0, 0, 0,
+		];
+		let b = [
+			31, 139, 8, 8, 159, 134, 1, 0, 2, 255, 111, 116, 104, 101, 114, 45, 110, 97, 109, 101, 46, 116,
+			120, 116, 0, 203, 72, 205, 201, 201, 231, 2, 0, 32, 48, 58, 54, 6, 0, 0, 0,
+		];
+
+		assert_eq!(gunzip(&a), Some(b"hello\n".to_vec()));
+		assert_eq!(gunzip(&b), Some(b"hello\n".to_vec()));
+		assert_eq!(gunzip(&a[..a.len() - 1]), None);
+	}
+
+	#[test]
+	fn jpeg_without_metadata() {
+		let plain = [
+			0xFF, 0xD8, 0xFF, 0xDB, 0, 4, 1, 2, 0xFF, 0xDA, 0, 2, 9, 9, 0xFF, 0xD9,
+		];
+		let tagged = [
+			0xFF, 0xD8, 0xFF, 0xE1, 0, 4, b'a', b'b', 0xFF, 0xDB, 0, 4, 1, 2, 0xFF, 0xFE, 0, 3, b'c', 0xFF,
+			0xDA, 0, 2, 9, 9, 0xFF, 0xD9,
+		];
+
+		assert_eq!(jpeg_payload(&tagged), Some(plain.to_vec()));
+	}
+
+	#[test]
+	fn png_without_metadata() {
+		let chunk = |kind: &[u8], data: &[u8]| {
+			let mut chunk = (data.len() as u32).to_be_bytes().to_vec();
+			chunk.extend_from_slice(kind);
+			chunk.extend_from_slice(data);
+			chunk.extend_from_slice(&[0; 4]);
+			chunk
+		};
+		let mut plain = b"\x89PNG\r\n\x1A\n".to_vec();
+		plain.extend(chunk(b"IHDR", &[1; 13]));
+		plain.extend(chunk(b"IEND", &[]));
+		let mut tagged = b"\x89PNG\r\n\x1A\n".to_vec();
+		tagged.extend(chunk(b"IHDR", &[1; 13]));
+		tagged.extend(chunk(b"tEXt", b"Comment\0hi"));
+		tagged.extend(chunk(b"IEND", &[]));
+
+		assert_eq!(png_payload(&tagged), Some(plain));
+	}
+
+	#[test]
+	fn utf16_text() {
+		assert_eq!(decode_text(b"\xFF\xFEh\0i\0"), Some(b"hi".to_vec()));
+		assert_eq!(decode_text(b"\xFE\xFF\0h\0i"), Some(b"hi".to_vec()));
+		assert_eq!(decode_text(b"\xFF\xFEh\0i\0\n"), None);
+	}
+
+	#[test]
+	fn archives_over_the_limit_are_not_normalized() {
+		// Directory entries sharing one member's data, as in zip bombs, each
+		// count towards the limit.
+		let data = vec![0; 1024 * 1024];
+		let single = zip(&[("a", 0, data.len() as u32, &data)]);
+		let directory_start = single.len() - 22 - 46 - 1;
+		let entry = &single[directory_start..single.len() - 22];
+		let bomb = |count: u16| {
+			let mut archive = single[..directory_start].to_vec();
+
+			for _ in 0..count {
+				archive.extend_from_slice(entry);
+			}
+
+			archive.extend_from_slice(b"PK\x05\x06");
+			archive.extend_from_slice(&[0; 4]);
+			archive.extend_from_slice(&count.to_le_bytes());
+			archive.extend_from_slice(&count.to_le_bytes());
+			archive.extend_from_slice(&(entry.len() as u32 * count as u32).to_le_bytes());
+			archive.extend_from_slice(&(directory_start as u32).to_le_bytes());
+			archive.extend_from_slice(&[0; 2]);
+			archive
+		};
+
+		assert_eq!(zip_members(&bomb(256)).map(|m| m.len()), Some(256));
+		assert_eq!(zip_members(&bomb(257)), None);
+	}
+
+	#[test]
+	fn long_data_is_hashed_without_normalizing() {
+		let data = || (&b"a\r\nb"[..]).chain(io::repeat(b'c').take(MAX_NORMALIZED_LEN as u64));
+		let hash = normalized_reader_hash(data(), &[Normalization::LineEndings]);
+
+		assert_eq!(hash.unwrap(), crate::hash::hash_reader(data()).unwrap());
+	}
+}
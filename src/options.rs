@@ -0,0 +1,864 @@
+use crate::benchmark::ReadMethod;
+use crate::cache::HashCache;
+use crate::category::Category;
+use crate::content_type::{content_type, matches};
+use crate::events::{ScanEvent, SkipReason};
+use crate::handle::ScanHandle;
+use crate::hash::DEFAULT_BUFFER_SIZE;
+use crate::normalize::Normalization;
+use crate::pipeline::{Pipeline, Stage};
+use crate::progress::Progress;
+use crate::retry::RetryPolicy;
+use crate::signature::Signature;
+use crate::skipped::SkipLog;
+use crate::utilities::absolute;
+use crate::walker::{BuiltinWalker, Walker};
+use crate::DupGroup;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::Sender;
+use std::sync::Arc;
+
+/// The mount points of the kernel's virtual filesystems, which are skipped
+/// unless `DupOptions::scan_virtual_filesystems()` is enabled.
+#[cfg(target_os = "linux")]
+const VIRTUAL_FILESYSTEMS: &[&str] = &["/proc", "/sys", "/dev", "/run"];
+
+/// Virtual filesystems aren't skipped on this platform.
+#[cfg(not(target_os = "linux"))]
+const VIRTUAL_FILESYSTEMS: &[&str] = &[];
+
+/// Options controlling how duplicate file checks are run.
+#[derive(Clone, Debug, Default)]
+pub struct DupOptions {
+	/// The comparison stages that files are run through.
+	pipeline: Pipeline,
+
+	/// How failed reads of file metadata or contents are retried.
+	retry_policy: RetryPolicy,
+
+	/// Whether every checked file is hashed and recorded.
+	hash_all: bool,
+
+	/// Whether files given to `DupResults::of()` are kept apart from the
+	/// files in their groups.
+	exclude_queried: bool,
+
+	/// Whether system junk files are skipped when finding files in
+	/// directories.
+	skip_junk: bool,
+
+	/// Whether cloud storage placeholder files are skipped when finding
+	/// files in directories.
+	skip_placeholders: bool,
+
+	/// Directories that are skipped when finding files in directories.
+	excluded_dirs: Vec<PathBuf>,
+
+	/// Whether the kernel's virtual filesystems are searched when finding
+	/// files in directories.
+	scan_virtual_filesystems: bool,
+
+	/// Whether the sizes of all files are read before they're compared.
+	prescan: bool,
+
+	/// Whether searching directories, reading sizes and hashing files run one
+	/// after another rather than at the same time.
+	sequential: bool,
+
+	/// The walker used to find files in directories, if not the default.
+	walker: Option<Arc<dyn Walker>>,
+
+	/// Whether the kernel is advised not to cache hashed files.
+	fadvise: bool,
+
+	/// How files are read to hash them.
+	read_method: ReadMethod,
+
+	/// The number of bytes read from a file at a time, if not the default.
+	buffer_size: Option<usize>,
+
+	/// Whether checks lower the priority of the threads they run on.
+	low_priority: bool,
+
+	/// Whether the owners and permissions of files in duplicate groups are
+	/// recorded.
+	record_ownership: bool,
+
+	/// Whether the files and directories skipped by checks are recorded.
+	record_skipped: bool,
+
+	/// Whether checks record why files didn't end up in groups.
+	explainable: bool,
+
+	/// The files and directories skipped by the running check, if they're
+	/// recorded.
+	skip_log: SkipLog,
+
+	/// The fewest files a duplicate group must have to be kept.
+	min_group_size: usize,
+
+	/// The fewest bytes that must be reclaimable from a duplicate group for
+	/// it to be kept.
+	min_redundant_bytes: u64,
+
+	/// MIME types of files to include when finding files in directories, or
+	/// all types if empty.
+	content_types: Vec<String>,
+
+	/// MIME types of files to exclude when finding files in directories.
+	excluded_content_types: Vec<String>,
+
+	/// Categories of files to include when finding files in directories, or
+	/// all files if empty.
+	categories: Vec<Category>,
+
+	/// The signatures of files to leave out of checks.
+	excluded_signatures: Vec<Signature>,
+
+	/// Normalizations applied to file contents by the normalized hash
+	/// comparison stage.
+	normalizations: Vec<Normalization>,
+
+	/// BLAKE3 hashes of files whose duplicates are left out of the results.
+	ignored_hashes: Vec<String>,
+
+	/// A cache of file hashes shared between checks, if any.
+	hash_cache: Option<HashCache>,
+
+	/// Counters recording the progress of checks, if any.
+	progress: Option<Progress>,
+
+	/// The sender that groups are sent to as they're found, if any.
+	group_sender: Option<Sender<DupGroup>>,
+
+	/// The sender that events are sent to during checks, if any.
+	event_sender: Option<Sender<ScanEvent>>,
+
+	/// The handle used to pause and resume checks, if any.
+	scan_handle: Option<ScanHandle>,
+}
+
+impl DupOptions {
+	/// Creates a new `DupOptions` with the default settings.
+	pub fn new() -> DupOptions {
+		DupOptions::default()
+	}
+
+	/// Returns a reference to the comparison pipeline.
+	pub fn pipeline(&self) -> &Pipeline {
+		&self.pipeline
+	}
+
+	/// Sets the comparison pipeline.
+	pub fn set_pipeline(&mut self, pipeline: Pipeline) {
+		self.pipeline = pipeline;
+	}
+
+	/// Returns the policy for retrying failed reads of file metadata or
+	/// contents.
+	pub fn retry_policy(&self) -> RetryPolicy {
+		self.retry_policy
+	}
+
+	/// Sets the policy for retrying failed reads of file metadata or
+	/// contents.  By default, failed reads aren't retried.
+	pub fn set_retry_policy(&mut self, retry_policy: RetryPolicy) {
+		self.retry_policy = retry_policy;
+	}
+
+	/// Returns whether every checked file is hashed and recorded.
+	pub fn hash_all(&self) -> bool {
+		self.hash_all
+	}
+
+	/// Sets whether every checked file is hashed and recorded, rather than
+	/// only those that may have duplicates, so that a manifest of all checked
+	/// files can be written with `DupResults::write_manifest()`.  This is
+	/// disabled by default.
+	pub fn set_hash_all(&mut self, hash_all: bool) {
+		self.hash_all = hash_all;
+	}
+
+	/// Returns whether files given to `DupResults::of()` are kept apart from
+	/// the files in their groups.
+	pub fn exclude_queried(&self) -> bool {
+		self.exclude_queried
+	}
+
+	/// Sets whether the files given to `DupResults::of()` are left out of
+	/// the files of the groups they're found in, so that the groups only
+	/// contain their other copies, with the given files instead given by
+	/// `DupGroup::queried_files()`.  Groups containing only given files are
+	/// then left out of the results.  This is disabled by default.
+	pub fn set_exclude_queried(&mut self, exclude_queried: bool) {
+		self.exclude_queried = exclude_queried;
+	}
+
+	/// Returns whether system junk files are skipped when finding files in
+	/// directories.
+	pub fn skip_junk(&self) -> bool {
+		self.skip_junk
+	}
+
+	/// Sets whether files created by operating systems and file managers to
+	/// hold folder settings, thumbnails or extended attributes -- `.DS_Store`,
+	/// `Thumbs.db`, `desktop.ini` and AppleDouble `._*` files -- are skipped
+	/// when finding files in directories.  This is disabled by default.
+	pub fn set_skip_junk(&mut self, skip_junk: bool) {
+		self.skip_junk = skip_junk;
+	}
+
+	/// Returns whether cloud storage placeholder files are skipped when
+	/// finding files in directories.
+	pub fn skip_placeholders(&self) -> bool {
+		self.skip_placeholders
+	}
+
+	/// Sets whether online-only files from cloud storage services such as
+	/// OneDrive, iCloud Drive and Dropbox, whose contents aren't stored
+	/// locally, are skipped when finding files in directories, rather than
+	/// downloaded when they're read.  These are found using the attributes
+	/// set by the Windows cloud files API and macOS's dataless file flag, and
+	/// on Unix, by files having a length but no storage allocated to them,
+	/// which also skips files made up entirely of holes.  This is disabled
+	/// by default.
+	pub fn set_skip_placeholders(&mut self, skip_placeholders: bool) {
+		self.skip_placeholders = skip_placeholders;
+	}
+
+	/// Returns a reference to the directories that are skipped when finding
+	/// files in directories, other than the virtual filesystems skipped
+	/// unless `scan_virtual_filesystems()` is enabled.
+	pub fn excluded_dirs(&self) -> &[PathBuf] {
+		&self.excluded_dirs
+	}
+
+	/// Sets directories that are skipped, along with everything within them,
+	/// when they're found within the directories being checked.  Directories
+	/// given to be checked themselves aren't skipped.  Relative paths are
+	/// resolved against the current directory.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use std::path::PathBuf;
+	///
+	/// let mut options = dupcheck::DupOptions::new();
+	/// options.set_excluded_dirs(vec![PathBuf::from("foo/node_modules")]);
+	///
+	/// let dirs = vec![PathBuf::from("foo")];
+	/// let mut dup_result = dupcheck::DupResults::with_options(options);
+	///
+	/// if let Err(dup_error) = dup_result.within(&dirs) {
+	///     // Error handling
+	/// }
+	/// ```
+	pub fn set_excluded_dirs(&mut self, excluded_dirs: Vec<PathBuf>) {
+		self.excluded_dirs = excluded_dirs.iter().map(|d| absolute(d)).collect();
+	}
+
+	/// Returns whether the kernel's virtual filesystems are searched when
+	/// finding files in directories.
+	pub fn scan_virtual_filesystems(&self) -> bool {
+		self.scan_virtual_filesystems
+	}
+
+	/// Sets whether, on Linux, `/proc`, `/sys`, `/dev` and `/run` are
+	/// searched when they're found within the directories being checked.
+	/// Their files are created by the kernel and running programs rather
+	/// than stored on disk, and reading some of them never finishes, so
+	/// they're skipped by default.
+	pub fn set_scan_virtual_filesystems(&mut self, scan_virtual_filesystems: bool) {
+		self.scan_virtual_filesystems = scan_virtual_filesystems;
+	}
+
+	/// Returns whether a directory found within the directories being
+	/// checked is one to skip.
+	pub(crate) fn is_excluded_dir(&self, dir: &Path) -> bool {
+		let virtual_dirs = match self.scan_virtual_filesystems {
+			true => &[][..],
+			false => VIRTUAL_FILESYSTEMS,
+		};
+
+		// Paths are only made absolute for directories with the same name
+		// as an excluded one, so most directories are cheap to check.
+		let name = dir.file_name();
+		let mut excluded = self
+			.excluded_dirs
+			.iter()
+			.map(|d| d.as_path())
+			.chain(virtual_dirs.iter().map(Path::new))
+			.filter(|d| d.file_name() == name)
+			.peekable();
+
+		if excluded.peek().is_none() {
+			return false;
+		}
+
+		let dir = absolute(dir);
+		excluded.any(|d| d == dir)
+	}
+
+	/// Returns whether the sizes of all files are read before they're
+	/// compared.
+	pub fn prescan(&self) -> bool {
+		self.prescan
+	}
+
+	/// Sets whether the sizes of all files are read before they're compared,
+	/// so that the progress counters know how many files and bytes will need
+	/// to be hashed before hashing starts, however the comparison pipeline
+	/// is ordered.  The sizes are reused by the size comparison stage.  This
+	/// is disabled by default.
+	pub fn set_prescan(&mut self, prescan: bool) {
+		self.prescan = prescan;
+	}
+
+	/// Returns whether `DupResults::within()` searches directories, reads
+	/// files' sizes and hashes files one after another rather than at the
+	/// same time.
+	pub fn sequential(&self) -> bool {
+		self.sequential
+	}
+
+	/// Sets whether `DupResults::within()` finishes searching directories
+	/// before comparing any files.
+	///
+	/// By default, if the comparison pipeline compares sizes and then full
+	/// hashes, files are hashed on other threads as soon as they're found to
+	/// have the same size as another file, while the directories are still
+	/// being searched.  Reading from several places at once can be slower on
+	/// hard drives, where this can be enabled.  Checks that read the sizes
+	/// or hashes of all files first with `set_prescan()` or `set_hash_all()`
+	/// are always sequential.
+	pub fn set_sequential(&mut self, sequential: bool) {
+		self.sequential = sequential;
+	}
+
+	/// Returns the walker used to find files in directories.
+	pub fn walker(&self) -> &dyn Walker {
+		self.walker.as_deref().unwrap_or(&BuiltinWalker::Recursive)
+	}
+
+	/// Sets the walker used to find files in directories, which may be one
+	/// of the `BuiltinWalker`s or any other `Walker`.  By default, or if
+	/// `None` is given, `BuiltinWalker::Recursive` is used.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use dupcheck::BuiltinWalker;
+	/// use std::path::PathBuf;
+	/// use std::sync::Arc;
+	///
+	/// let mut options = dupcheck::DupOptions::new();
+	/// options.set_walker(Some(Arc::new(BuiltinWalker::Sorted)));
+	///
+	/// let dirs = vec![PathBuf::from("foo")];
+	/// let mut dup_result = dupcheck::DupResults::with_options(options);
+	///
+	/// if let Err(dup_error) = dup_result.within(&dirs) {
+	///     // Error handling
+	/// }
+	/// ```
+	pub fn set_walker(&mut self, walker: Option<Arc<dyn Walker>>) {
+		self.walker = walker;
+	}
+
+	/// Returns whether the kernel is advised not to cache hashed files.
+	pub fn fadvise(&self) -> bool {
+		self.fadvise
+	}
+
+	/// Sets whether, on Linux, the kernel is advised with `posix_fadvise()`
+	/// that files are read sequentially when hashing them and that their
+	/// data isn't needed afterwards, so large checks don't evict the rest of
+	/// the system's data from the page cache.  This has no effect on other
+	/// platforms, and is disabled by default.
+	pub fn set_fadvise(&mut self, fadvise: bool) {
+		self.fadvise = fadvise;
+	}
+
+	/// Returns how files are read to hash them.
+	pub fn read_method(&self) -> ReadMethod {
+		self.read_method
+	}
+
+	/// Sets how files are read to hash them, which can be chosen for the
+	/// storage being checked with `Benchmark::run()`.  Files are read into a
+	/// buffer by default.  If `fadvise()` is enabled, files are always read
+	/// into a buffer.
+	pub fn set_read_method(&mut self, read_method: ReadMethod) {
+		self.read_method = read_method;
+	}
+
+	/// Returns the number of bytes read from a file at a time when hashing
+	/// or comparing it.
+	pub fn buffer_size(&self) -> usize {
+		self.buffer_size.unwrap_or(DEFAULT_BUFFER_SIZE)
+	}
+
+	/// Sets the number of bytes read from a file at a time when hashing it
+	/// with `ReadMethod::Buffered` or comparing it with `Stage::ByteCompare`.
+	/// Larger reads suit storage with high latency, such as spinning disks
+	/// and network shares, while smaller reads use less memory.  A size of 0
+	/// is treated as 1.  By default, `DEFAULT_BUFFER_SIZE` is used.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// let mut options = dupcheck::DupOptions::new();
+	/// options.set_buffer_size(1024 * 1024);
+	/// ```
+	pub fn set_buffer_size(&mut self, buffer_size: usize) {
+		self.buffer_size = Some(buffer_size);
+	}
+
+	/// Returns whether checks lower the priority of the threads they run on.
+	pub fn low_priority(&self) -> bool {
+		self.low_priority
+	}
+
+	/// Sets whether checks lower the CPU and I/O priority of the threads
+	/// they run on, so that background checks don't make the computer
+	/// sluggish.  This uses `setpriority()` and the idle I/O class on Linux,
+	/// the background state on macOS and background processing mode on
+	/// Windows.  This is disabled by default.
+	///
+	/// The priority of a thread can't be raised again without extra
+	/// privileges, so it stays lowered after the check finishes.  Checks
+	/// should be run on their own threads if this is a problem.
+	pub fn set_low_priority(&mut self, low_priority: bool) {
+		self.low_priority = low_priority;
+	}
+
+	/// Returns whether the owners and permissions of files in duplicate
+	/// groups are recorded.
+	pub fn record_ownership(&self) -> bool {
+		self.record_ownership
+	}
+
+	/// Sets whether, on Unix, the user and group IDs and permission bits of
+	/// files in duplicate groups are recorded along with their other
+	/// metadata, to be given by `FileMetadata::uid()`, `FileMetadata::gid()`
+	/// and `FileMetadata::mode()` and summarised by
+	/// `DupResults::owner_stats()`.  This has no effect on other platforms,
+	/// and is disabled by default.
+	pub fn set_record_ownership(&mut self, record_ownership: bool) {
+		self.record_ownership = record_ownership;
+	}
+
+	/// Returns whether the files and directories skipped by checks are
+	/// recorded.
+	pub fn record_skipped(&self) -> bool {
+		self.record_skipped
+	}
+
+	/// Sets whether the files and directories skipped by checks, such as
+	/// files left out by the category filters or excluded directories, are
+	/// recorded with the reason they were skipped, to be given by
+	/// `DupResults::skipped()`.  Checks for duplicates of given files skip
+	/// every file of another size, so this can record many files.  This is
+	/// disabled by default.
+	pub fn set_record_skipped(&mut self, record_skipped: bool) {
+		self.record_skipped = record_skipped;
+	}
+
+	/// Returns whether checks record why files didn't end up in duplicate
+	/// groups.
+	pub fn explainable(&self) -> bool {
+		self.explainable
+	}
+
+	/// Sets whether checks record the files they skip and the comparison
+	/// stage at which each file found to have no duplicates was found to be
+	/// unique, so that `DupResults::explain()` can tell why any file found
+	/// by a check did or didn't end up in a group.  This records every file
+	/// checked, so it's disabled by default.
+	pub fn set_explainable(&mut self, explainable: bool) {
+		self.explainable = explainable;
+	}
+
+	/// Returns the fewest files a duplicate group must have to be kept.
+	pub fn min_group_size(&self) -> usize {
+		self.min_group_size
+	}
+
+	/// Sets the fewest files, including any files kept apart by
+	/// `exclude_queried()`, that a duplicate group must have to be kept in
+	/// the results.  Smaller groups are dropped as soon as they're found, so
+	/// they aren't sent to the group sender or held in memory.  Groups are
+	/// measured when each check finds them, so a group dropped by one check
+	/// isn't kept if a later check finds more copies.  By default, all
+	/// groups are kept.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use std::path::PathBuf;
+	///
+	/// let mut options = dupcheck::DupOptions::new();
+	/// options.set_min_group_size(3);
+	///
+	/// let dirs = vec![PathBuf::from("foo")];
+	/// let mut dup_result = dupcheck::DupResults::with_options(options);
+	///
+	/// if let Err(dup_error) = dup_result.within(&dirs) {
+	///     // Error handling
+	/// }
+	/// ```
+	pub fn set_min_group_size(&mut self, min_group_size: usize) {
+		self.min_group_size = min_group_size;
+	}
+
+	/// Returns the fewest bytes that must be reclaimable from a duplicate
+	/// group for it to be kept.
+	pub fn min_redundant_bytes(&self) -> u64 {
+		self.min_redundant_bytes
+	}
+
+	/// Sets the fewest bytes, as given by `DupGroup::reclaimable_bytes()`,
+	/// that must be reclaimable from a duplicate group for it to be kept in
+	/// the results.  Groups are dropped as for `set_min_group_size()`.  By
+	/// default, all groups are kept.
+	pub fn set_min_redundant_bytes(&mut self, min_redundant_bytes: u64) {
+		self.min_redundant_bytes = min_redundant_bytes;
+	}
+
+	/// Returns the MIME types of files to include when finding files in
+	/// directories.
+	pub fn content_types(&self) -> &[String] {
+		&self.content_types
+	}
+
+	/// Sets the MIME types of files to include when finding files in
+	/// directories, such as `image/jpeg`, or `image/*` for all image types.
+	/// Types are detected from the magic bytes at the start of each file's
+	/// contents rather than its extension, and files whose type isn't
+	/// recognised aren't included.  If empty, which is the default, files of
+	/// all types are included.
+	pub fn set_content_types(&mut self, content_types: Vec<String>) {
+		self.content_types = content_types;
+	}
+
+	/// Returns the MIME types of files to exclude when finding files in
+	/// directories.
+	pub fn excluded_content_types(&self) -> &[String] {
+		&self.excluded_content_types
+	}
+
+	/// Sets the MIME types of files to exclude when finding files in
+	/// directories, in the same form as `set_content_types()`.  Files whose
+	/// type isn't recognised aren't excluded.
+	pub fn set_excluded_content_types(&mut self, excluded_content_types: Vec<String>) {
+		self.excluded_content_types = excluded_content_types;
+	}
+
+	/// Returns the categories of files to include when finding files in
+	/// directories.
+	pub fn categories(&self) -> &[Category] {
+		&self.categories
+	}
+
+	/// Sets the categories of files to include when finding files in
+	/// directories.  Files are included if their extension or their MIME
+	/// type, detected from their contents, belongs to any of the categories.
+	/// If empty, which is the default, files of all categories are included.
+	pub fn set_categories(&mut self, categories: Vec<Category>) {
+		self.categories = categories;
+	}
+
+	/// Returns a reference to the signatures of files to leave out of
+	/// checks.
+	pub fn excluded_signatures(&self) -> &[Signature] {
+		&self.excluded_signatures
+	}
+
+	/// Sets the signatures of files to leave out of checks, such as
+	/// databases or lock files that are expected to be duplicated, whatever
+	/// their names.  Files are left out if they start with any of the
+	/// signatures.  Only files with the same size as another file are read
+	/// for their signatures, and only their first few bytes are read, so
+	/// files left out are never read in full.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use dupcheck::Signature;
+	/// use std::path::PathBuf;
+	///
+	/// // SQLite databases.
+	/// let mut options = dupcheck::DupOptions::new();
+	/// options.set_excluded_signatures(vec![Signature::new(b"SQLite format 3\0".to_vec())]);
+	///
+	/// let dirs = vec![PathBuf::from("foo")];
+	/// let mut dup_result = dupcheck::DupResults::with_options(options);
+	///
+	/// if let Err(dup_error) = dup_result.within(&dirs) {
+	///     // Error handling
+	/// }
+	/// ```
+	pub fn set_excluded_signatures(&mut self, excluded_signatures: Vec<Signature>) {
+		self.excluded_signatures = excluded_signatures;
+	}
+
+	/// Returns a reference to the normalizations applied to file contents by
+	/// the normalized hash comparison stage.
+	pub fn normalizations(&self) -> &[Normalization] {
+		&self.normalizations
+	}
+
+	/// Sets the normalizations applied, in order, to file contents by the
+	/// normalized hash comparison stage, and replaces the comparison pipeline
+	/// with one containing only that stage, or the default pipeline if no
+	/// normalizations are given.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use dupcheck::Normalization;
+	///
+	/// let mut options = dupcheck::DupOptions::new();
+	/// options.set_normalizations(vec![Normalization::ImageMetadata]);
+	///
+	/// let mut dup_result = dupcheck::DupResults::with_options(options);
+	/// ```
+	pub fn set_normalizations(&mut self, normalizations: Vec<Normalization>) {
+		self.pipeline = match normalizations.is_empty() {
+			true => Pipeline::default(),
+			false => Pipeline::new(&[Stage::NormalizedHash]),
+		};
+		self.normalizations = normalizations;
+	}
+
+	/// Returns a reference to the BLAKE3 hashes of files whose duplicates are
+	/// left out of the results.
+	pub fn ignored_hashes(&self) -> &[String] {
+		&self.ignored_hashes
+	}
+
+	/// Sets the BLAKE3 hashes of files whose duplicates are left out of the
+	/// results, such as standard licence files or empty placeholder files
+	/// that are expected to be duplicated.
+	pub fn set_ignored_hashes(&mut self, ignored_hashes: Vec<String>) {
+		self.ignored_hashes = ignored_hashes
+			.into_iter()
+			.map(|h| h.to_ascii_lowercase())
+			.collect();
+	}
+
+	/// Returns whether a BLAKE3 hash is one whose duplicates are left out of
+	/// the results.
+	pub(crate) fn is_ignored_hash(&self, hash: &str) -> bool {
+		self.ignored_hashes.iter().any(|h| h == hash)
+	}
+
+	/// Returns a reference to the cache of file hashes shared between
+	/// checks, if any.
+	pub fn hash_cache(&self) -> Option<&HashCache> {
+		self.hash_cache.as_ref()
+	}
+
+	/// Sets a cache of file hashes to use, so files that haven't changed
+	/// since they were hashed by an earlier check aren't read again.  By
+	/// default, no cache is used.
+	pub fn set_hash_cache(&mut self, hash_cache: Option<HashCache>) {
+		self.hash_cache = hash_cache;
+	}
+
+	/// Returns a reference to the counters recording the progress of checks,
+	/// if any.
+	pub fn progress(&self) -> Option<&Progress> {
+		self.progress.as_ref()
+	}
+
+	/// Sets counters to record the progress of checks in, which can be read
+	/// from another thread while a check runs.  By default, progress isn't
+	/// recorded.
+	pub fn set_progress(&mut self, progress: Option<Progress>) {
+		self.progress = progress;
+	}
+
+	/// Returns a reference to the sender that groups are sent to as they're
+	/// found, if any.
+	pub fn group_sender(&self) -> Option<&Sender<DupGroup>> {
+		self.group_sender.as_ref()
+	}
+
+	/// Sets a sender to send duplicate file groups to as soon as they're
+	/// found, so they can be processed while a check continues on another
+	/// thread.  By default, groups aren't sent.
+	///
+	/// Each group is sent when it's found, and sent again with all of its
+	/// files whenever more duplicates are added to it, so receivers should
+	/// replace any earlier group with the same hash.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use std::path::PathBuf;
+	/// use std::sync::mpsc;
+	/// use std::thread;
+	///
+	/// let (sender, receiver) = mpsc::channel();
+	/// let mut options = dupcheck::DupOptions::new();
+	/// options.set_group_sender(Some(sender));
+	///
+	/// thread::spawn(move || {
+	///     let dirs = vec![PathBuf::from("foo")];
+	///     let mut dup_result = dupcheck::DupResults::with_options(options);
+	///     let _ = dup_result.within(&dirs);
+	/// });
+	///
+	/// // The loop ends when the check finishes and drops its options.
+	/// for group in receiver {
+	///     println!("{} files with hash {}", group.file_count(), group.get_hash());
+	/// }
+	/// ```
+	pub fn set_group_sender(&mut self, group_sender: Option<Sender<DupGroup>>) {
+		self.group_sender = group_sender;
+	}
+
+	/// Returns a reference to the sender that events are sent to during
+	/// checks, if any.
+	pub fn event_sender(&self) -> Option<&Sender<ScanEvent>> {
+		self.event_sender.as_ref()
+	}
+
+	/// Sets a sender to send `ScanEvent`s to during checks, such as when
+	/// directories are searched, files are skipped or hashed, groups are
+	/// found, errors occur and checks finish.  By default, events aren't
+	/// sent.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use dupcheck::ScanEvent;
+	/// use std::path::PathBuf;
+	/// use std::sync::mpsc;
+	/// use std::thread;
+	///
+	/// let (sender, receiver) = mpsc::channel();
+	/// let mut options = dupcheck::DupOptions::new();
+	/// options.set_event_sender(Some(sender));
+	///
+	/// thread::spawn(move || {
+	///     let dirs = vec![PathBuf::from("foo")];
+	///     let mut dup_result = dupcheck::DupResults::with_options(options);
+	///     let _ = dup_result.within(&dirs);
+	/// });
+	///
+	/// for event in receiver {
+	///     match event {
+	///         ScanEvent::FileHashed { path, .. } => println!("Hashed {}", path.display()),
+	///         ScanEvent::Error(message) => println!("Error: {}", message),
+	///         ScanEvent::Finished { stats } => println!("{} groups found", stats.groups()),
+	///         _ => {}
+	///     };
+	/// }
+	/// ```
+	pub fn set_event_sender(&mut self, event_sender: Option<Sender<ScanEvent>>) {
+		self.event_sender = event_sender;
+	}
+
+	/// Sends an event to the event sender, if one is set.  The event is only
+	/// created if it will be sent.
+	pub(crate) fn emit<F: FnOnce() -> ScanEvent>(&self, event: F) {
+		if let Some(sender) = &self.event_sender {
+			// The receiver may have been dropped if it's no longer interested
+			// in events, which shouldn't stop the check.
+			let _ = sender.send(event());
+		}
+	}
+
+	/// Returns a reference to the handle used to pause and resume checks, if
+	/// any.
+	pub fn scan_handle(&self) -> Option<&ScanHandle> {
+		self.scan_handle.as_ref()
+	}
+
+	/// Sets a handle that can pause and resume checks from another thread.
+	/// By default, checks can't be paused.
+	pub fn set_scan_handle(&mut self, scan_handle: Option<ScanHandle>) {
+		self.scan_handle = scan_handle;
+	}
+
+	/// Waits until the scan handle isn't paused, if one is set.  This is
+	/// called before each file or directory is read.
+	pub(crate) fn checkpoint(&self) {
+		if let Some(handle) = &self.scan_handle {
+			handle.wait_while_paused();
+		}
+	}
+
+	/// Sends the event for a skipped file or directory, and records it if
+	/// `record_skipped()` is enabled.
+	pub(crate) fn skip(&self, path: &Path, reason: SkipReason) {
+		if self.record_skipped || self.explainable {
+			self.skip_log.push(path.to_path_buf(), reason);
+		}
+
+		self.emit(|| ScanEvent::FileSkipped {
+			path: path.to_path_buf(),
+			reason,
+		});
+	}
+
+	/// Returns the files and directories recorded as skipped since this was
+	/// last called, leaving none recorded.
+	pub(crate) fn take_skipped(&self) -> Vec<(PathBuf, SkipReason)> {
+		self.skip_log.take()
+	}
+
+	/// Records progress with the progress counters, if they're set.
+	pub(crate) fn record<F: FnOnce(&Progress)>(&self, record: F) {
+		if let Some(progress) = &self.progress {
+			record(progress);
+		}
+	}
+
+	/// Returns whether a file is allowed by the category and content type
+	/// filters, detecting its type from its contents if needed.
+	///
+	/// # Errors
+	///
+	/// Returns an error if the file's type needs to be detected and reading
+	/// it fails.
+	pub(crate) fn allows_file(&self, file: &Path) -> io::Result<bool> {
+		let filters_content_types =
+			!self.content_types.is_empty() || !self.excluded_content_types.is_empty();
+
+		if self.categories.is_empty() && !filters_content_types {
+			return Ok(true);
+		}
+
+		let extension = file
+			.extension()
+			.map(|e| e.to_string_lossy().to_lowercase())
+			.unwrap_or_default();
+		let in_category =
+			self.categories.is_empty() || self.categories.iter().any(|c| c.has_extension(&extension));
+
+		// Files are only read if their extension doesn't settle it.
+		if in_category && !filters_content_types {
+			return Ok(true);
+		}
+
+		let mime = content_type(file)?;
+		let in_category =
+			in_category || mime.is_some_and(|m| self.categories.iter().any(|c| c.has_content_type(m)));
+
+		Ok(in_category && self.allows_content_type(mime))
+	}
+
+	/// Returns whether a file of the given detected MIME type, if any, is
+	/// allowed by the content type filters.
+	fn allows_content_type(&self, mime: Option<&str>) -> bool {
+		let included = self.content_types.is_empty()
+			|| mime.is_some_and(|m| self.content_types.iter().any(|p| matches(m, p)));
+		let excluded = mime.is_some_and(|m| self.excluded_content_types.iter().any(|p| matches(m, p)));
+
+		included && !excluded
+	}
+}
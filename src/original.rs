@@ -0,0 +1,68 @@
+use crate::duperror::DupError;
+use crate::keep::{KeepRule, KeepRules};
+use crate::{DupGroup, DupResults};
+use std::path::Path;
+
+impl DupResults {
+	/// Designates one file in each duplicate file group as the group's
+	/// probable original, which can then be found with
+	/// `DupGroup::original()`.
+	///
+	/// The original is chosen by preferring, in order: files within the
+	/// earliest of `preferred_roots` that contains any of the group's files;
+	/// files whose names don't look like those given to copies; and the file
+	/// with the earliest creation or modification time.
+	///
+	/// # Errors
+	///
+	/// The `DupResults` will contain errors for any groups whose originals
+	/// couldn't be chosen because file metadata couldn't be read.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use std::path::PathBuf;
+	///
+	/// let dirs = vec![PathBuf::from("foo")];
+	/// let mut dup_result = dupcheck::DupResults::new();
+	///
+	/// if let Err(dup_error) = dup_result.within(&dirs) {
+	///     // Error handling
+	/// }
+	///
+	/// dup_result.designate_originals(&[PathBuf::from("foo/photos")]);
+	///
+	/// for group in dup_result.duplicates() {
+	///     if let Some(original) = group.original() {
+	///         println!("{} is probably the original", original.display());
+	///     }
+	/// }
+	/// ```
+	pub fn designate_originals<T: AsRef<Path>>(&mut self, preferred_roots: &[T]) {
+		let mut rules = KeepRules::new(
+			preferred_roots
+				.iter()
+				.map(|r| KeepRule::Under(r.as_ref().to_path_buf()))
+				.collect(),
+		);
+		rules.add(KeepRule::OriginalName);
+		rules.add(KeepRule::Earliest);
+
+		for group in &mut self.duplicates {
+			match rules.select(group) {
+				Ok(i) => group.original = Some(i),
+				Err(e) => self
+					.errors
+					.push(DupError::new(group.files[0].to_path_buf(), e)),
+			};
+		}
+	}
+}
+
+impl DupGroup {
+	/// Returns the path to the file designated as the group's probable
+	/// original by `DupResults::designate_originals()`, if any.
+	pub fn original(&self) -> Option<&Path> {
+		self.original.map(|i| &*self.files[i])
+	}
+}
@@ -0,0 +1,88 @@
+use std::collections::HashSet;
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// The path to a file being checked, stored as its parent directory and its
+/// file name so files in the same directory can share their parent.
+///
+/// Checks of large directory trees hold the path of every file found at
+/// once, often several times over in the lists and maps of files found,
+/// their sizes and their hashes.  Paths given by a `PathInterner` share one
+/// copy of each parent directory, and clones share the file name, so each
+/// file only adds its own name to the memory used.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub(crate) struct FilePath {
+	/// The file's parent directory, or the whole path if it can't be split.
+	dir: Arc<Path>,
+
+	/// The file's name, or an empty name if the path can't be split.
+	name: Arc<OsStr>,
+}
+
+impl From<PathBuf> for FilePath {
+	fn from(path: PathBuf) -> FilePath {
+		FilePath::split(path, |parent| Arc::from(parent))
+	}
+}
+
+impl FilePath {
+	/// Splits a path into its parent directory, given by `dir`, and its file
+	/// name.  Paths that wouldn't be the same once joined again, such as
+	/// those ending in `..`, are kept whole.
+	fn split(path: PathBuf, dir: impl FnOnce(&Path) -> Arc<Path>) -> FilePath {
+		if let (Some(parent), Some(name)) = (path.parent(), path.file_name()) {
+			if parent.join(name).as_os_str() == path.as_os_str() {
+				return FilePath {
+					dir: dir(parent),
+					name: Arc::from(name),
+				};
+			}
+		}
+
+		FilePath {
+			dir: Arc::from(path),
+			name: Arc::from(OsStr::new("")),
+		}
+	}
+
+	/// Returns the full path to the file.
+	pub(crate) fn to_path_buf(&self) -> PathBuf {
+		match self.name.is_empty() {
+			true => self.dir.to_path_buf(),
+			false => self.dir.join(&*self.name),
+		}
+	}
+}
+
+/// Gives `FilePath`s that share one copy of each parent directory.
+#[derive(Debug, Default)]
+pub(crate) struct PathInterner {
+	/// The parent directories of the paths given so far.
+	dirs: HashSet<Arc<Path>>,
+}
+
+impl PathInterner {
+	/// Creates a new `PathInterner` with no directories.
+	pub(crate) fn new() -> PathInterner {
+		PathInterner::default()
+	}
+
+	/// Returns the `FilePath` for the given path, sharing its parent
+	/// directory with any other paths given by this interner.
+	pub(crate) fn intern(&mut self, path: PathBuf) -> FilePath {
+		FilePath::split(path, |parent| match self.dirs.get(parent) {
+			Some(dir) => Arc::clone(dir),
+			None => {
+				let dir: Arc<Path> = Arc::from(parent);
+				self.dirs.insert(Arc::clone(&dir));
+				dir
+			}
+		})
+	}
+
+	/// Returns the `FilePath`s for the given paths, in the same order.
+	pub(crate) fn intern_all(&mut self, paths: Vec<PathBuf>) -> Vec<FilePath> {
+		paths.into_iter().map(|p| self.intern(p)).collect()
+	}
+}
@@ -0,0 +1,127 @@
+use std::io;
+
+/// The number of bytes read from the start of a file by the
+/// `Stage::PrefixHash` comparison stage.
+pub const PREFIX_LEN: u64 = 4096;
+
+/// A comparison stage used to narrow down which files may be duplicates.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Stage {
+	/// Compares file sizes.
+	Size,
+
+	/// Compares the BLAKE3 hashes of the first `PREFIX_LEN` bytes of files.
+	PrefixHash,
+
+	/// Compares the BLAKE3 hashes of the full contents of files.
+	FullHash,
+
+	/// Compares the full contents of files byte by byte.
+	ByteCompare,
+
+	/// Compares the BLAKE3 hashes of the full contents of files after
+	/// applying the normalizations given by `DupOptions::normalizations()`.
+	///
+	/// Normalized files may differ in size, so this stage shouldn't be used
+	/// with `Stage::Size`, `Stage::PrefixHash`, `Stage::FullHash` or
+	/// `Stage::ByteCompare`.
+	NormalizedHash,
+}
+
+impl Stage {
+	/// Returns whether this stage compares the full contents of files.
+	pub fn is_conclusive(&self) -> bool {
+		matches!(
+			self,
+			Stage::FullHash | Stage::ByteCompare | Stage::NormalizedHash
+		)
+	}
+}
+
+/// An ordered list of comparison stages that files are run through when
+/// checking for duplicates.
+///
+/// Each stage splits the files remaining from the previous stage into sets of
+/// possible duplicates, and any file left alone in its set is discarded.
+/// Cheaper stages should therefore run first, so that fewer files reach the
+/// more expensive stages.  The default pipeline compares file sizes and then
+/// full BLAKE3 hashes.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Pipeline {
+	/// The stages, in the order they will be run.
+	stages: Vec<Stage>,
+}
+
+impl Default for Pipeline {
+	fn default() -> Pipeline {
+		Pipeline::new(&[Stage::Size, Stage::FullHash])
+	}
+}
+
+impl Pipeline {
+	/// Creates a new `Pipeline` that will run the given stages in order.
+	///
+	/// If a stage is given more than once, only its first position is used.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use dupcheck::{Pipeline, Stage};
+	///
+	/// let pipeline = Pipeline::new(&[
+	///     Stage::Size,
+	///     Stage::PrefixHash,
+	///     Stage::FullHash,
+	///     Stage::ByteCompare,
+	/// ]);
+	/// let mut options = dupcheck::DupOptions::new();
+	/// options.set_pipeline(pipeline);
+	///
+	/// let mut dup_result = dupcheck::DupResults::with_options(options);
+	/// ```
+	pub fn new(stages: &[Stage]) -> Pipeline {
+		let mut pipeline = Pipeline { stages: vec![] };
+
+		for stage in stages {
+			pipeline.enable(*stage);
+		}
+
+		pipeline
+	}
+
+	/// Returns a reference to the stages, in the order they will be run.
+	pub fn stages(&self) -> &[Stage] {
+		&self.stages
+	}
+
+	/// Returns whether the given stage is enabled.
+	pub fn contains(&self, stage: Stage) -> bool {
+		self.stages.contains(&stage)
+	}
+
+	/// Adds a stage to the end of the pipeline, if it isn't already enabled.
+	pub fn enable(&mut self, stage: Stage) {
+		if !self.contains(stage) {
+			self.stages.push(stage);
+		}
+	}
+
+	/// Removes a stage from the pipeline.
+	pub fn disable(&mut self, stage: Stage) {
+		self.stages.retain(|s| *s != stage);
+	}
+
+	/// Returns an error if no stage in the pipeline compares the full
+	/// contents of files, since the pipeline would then report files as
+	/// duplicates that may not be.
+	pub(crate) fn check_valid(&self) -> io::Result<()> {
+		if !self.stages.iter().any(|s| s.is_conclusive()) {
+			return Err(io::Error::new(
+				io::ErrorKind::InvalidInput,
+				"comparison pipeline must include a full hash, byte comparison or normalized hash stage",
+			));
+		}
+
+		Ok(())
+	}
+}
@@ -0,0 +1,55 @@
+use std::fs::Metadata;
+
+/// Returns whether a file is a cloud storage placeholder whose contents
+/// aren't stored locally, such as an online-only OneDrive, iCloud Drive or
+/// Dropbox file, so reading it would download it.
+///
+/// On Windows, these are files with the attributes set by the cloud files
+/// API for files recalled from the cloud when opened or read.
+#[cfg(windows)]
+pub(crate) fn is_placeholder(metadata: &Metadata) -> bool {
+	use std::os::windows::fs::MetadataExt;
+
+	const FILE_ATTRIBUTE_OFFLINE: u32 = 0x1000;
+	const FILE_ATTRIBUTE_RECALL_ON_OPEN: u32 = 0x40000;
+	const FILE_ATTRIBUTE_RECALL_ON_DATA_ACCESS: u32 = 0x400000;
+
+	metadata.file_attributes()
+		& (FILE_ATTRIBUTE_OFFLINE | FILE_ATTRIBUTE_RECALL_ON_OPEN | FILE_ATTRIBUTE_RECALL_ON_DATA_ACCESS)
+		!= 0
+}
+
+/// Returns whether a file is a cloud storage placeholder whose contents
+/// aren't stored locally, such as an online-only iCloud Drive or Dropbox
+/// file, so reading it would download it.
+///
+/// On macOS, these are files marked as dataless, and files that have a
+/// length but no storage allocated to them.
+#[cfg(target_os = "macos")]
+pub(crate) fn is_placeholder(metadata: &Metadata) -> bool {
+	use std::os::macos::fs::MetadataExt;
+
+	const SF_DATALESS: u32 = 0x40000000;
+
+	metadata.st_flags() & SF_DATALESS != 0 || (metadata.st_size() > 0 && metadata.st_blocks() == 0)
+}
+
+/// Returns whether a file is a cloud storage placeholder whose contents
+/// aren't stored locally, such as an online-only file in a Dropbox or
+/// OneDrive client's folder, so reading it would download it.
+///
+/// On other Unix platforms, these are files that have a length but no
+/// storage allocated to them.  Files made up entirely of holes also look
+/// like this.
+#[cfg(all(unix, not(target_os = "macos")))]
+pub(crate) fn is_placeholder(metadata: &Metadata) -> bool {
+	use std::os::unix::fs::MetadataExt;
+
+	metadata.size() > 0 && metadata.blocks() == 0
+}
+
+/// Placeholders can't be identified on this platform.
+#[cfg(not(any(unix, windows)))]
+pub(crate) fn is_placeholder(_metadata: &Metadata) -> bool {
+	false
+}
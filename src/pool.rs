@@ -0,0 +1,81 @@
+use std::ops::{Deref, DerefMut};
+use std::sync::Mutex;
+use std::thread;
+
+/// Read buffers kept for reuse, so files can be hashed and compared without
+/// allocating new buffers for each file.
+///
+/// The pool keeps at most two buffers for each thread the system can run at
+/// once, which is enough for every hashing thread of a check to reuse a
+/// buffer, or for a byte comparison to reuse two.
+#[derive(Debug)]
+pub(crate) struct BufferPool {
+	/// The buffers waiting to be reused.
+	buffers: Mutex<Vec<Vec<u8>>>,
+
+	/// The most buffers kept for reuse.
+	max_buffers: usize,
+}
+
+impl Default for BufferPool {
+	fn default() -> BufferPool {
+		BufferPool::new()
+	}
+}
+
+impl BufferPool {
+	/// Creates a new, empty `BufferPool`.
+	pub(crate) fn new() -> BufferPool {
+		BufferPool {
+			buffers: Mutex::new(vec![]),
+			max_buffers: thread::available_parallelism().map_or(1, |n| n.get()) * 2,
+		}
+	}
+
+	/// Returns a buffer of the given size, reusing one from the pool if
+	/// there is one.  The buffer is returned to the pool when it's dropped.
+	pub(crate) fn take(&self, size: usize) -> PooledBuffer<'_> {
+		let buffer = self.buffers.lock().unwrap_or_else(|e| e.into_inner()).pop();
+
+		// Buffers only change size if the options do, so they're resized
+		// rather than discarded.
+		let mut buffer = buffer.unwrap_or_default();
+		buffer.resize(size.max(1), 0);
+
+		PooledBuffer { pool: self, buffer }
+	}
+}
+
+/// A buffer taken from a `BufferPool`, which is returned to the pool when
+/// it's dropped.
+pub(crate) struct PooledBuffer<'a> {
+	/// The pool the buffer is returned to.
+	pool: &'a BufferPool,
+
+	/// The buffer.
+	buffer: Vec<u8>,
+}
+
+impl Deref for PooledBuffer<'_> {
+	type Target = [u8];
+
+	fn deref(&self) -> &[u8] {
+		&self.buffer
+	}
+}
+
+impl DerefMut for PooledBuffer<'_> {
+	fn deref_mut(&mut self) -> &mut [u8] {
+		&mut self.buffer
+	}
+}
+
+impl Drop for PooledBuffer<'_> {
+	fn drop(&mut self) {
+		let mut buffers = self.pool.buffers.lock().unwrap_or_else(|e| e.into_inner());
+
+		if buffers.len() < self.pool.max_buffers {
+			buffers.push(std::mem::take(&mut self.buffer));
+		}
+	}
+}
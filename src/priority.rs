@@ -0,0 +1,57 @@
+/// Lowers the CPU and I/O priority of the calling thread for the rest of its
+/// life, so checks running on it don't slow down other programs.
+///
+/// The priority can't be raised again without extra privileges, so this
+/// should only be called on threads that are only used for checks.  Lowering
+/// the priority is only a hint, so any error is ignored.
+#[cfg(target_os = "linux")]
+pub(crate) fn lower_thread_priority() {
+	// `ioprio_set()` has no libc wrapper.  The idle class only gets disk time
+	// when no other process needs it.
+	const IOPRIO_WHO_PROCESS: libc::c_int = 1;
+	const IOPRIO_CLASS_IDLE: libc::c_int = 3;
+	const IOPRIO_CLASS_SHIFT: libc::c_int = 13;
+
+	unsafe {
+		let tid = libc::gettid();
+		libc::setpriority(libc::PRIO_PROCESS, tid as libc::id_t, 19);
+		libc::syscall(
+			libc::SYS_ioprio_set,
+			IOPRIO_WHO_PROCESS,
+			tid,
+			IOPRIO_CLASS_IDLE << IOPRIO_CLASS_SHIFT,
+		);
+	}
+}
+
+/// Lowers the CPU and I/O priority of the calling thread for the rest of its
+/// life by putting it in the background state.
+#[cfg(any(target_os = "macos", target_os = "ios"))]
+pub(crate) fn lower_thread_priority() {
+	unsafe {
+		libc::setpriority(libc::PRIO_DARWIN_THREAD, 0, libc::PRIO_DARWIN_BG);
+	}
+}
+
+/// Lowers the CPU and I/O priority of the calling thread for the rest of its
+/// life by putting it in background processing mode.
+#[cfg(windows)]
+pub(crate) fn lower_thread_priority() {
+	use std::ffi::c_void;
+
+	const THREAD_MODE_BACKGROUND_BEGIN: i32 = 0x0001_0000;
+
+	#[link(name = "kernel32")]
+	extern "system" {
+		fn GetCurrentThread() -> *mut c_void;
+		fn SetThreadPriority(thread: *mut c_void, priority: i32) -> i32;
+	}
+
+	unsafe {
+		SetThreadPriority(GetCurrentThread(), THREAD_MODE_BACKGROUND_BEGIN);
+	}
+}
+
+/// Lowering thread priorities isn't supported on this platform.
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "ios", windows)))]
+pub(crate) fn lower_thread_priority() {}
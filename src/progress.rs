@@ -0,0 +1,323 @@
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, AtomicU8, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+/// The phase a check is in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ScanPhase {
+	/// No check has started.
+	Waiting,
+
+	/// Directories are being searched for files to check.
+	Discovering,
+
+	/// Files are being compared to find duplicates.
+	Comparing,
+
+	/// The check has finished.
+	Finished,
+}
+
+impl ScanPhase {
+	/// Returns the phase with the given number, as stored by `Progress`.
+	fn from_u8(n: u8) -> ScanPhase {
+		match n {
+			1 => ScanPhase::Discovering,
+			2 => ScanPhase::Comparing,
+			3 => ScanPhase::Finished,
+			_ => ScanPhase::Waiting,
+		}
+	}
+}
+
+/// A snapshot of the progress of checks, as returned by
+/// `Progress::status()`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ScanStatus {
+	/// The phase of the latest check.
+	phase: ScanPhase,
+
+	/// The number of files found to check.
+	files_found: u64,
+
+	/// The number of files whose hashes have been found.
+	files_hashed: u64,
+
+	/// The total size of the files whose hashes have been found.
+	bytes_hashed: u64,
+
+	/// The total size of the files that may need to be hashed.
+	bytes_total: u64,
+
+	/// The number of files that may need to be hashed.
+	files_total: u64,
+
+	/// The file or directory being read, if any.
+	current_path: Option<PathBuf>,
+
+	/// The number of errors that have occurred.
+	errors: u64,
+
+	/// The average number of bytes hashed per second while comparing files.
+	throughput: Option<u64>,
+
+	/// The estimated time until the remaining files are hashed.
+	eta: Option<Duration>,
+}
+
+impl ScanStatus {
+	/// Returns the phase of the latest check.
+	pub fn phase(&self) -> ScanPhase {
+		self.phase
+	}
+
+	/// Returns the number of files found to check so far.
+	pub fn files_found(&self) -> u64 {
+		self.files_found
+	}
+
+	/// Returns the number of files whose hashes have been found so far.
+	pub fn files_hashed(&self) -> u64 {
+		self.files_hashed
+	}
+
+	/// Returns the total size in bytes of the files whose hashes have been
+	/// found so far.
+	pub fn bytes_hashed(&self) -> u64 {
+		self.bytes_hashed
+	}
+
+	/// Returns the total size in bytes of the files that may need to be
+	/// hashed, as described for `Progress::bytes_total()`.
+	pub fn bytes_total(&self) -> u64 {
+		self.bytes_total
+	}
+
+	/// Returns the number of files that may need to be hashed, as described
+	/// for `Progress::files_total()`.
+	pub fn files_total(&self) -> u64 {
+		self.files_total
+	}
+
+	/// Returns the path to the file or directory being read, if any.
+	pub fn current_path(&self) -> Option<&Path> {
+		self.current_path.as_deref()
+	}
+
+	/// Returns the number of errors that have occurred so far.
+	pub fn errors(&self) -> u64 {
+		self.errors
+	}
+
+	/// Returns the average number of bytes hashed per second, as described
+	/// for `Progress::throughput()`.
+	pub fn throughput(&self) -> Option<u64> {
+		self.throughput
+	}
+
+	/// Returns the estimated time until the remaining files are hashed, as
+	/// described for `Progress::eta()`.
+	pub fn eta(&self) -> Option<Duration> {
+		self.eta
+	}
+}
+
+/// Counters recording the progress of checks, which can be read from another
+/// thread while a check runs.
+///
+/// Clones of a `Progress` share the same counters, so a clone can be given
+/// to the options of a check and the original polled for updates.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::PathBuf;
+/// use std::thread;
+///
+/// let progress = dupcheck::Progress::new();
+/// let mut options = dupcheck::DupOptions::new();
+/// options.set_progress(Some(progress.clone()));
+///
+/// let check = thread::spawn(move || {
+///     let dirs = vec![PathBuf::from("foo")];
+///     let mut dup_result = dupcheck::DupResults::with_options(options);
+///     let _ = dup_result.within(&dirs);
+/// });
+///
+/// let status = progress.status();
+/// println!("{:?}: {} of {} bytes hashed", status.phase(), status.bytes_hashed(), status.bytes_total());
+/// check.join().unwrap();
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Progress {
+	/// The number of files found to check.
+	files_found: Arc<AtomicU64>,
+
+	/// The number of files whose hashes have been found.
+	files_hashed: Arc<AtomicU64>,
+
+	/// The total size of the files whose hashes have been found.
+	bytes_hashed: Arc<AtomicU64>,
+
+	/// The total size of the files that may need to be hashed.
+	bytes_total: Arc<AtomicU64>,
+
+	/// The number of files that may need to be hashed.
+	files_total: Arc<AtomicU64>,
+
+	/// The number of errors that have occurred.
+	errors: Arc<AtomicU64>,
+
+	/// The phase of the latest check, as stored by `ScanPhase::from_u8()`.
+	phase: Arc<AtomicU8>,
+
+	/// The file or directory being read, if any.
+	current_path: Arc<Mutex<Option<PathBuf>>>,
+
+	/// When the latest check started comparing files, and the number of
+	/// bytes hashed by then.
+	comparing_since: Arc<Mutex<Option<(Instant, u64)>>>,
+}
+
+impl Progress {
+	/// Creates a new `Progress` with its counters at zero.
+	pub fn new() -> Progress {
+		Progress::default()
+	}
+
+	/// Returns the number of files found to check.
+	pub fn files_found(&self) -> u64 {
+		self.files_found.load(Ordering::Relaxed)
+	}
+
+	/// Returns the number of files whose hashes have been found, including
+	/// any taken from a hash cache.  Files that are ruled out as duplicates
+	/// by an earlier comparison stage, such as by their size, are never
+	/// hashed.
+	pub fn files_hashed(&self) -> u64 {
+		self.files_hashed.load(Ordering::Relaxed)
+	}
+
+	/// Returns the total size in bytes of the files whose hashes have been
+	/// found, including any taken from a hash cache.
+	pub fn bytes_hashed(&self) -> u64 {
+		self.bytes_hashed.load(Ordering::Relaxed)
+	}
+
+	/// Returns the total size in bytes of the files that may need to be
+	/// hashed.  If the comparison pipeline starts with `Stage::Size`, this
+	/// only counts files that have the same size as another file once that
+	/// stage has run.  If `DupOptions::prescan()` is enabled, files with
+	/// unique sizes are left out however the pipeline is ordered, and the
+	/// total is known before any files are compared.
+	pub fn bytes_total(&self) -> u64 {
+		self.bytes_total.load(Ordering::Relaxed)
+	}
+
+	/// Returns the number of files that may need to be hashed, counted in
+	/// the same way as `bytes_total()`.
+	pub fn files_total(&self) -> u64 {
+		self.files_total.load(Ordering::Relaxed)
+	}
+
+	/// Returns the number of errors that have occurred.
+	pub fn errors(&self) -> u64 {
+		self.errors.load(Ordering::Relaxed)
+	}
+
+	/// Returns the average number of bytes hashed per second since the
+	/// latest check started comparing files, or `None` if it isn't comparing
+	/// files or hasn't been for long enough to tell.
+	pub fn throughput(&self) -> Option<u64> {
+		if ScanPhase::from_u8(self.phase.load(Ordering::Relaxed)) != ScanPhase::Comparing {
+			return None;
+		}
+
+		let (since, bytes_before) = (*lock(&self.comparing_since))?;
+		let elapsed = since.elapsed().as_secs_f64();
+
+		if elapsed < 0.5 {
+			return None;
+		}
+
+		let bytes = self.bytes_hashed().saturating_sub(bytes_before);
+
+		Some((bytes as f64 / elapsed) as u64)
+	}
+
+	/// Returns the estimated time until the files that may need to be
+	/// hashed have been hashed, based on the current throughput, or `None`
+	/// if the throughput isn't known.
+	///
+	/// Until the first comparison stage has run, not all of the files that
+	/// may need to be hashed are known, so early estimates may be too low.
+	pub fn eta(&self) -> Option<Duration> {
+		let throughput = self.throughput().filter(|t| *t > 0)?;
+		let remaining = self.bytes_total().saturating_sub(self.bytes_hashed());
+
+		Some(Duration::from_secs_f64(
+			remaining as f64 / throughput as f64,
+		))
+	}
+
+	/// Returns a snapshot of the progress, which can be kept and compared
+	/// while the check continues.
+	pub fn status(&self) -> ScanStatus {
+		ScanStatus {
+			phase: ScanPhase::from_u8(self.phase.load(Ordering::Relaxed)),
+			files_found: self.files_found(),
+			files_hashed: self.files_hashed(),
+			bytes_hashed: self.bytes_hashed(),
+			bytes_total: self.bytes_total(),
+			files_total: self.files_total(),
+			current_path: lock(&self.current_path).clone(),
+			errors: self.errors(),
+			throughput: self.throughput(),
+			eta: self.eta(),
+		}
+	}
+
+	/// Records that a file was found to check.
+	pub(crate) fn add_file_found(&self) {
+		self.files_found.fetch_add(1, Ordering::Relaxed);
+	}
+
+	/// Records the number and total size of files that may need to be
+	/// hashed.
+	pub(crate) fn add_total(&self, files: u64, len: u64) {
+		self.files_total.fetch_add(files, Ordering::Relaxed);
+		self.bytes_total.fetch_add(len, Ordering::Relaxed);
+	}
+
+	/// Records that an error occurred.
+	pub(crate) fn add_error(&self) {
+		self.errors.fetch_add(1, Ordering::Relaxed);
+	}
+
+	/// Records the phase of the current check.
+	pub(crate) fn set_phase(&self, phase: ScanPhase) {
+		if phase == ScanPhase::Comparing {
+			*lock(&self.comparing_since) = Some((Instant::now(), self.bytes_hashed()));
+		}
+
+		self.phase.store(phase as u8, Ordering::Relaxed);
+	}
+
+	/// Records the file or directory being read.
+	pub(crate) fn set_current_path(&self, path: Option<&Path>) {
+		*lock(&self.current_path) = path.map(Path::to_path_buf);
+	}
+
+	/// Records that the hash of a file of the given size was found.
+	pub(crate) fn add_file_hashed(&self, len: u64) {
+		self.files_hashed.fetch_add(1, Ordering::Relaxed);
+		self.bytes_hashed.fetch_add(len, Ordering::Relaxed);
+	}
+}
+
+/// Locks a mutex, recovering its value if another thread panicked while
+/// holding the lock.
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+	mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
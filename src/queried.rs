@@ -0,0 +1,59 @@
+use crate::{DupGroup, DupResults};
+use std::path::Path;
+use std::sync::Arc;
+
+impl DupResults {
+	/// Moves any files given to the running `of()` check from the files of
+	/// the group at the given index to its queried files, if they're to be
+	/// kept apart.  Returns whether the group is still in the results, which
+	/// it isn't if it had no other files.
+	pub(crate) fn separate_queried(&mut self, i: usize) -> bool {
+		if self.queried.is_empty() {
+			return true;
+		}
+
+		let queried = &self.queried;
+		let group = &mut self.duplicates[i];
+		let removed = group.remove_files(|f| !queried.iter().any(|q| q == f));
+		group.queried.extend(removed);
+
+		if group.files.is_empty() {
+			self.duplicates.remove(i);
+			return false;
+		}
+
+		true
+	}
+}
+
+impl DupGroup {
+	/// Returns a reference to the files given to `DupResults::of()` that
+	/// were found to be in this group, if `DupOptions::exclude_queried()`
+	/// was enabled for the check.  These aren't included in the group's
+	/// files.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use std::path::PathBuf;
+	///
+	/// let mut options = dupcheck::DupOptions::new();
+	/// options.set_exclude_queried(true);
+	///
+	/// let files = vec![PathBuf::from("foo.txt")];
+	/// let mut dup_result = dupcheck::DupResults::with_options(options);
+	///
+	/// if let Err(dup_error) = dup_result.of(&files, None) {
+	///     // Error handling
+	/// }
+	///
+	/// for group in dup_result.duplicates() {
+	///     for file in group.queried_files() {
+	///         println!("{} has {} copies", file.display(), group.file_count());
+	///     }
+	/// }
+	/// ```
+	pub fn queried_files(&self) -> &[Arc<Path>] {
+		&self.queried
+	}
+}
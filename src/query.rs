@@ -0,0 +1,258 @@
+use crate::{DupGroup, DupResults};
+use std::cmp::Ordering;
+use std::path::Path;
+
+impl DupResults {
+	/// Returns the duplicate file groups that contain the given path, either
+	/// as one of their files, a baseline file or a queried file.
+	///
+	/// Paths are compared as given, so they should be given in the same form
+	/// as the paths that were checked.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use std::path::PathBuf;
+	///
+	/// let dirs = vec![PathBuf::from("foo")];
+	/// let mut dup_result = dupcheck::DupResults::new();
+	///
+	/// if let Err(dup_error) = dup_result.within(&dirs) {
+	///     // Error handling
+	/// }
+	///
+	/// for group in dup_result.groups_containing("foo/bar.txt") {
+	///     println!("foo/bar.txt has {} copies", group.file_count() - 1);
+	/// }
+	/// ```
+	pub fn groups_containing<P: AsRef<Path>>(&self, path: P) -> Vec<&DupGroup> {
+		let path = path.as_ref();
+
+		self
+			.duplicates
+			.iter()
+			.filter(|g| {
+				g.files
+					.iter()
+					.chain(&g.baseline)
+					.chain(&g.queried)
+					.any(|f| **f == *path)
+			})
+			.collect()
+	}
+
+	/// Returns the duplicate file groups with at least one file within the
+	/// given directory or its subdirectories.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use std::path::PathBuf;
+	///
+	/// let dirs = vec![PathBuf::from("foo")];
+	/// let mut dup_result = dupcheck::DupResults::new();
+	///
+	/// if let Err(dup_error) = dup_result.within(&dirs) {
+	///     // Error handling
+	/// }
+	///
+	/// let photo_groups = dup_result.groups_under("foo/photos");
+	/// ```
+	pub fn groups_under<P: AsRef<Path>>(&self, dir: P) -> Vec<&DupGroup> {
+		let dir = dir.as_ref();
+
+		self
+			.duplicates
+			.iter()
+			.filter(|g| g.files.iter().any(|f| f.starts_with(dir)))
+			.collect()
+	}
+
+	/// Splits the duplicate file groups along the given directories,
+	/// returning a `DupResults` for each directory with the groups whose
+	/// files are all within it, in the order the directories were given;
+	/// and also returning a `DupResults` with the groups whose files span
+	/// more than one directory or aren't all within any of them.
+	///
+	/// Groups with files all within more than one of the directories, such
+	/// as when one directory is within another, go to the first of them.
+	/// Paths are compared as given, as for `groups_under()`.  The returned
+	/// results have the same options as this `DupResults`, but no errors or
+	/// other results.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use std::path::PathBuf;
+	///
+	/// let dirs = vec![PathBuf::from("backup-1"), PathBuf::from("backup-2")];
+	/// let mut dup_result = dupcheck::DupResults::new();
+	///
+	/// if let Err(dup_error) = dup_result.within(&dirs) {
+	///     // Error handling
+	/// }
+	///
+	/// let (within, spanning) = dup_result.partition_by_root(&dirs);
+	///
+	/// for (dir, results) in dirs.iter().zip(&within) {
+	///     println!("{}: {} groups", dir.display(), results.group_count());
+	/// }
+	///
+	/// println!("{} groups span backups", spanning.group_count());
+	/// ```
+	pub fn partition_by_root<T: AsRef<Path>>(&self, dirs: &[T]) -> (Vec<DupResults>, DupResults) {
+		let mut within: Vec<DupResults> = dirs
+			.iter()
+			.map(|_| DupResults::with_options(self.options.clone()))
+			.collect();
+		let mut spanning = DupResults::with_options(self.options.clone());
+
+		for group in &self.duplicates {
+			let root = dirs
+				.iter()
+				.position(|d| group.files.iter().all(|f| f.starts_with(d)));
+
+			match root {
+				Some(i) => within[i].duplicates.push(group.clone()),
+				None => spanning.duplicates.push(group.clone()),
+			};
+		}
+
+		(within, spanning)
+	}
+
+	/// Returns the duplicate file group with the given hash, if any.  Hashes
+	/// are compared ignoring case.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use std::path::PathBuf;
+	///
+	/// let dirs = vec![PathBuf::from("foo")];
+	/// let mut dup_result = dupcheck::DupResults::new();
+	///
+	/// if let Err(dup_error) = dup_result.within(&dirs) {
+	///     // Error handling
+	/// }
+	///
+	/// if let Some(group) = dup_result.group_by_hash("af1349b9f5f9a1a6a0404dea36dcc9499bcb25c9adc112b7cc9a93cae41f3262") {
+	///     println!("{} copies", group.file_count());
+	/// }
+	/// ```
+	pub fn group_by_hash(&self, hash: &str) -> Option<&DupGroup> {
+		self
+			.duplicates
+			.iter()
+			.find(|g| g.hash.eq_ignore_ascii_case(hash))
+	}
+
+	/// Returns a mutable reference to the duplicate file group with the given
+	/// hash, if any, so it can be tagged.  Hashes are compared ignoring case.
+	pub fn group_by_hash_mut(&mut self, hash: &str) -> Option<&mut DupGroup> {
+		self
+			.duplicates
+			.iter_mut()
+			.find(|g| g.hash.eq_ignore_ascii_case(hash))
+	}
+
+	/// Returns up to `limit` duplicate file groups, starting from the group
+	/// at `offset`, so large results can be shown a page at a time.  The
+	/// page is empty if `offset` is past the last group.
+	///
+	/// The total numbers of groups and files are given by `group_count()`
+	/// and `file_count()`.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use std::path::PathBuf;
+	///
+	/// let dirs = vec![PathBuf::from("foo")];
+	/// let mut dup_result = dupcheck::DupResults::new();
+	///
+	/// if let Err(dup_error) = dup_result.within(&dirs) {
+	///     // Error handling
+	/// }
+	///
+	/// let page_len = 100;
+	/// let pages = dup_result.group_count().div_ceil(page_len);
+	///
+	/// for page in 0..pages {
+	///     for group in dup_result.groups_page(page * page_len, page_len) {
+	///         println!("{}", group.get_hash());
+	///     }
+	/// }
+	/// ```
+	pub fn groups_page(&self, offset: usize, limit: usize) -> &[DupGroup] {
+		let start = offset.min(self.duplicates.len());
+		let end = start.saturating_add(limit).min(self.duplicates.len());
+
+		&self.duplicates[start..end]
+	}
+
+	/// Returns the number of duplicate file groups.
+	pub fn group_count(&self) -> usize {
+		self.duplicates.len()
+	}
+
+	/// Sorts the duplicate file groups with the given comparison function,
+	/// which orders the groups returned by `duplicates()` and exported by
+	/// the `write_*` methods.  The sort is stable, so groups that compare
+	/// equal keep their order, and groups found by later checks are added to
+	/// the end.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use std::path::PathBuf;
+	///
+	/// let dirs = vec![PathBuf::from("foo")];
+	/// let mut dup_result = dupcheck::DupResults::new();
+	///
+	/// if let Err(dup_error) = dup_result.within(&dirs) {
+	///     // Error handling
+	/// }
+	///
+	/// // Groups with the most deeply nested files first.
+	/// let depth = |group: &dupcheck::DupGroup| {
+	///     group
+	///         .get_files()
+	///         .iter()
+	///         .map(|f| f.components().count())
+	///         .max()
+	/// };
+	/// dup_result.sort_by(|a, b| depth(b).cmp(&depth(a)));
+	/// ```
+	pub fn sort_by<F>(&mut self, compare: F)
+	where
+		F: FnMut(&DupGroup, &DupGroup) -> Ordering,
+	{
+		self.duplicates.sort_by(compare);
+	}
+
+	/// Keeps only the duplicate file groups for which the given function
+	/// returns `true`, removing the others from the results.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use std::path::PathBuf;
+	///
+	/// let dirs = vec![PathBuf::from("foo")];
+	/// let mut dup_result = dupcheck::DupResults::new();
+	///
+	/// if let Err(dup_error) = dup_result.within(&dirs) {
+	///     // Error handling
+	/// }
+	///
+	/// // Only groups with at least 3 copies.
+	/// dup_result.retain(|group| group.file_count() >= 3);
+	/// ```
+	pub fn retain<F>(&mut self, keep: F)
+	where
+		F: FnMut(&DupGroup) -> bool,
+	{
+		self.duplicates.retain(keep);
+	}
+}
@@ -0,0 +1,71 @@
+use crate::hash::hash_file;
+use crate::manifest::{verify_files, VerifyResults};
+use crate::normalize::normalized_hash;
+use crate::DupResults;
+use std::path::Path;
+
+impl DupResults {
+	/// Re-hashes the files in each group, such as groups read from saved
+	/// results, and returns which of them no longer match their group's hash
+	/// or no longer exist, so results that have gone stale since they were
+	/// found aren't acted on.  Files that have been moved are missing from
+	/// their group.
+	///
+	/// Files are hashed with the normalizations their group was found with,
+	/// or with these results' normalizations for groups that don't record
+	/// them, such as groups read from a manifest.  Groups found without
+	/// hashing, whose identifiers aren't BLAKE3 hashes, are verified against
+	/// the hash of their first file that can be read.
+	///
+	/// The results for each group are returned in the same order as
+	/// `duplicates()`, and a group still holds if its results are ok.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use std::fs::File;
+	/// use std::io::BufReader;
+	///
+	/// let mut dup_result = dupcheck::DupResults::new();
+	///
+	/// if let Err(read_error) = File::open("results.dupr")
+	///     .and_then(|file| dup_result.read_binary(BufReader::new(file)))
+	/// {
+	///     // Error handling
+	/// }
+	///
+	/// for (group, results) in dup_result.duplicates().iter().zip(dup_result.verify()) {
+	///     if !results.is_ok() {
+	///         println!("{} no longer holds", group.get_hash());
+	///     }
+	/// }
+	/// ```
+	pub fn verify(&self) -> Vec<VerifyResults> {
+		self
+			.duplicates
+			.iter()
+			.map(|g| {
+				let normalizations = match g.normalizations.is_empty() {
+					true => self.options.normalizations(),
+					false => &g.normalizations,
+				};
+				let hash = |file: &Path| match normalizations.is_empty() {
+					true => hash_file(file),
+					false => normalized_hash(file, normalizations),
+				};
+				let is_blake3 = g.hash.len() == 64 && g.hash.chars().all(|c| c.is_ascii_hexdigit());
+				let expected = match is_blake3 {
+					true => g.hash.clone(),
+					false => g
+						.files
+						.iter()
+						.find_map(|f| hash(f).ok())
+						.unwrap_or_default(),
+				};
+				let files = g.files.iter().map(|f| (expected.as_str(), &**f));
+
+				verify_files(files, &self.options, hash)
+			})
+			.collect()
+	}
+}
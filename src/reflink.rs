@@ -0,0 +1,99 @@
+use crate::utilities::temp_path;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Replaces `link` with a copy-on-write clone of `original`, keeping the
+/// permissions of `link`.
+///
+/// The clone is made under a temporary name in the same directory as `link`
+/// and then renamed over it, so `link` is left unchanged if cloning fails.
+///
+/// # Errors
+///
+/// Returns an error of kind `Unsupported` if the filesystem doesn't support
+/// cloning files.
+pub(crate) fn reflink(original: &Path, link: &Path) -> io::Result<()> {
+	let permissions = fs::metadata(link)?.permissions();
+	let temp = temp_path(link);
+
+	if let Err(e) = clone_file(original, &temp) {
+		let _ = fs::remove_file(&temp);
+		return Err(e);
+	}
+
+	let renamed = fs::set_permissions(&temp, permissions).and_then(|_| fs::rename(&temp, link));
+
+	if renamed.is_err() {
+		let _ = fs::remove_file(&temp);
+	}
+
+	renamed
+}
+
+/// Returns an `Unsupported` error for a filesystem that can't clone files.
+fn unsupported() -> io::Error {
+	io::Error::new(
+		io::ErrorKind::Unsupported,
+		"the filesystem doesn't support reflinks",
+	)
+}
+
+/// Clones `original` to the new file `clone` using the `FICLONE` ioctl.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn clone_file(original: &Path, clone: &Path) -> io::Result<()> {
+	use std::os::unix::io::AsRawFd;
+
+	let src = fs::File::open(original)?;
+	let dst = fs::OpenOptions::new()
+		.write(true)
+		.create_new(true)
+		.open(clone)?;
+	let result = unsafe { libc::ioctl(dst.as_raw_fd(), libc::FICLONE, src.as_raw_fd()) };
+
+	if result == -1 {
+		let error = io::Error::last_os_error();
+
+		return match error.raw_os_error() {
+			Some(libc::EOPNOTSUPP) | Some(libc::EXDEV) | Some(libc::EINVAL) | Some(libc::ENOTTY) => {
+				Err(unsupported())
+			}
+			_ => Err(error),
+		};
+	}
+
+	Ok(())
+}
+
+/// Clones `original` to the new file `clone` using `clonefile()`.
+#[cfg(any(target_os = "macos", target_os = "ios"))]
+fn clone_file(original: &Path, clone: &Path) -> io::Result<()> {
+	use std::ffi::CString;
+	use std::os::unix::ffi::OsStrExt;
+
+	let src = CString::new(original.as_os_str().as_bytes())?;
+	let dst = CString::new(clone.as_os_str().as_bytes())?;
+	let result = unsafe { libc::clonefile(src.as_ptr(), dst.as_ptr(), 0) };
+
+	if result == -1 {
+		let error = io::Error::last_os_error();
+
+		return match error.raw_os_error() {
+			Some(libc::ENOTSUP) | Some(libc::EXDEV) => Err(unsupported()),
+			_ => Err(error),
+		};
+	}
+
+	Ok(())
+}
+
+/// Cloning files isn't supported on this platform.
+#[cfg(not(any(
+	target_os = "linux",
+	target_os = "android",
+	target_os = "macos",
+	target_os = "ios"
+)))]
+fn clone_file(_original: &Path, _clone: &Path) -> io::Result<()> {
+	Err(unsupported())
+}
@@ -0,0 +1,51 @@
+use crate::duperror::DupError;
+use crate::utilities::absolute;
+use crate::DupResults;
+use std::collections::HashSet;
+use std::io;
+use std::path::PathBuf;
+
+impl DupResults {
+	/// Returns the given paths without any that are repeats of an earlier
+	/// path, adding a warning for each repeat.  Paths are compared once made
+	/// absolute, so `foo` and `./foo` are the same path.
+	pub(crate) fn remove_repeated(&mut self, paths: Vec<PathBuf>) -> Vec<PathBuf> {
+		let mut seen = HashSet::new();
+		let mut unique = vec![];
+
+		for path in paths {
+			match seen.insert(absolute(&path)) {
+				true => unique.push(path),
+				false => self.warnings.push(DupError::new(
+					path,
+					io::Error::new(io::ErrorKind::InvalidInput, "given more than once"),
+				)),
+			};
+		}
+
+		unique
+	}
+
+	/// Returns a reference to the warnings, for paths that were given more
+	/// than once to a check and were only checked once.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use std::path::PathBuf;
+	///
+	/// let files = vec![PathBuf::from("foo.txt"), PathBuf::from("foo.txt")];
+	/// let mut dup_result = dupcheck::DupResults::new();
+	///
+	/// if let Err(dup_error) = dup_result.files(&files) {
+	///     // Error handling
+	/// }
+	///
+	/// for warning in dup_result.warnings() {
+	///     eprintln!("{}", warning);
+	/// }
+	/// ```
+	pub fn warnings(&self) -> &[DupError] {
+		&self.warnings
+	}
+}
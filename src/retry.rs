@@ -0,0 +1,87 @@
+use std::io;
+use std::thread;
+use std::time::Duration;
+
+/// How failed attempts to read file metadata or contents are retried before
+/// an error is recorded.
+///
+/// Files on network shares can fail to be read due to transient errors, so
+/// retrying those reads can reduce the number of errors reported.  Errors
+/// that won't go away by retrying, such as a file not being found, are never
+/// retried.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RetryPolicy {
+	/// The number of times a failed attempt is retried.
+	retries: u32,
+
+	/// How long to wait before the first retry, doubling for each retry
+	/// after it.
+	backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+	fn default() -> RetryPolicy {
+		RetryPolicy::new(0, Duration::ZERO)
+	}
+}
+
+impl RetryPolicy {
+	/// Creates a new `RetryPolicy` that will retry failed attempts up to
+	/// `retries` times, waiting for `backoff` before the first retry and
+	/// doubling the wait for each retry after it.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use std::time::Duration;
+	///
+	/// let mut options = dupcheck::DupOptions::new();
+	/// options.set_retry_policy(dupcheck::RetryPolicy::new(3, Duration::from_millis(100)));
+	///
+	/// let mut dup_result = dupcheck::DupResults::with_options(options);
+	/// ```
+	pub fn new(retries: u32, backoff: Duration) -> RetryPolicy {
+		RetryPolicy { retries, backoff }
+	}
+
+	/// Returns the number of times a failed attempt is retried.
+	pub fn retries(&self) -> u32 {
+		self.retries
+	}
+
+	/// Returns how long to wait before the first retry.
+	pub fn backoff(&self) -> Duration {
+		self.backoff
+	}
+
+	/// Runs `f`, retrying it according to this policy if it fails with an
+	/// error that may be transient.
+	pub(crate) fn run<T, F: FnMut() -> io::Result<T>>(&self, mut f: F) -> io::Result<T> {
+		let mut wait = self.backoff;
+		let mut retries = 0;
+
+		loop {
+			match f() {
+				Err(e) if retries < self.retries && is_transient(&e) => {
+					thread::sleep(wait);
+					wait = wait.saturating_mul(2);
+					retries += 1;
+				}
+				result => return result,
+			};
+		}
+	}
+}
+
+/// Returns whether an error may not occur again if the failed operation is
+/// retried.
+fn is_transient(error: &io::Error) -> bool {
+	!matches!(
+		error.kind(),
+		io::ErrorKind::NotFound
+			| io::ErrorKind::PermissionDenied
+			| io::ErrorKind::InvalidInput
+			| io::ErrorKind::InvalidData
+			| io::ErrorKind::Unsupported
+	)
+}
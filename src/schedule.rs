@@ -0,0 +1,182 @@
+use std::str::FromStr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// The number of seconds in a day.
+const DAY: i64 = 86400;
+
+/// The longest interval between checks, of 100 years, in seconds.
+const MAX_INTERVAL: u64 = 36525 * DAY as u64;
+
+/// The days of the week, starting from Monday, as parsed by `Schedule`.
+const WEEKDAYS: [&str; 7] = ["mon", "tue", "wed", "thu", "fri", "sat", "sun"];
+
+/// When a `Daemon` runs a profile's scheduled checks.
+///
+/// Schedules are parsed from strings such as `daily 02:00`, `weekly sun
+/// 03:30` or `every 6h`.  `nightly` can be used instead of `daily`, and the
+/// time can be preceded by `at`, as in `nightly at 02:00`.  Intervals are
+/// given in minutes (`m`), hours (`h`) or days (`d`), and can be at most
+/// 100 years.  Times are in the
+/// local time zone.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Schedule {
+	/// Every day at the given hour and minute.
+	Daily {
+		/// The hour, from 0 to 23.
+		hour: u8,
+
+		/// The minute, from 0 to 59.
+		minute: u8,
+	},
+
+	/// Every week on the given day at the given hour and minute.
+	Weekly {
+		/// The day of the week, from 0 for Monday to 6 for Sunday.
+		weekday: u8,
+
+		/// The hour, from 0 to 23.
+		hour: u8,
+
+		/// The minute, from 0 to 59.
+		minute: u8,
+	},
+
+	/// Repeatedly, with the given time between checks.
+	Every(Duration),
+}
+
+impl FromStr for Schedule {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Schedule, String> {
+		let invalid = || format!("invalid schedule {}", s);
+		let words: Vec<&str> = s
+			.split_whitespace()
+			.filter(|w| !w.eq_ignore_ascii_case("at"))
+			.collect();
+
+		match words.as_slice() {
+			[period, time] if *period == "daily" || *period == "nightly" => {
+				let (hour, minute) = parse_time(time).ok_or_else(invalid)?;
+				Ok(Schedule::Daily { hour, minute })
+			}
+			["weekly", day, time] => {
+				let day = day.to_lowercase();
+				let weekday = WEEKDAYS
+					.iter()
+					.position(|d| day.starts_with(d))
+					.ok_or_else(invalid)?;
+				let (hour, minute) = parse_time(time).ok_or_else(invalid)?;
+
+				Ok(Schedule::Weekly {
+					weekday: weekday as u8,
+					hour,
+					minute,
+				})
+			}
+			["every", interval] => {
+				let unit = match interval.chars().last() {
+					Some('m') => 60,
+					Some('h') => 3600,
+					Some('d') => DAY as u64,
+					_ => return Err(invalid()),
+				};
+				let count: u64 = interval[..interval.len() - 1]
+					.parse()
+					.map_err(|_| invalid())?;
+
+				let interval = count
+					.checked_mul(unit)
+					.filter(|secs| (1..=MAX_INTERVAL).contains(secs))
+					.map(Duration::from_secs)
+					.filter(|d| SystemTime::now().checked_add(*d).is_some())
+					.ok_or_else(invalid)?;
+
+				Ok(Schedule::Every(interval))
+			}
+			_ => Err(invalid()),
+		}
+	}
+}
+
+impl Schedule {
+	/// Returns the first time after the given time that a check is due, or
+	/// `None` if it's too far away to be represented.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use std::time::SystemTime;
+	///
+	/// let schedule: dupcheck::Schedule = "nightly at 02:00".parse().unwrap();
+	/// let next = schedule.next_after(SystemTime::now());
+	/// ```
+	pub fn next_after(&self, time: SystemTime) -> Option<SystemTime> {
+		let (hour, minute, weekday) = match *self {
+			Schedule::Every(interval) => return time.checked_add(interval),
+			Schedule::Daily { hour, minute } => (hour, minute, None),
+			Schedule::Weekly {
+				weekday,
+				hour,
+				minute,
+			} => (hour, minute, Some(weekday)),
+		};
+
+		let secs = time
+			.duration_since(UNIX_EPOCH)
+			.map_or(0, |d| d.as_secs() as i64);
+		let offset = local_offset(secs);
+		let local = secs + offset;
+		let day_start = local - local.rem_euclid(DAY);
+		let mut next = day_start + hour as i64 * 3600 + minute as i64 * 60;
+
+		// 1 January 1970 was a Thursday.
+		if let Some(weekday) = weekday {
+			let today = (local.div_euclid(DAY) + 3).rem_euclid(7);
+			next += (weekday as i64 - today).rem_euclid(7) * DAY;
+		}
+
+		if next <= local {
+			next += match weekday {
+				Some(_) => 7 * DAY,
+				None => DAY,
+			};
+		}
+
+		UNIX_EPOCH.checked_add(Duration::from_secs((next - offset).max(0) as u64))
+	}
+}
+
+/// Parses a time of day given as `HH:MM`.
+fn parse_time(time: &str) -> Option<(u8, u8)> {
+	let (hour, minute) = time.split_once(':')?;
+	let hour: u8 = hour.parse().ok()?;
+	let minute: u8 = minute.parse().ok()?;
+
+	match hour < 24 && minute < 60 {
+		true => Some((hour, minute)),
+		false => None,
+	}
+}
+
+/// Returns the local time zone's offset from UTC, in seconds, at the given
+/// Unix time.
+#[cfg(unix)]
+fn local_offset(secs: i64) -> i64 {
+	let time = secs as libc::time_t;
+	let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+	let result = unsafe { libc::localtime_r(&time, &mut tm) };
+
+	match result.is_null() {
+		true => 0,
+		false => tm.tm_gmtoff as i64,
+	}
+}
+
+/// Returns the local time zone's offset from UTC, in seconds, at the given
+/// Unix time.  The time zone isn't known on this platform, so times are in
+/// UTC.
+#[cfg(not(unix))]
+fn local_offset(_secs: i64) -> i64 {
+	0
+}
@@ -0,0 +1,238 @@
+use crate::actions::{Action, ActionPlan};
+use crate::keep::KeepPolicy;
+use crate::utilities::{absolute, quarantine_path};
+use crate::DupResults;
+use std::io;
+use std::io::Write;
+use std::path::Path;
+use std::str::FromStr;
+
+/// The kind of shell script written by `DupResults::write_script()`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ScriptFormat {
+	/// A POSIX shell script.
+	Sh,
+
+	/// A PowerShell script.
+	PowerShell,
+}
+
+impl FromStr for ScriptFormat {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<ScriptFormat, String> {
+		match s {
+			"sh" => Ok(ScriptFormat::Sh),
+			"powershell" => Ok(ScriptFormat::PowerShell),
+			_ => Err(format!("unknown script format {}", s)),
+		}
+	}
+}
+
+impl DupResults {
+	/// Writes a commented shell script to the given writer that will carry
+	/// out the given action on each duplicate file group, keeping the group's
+	/// canonical copy or otherwise the file selected by the keep policy.
+	///
+	/// Nothing is changed by writing the script, so it can be reviewed and
+	/// edited before it's run.  Groups that couldn't be planned, or that have
+	/// a path that isn't valid UTF-8 or contains line breaks or other control
+	/// characters, are skipped and noted in comments.
+	///
+	/// # Errors
+	///
+	/// Returns an error if writing fails.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use dupcheck::{Action, KeepPolicy, ScriptFormat};
+	/// use std::path::PathBuf;
+	///
+	/// let dirs = vec![PathBuf::from("foo")];
+	/// let mut dup_result = dupcheck::DupResults::new();
+	///
+	/// if let Err(dup_error) = dup_result.within(&dirs) {
+	///     // Error handling
+	/// }
+	///
+	/// let mut stdout = std::io::stdout();
+	///
+	/// if let Err(write_error) =
+	///     dup_result.write_script(&mut stdout, ScriptFormat::Sh, &KeepPolicy::Oldest, Action::Delete)
+	/// {
+	///     // Error handling
+	/// }
+	/// ```
+	pub fn write_script<W: Write>(
+		&self,
+		writer: &mut W,
+		format: ScriptFormat,
+		policy: &KeepPolicy,
+		action: Action,
+	) -> io::Result<()> {
+		if format == ScriptFormat::Sh {
+			writeln!(writer, "#!/bin/sh")?;
+		}
+
+		writeln!(
+			writer,
+			"# Generated by dupcheck {}.  Review this script before running it;",
+			env!("CARGO_PKG_VERSION")
+		)?;
+		writeln!(writer, "# comment out any line to leave that file alone.")?;
+
+		if format == ScriptFormat::PowerShell && action == Action::Trash {
+			writeln!(writer, "Add-Type -AssemblyName Microsoft.VisualBasic")?;
+		}
+
+		for group in &self.duplicates {
+			writeln!(writer)?;
+			writeln!(writer, "# Duplicates of file {}:", comment(&group.hash))?;
+
+			let plan = match group.plan(policy, action.clone()) {
+				Ok(p) => p,
+				Err(e) => {
+					writeln!(
+						writer,
+						"# Skipped, as the group couldn't be planned ({}).",
+						comment(&e.to_string())
+					)?;
+					continue;
+				}
+			};
+
+			// A path that isn't valid UTF-8 can't be written into the script
+			// as it is, and running commands on a changed path could affect a
+			// different file.  A path split across lines would leave part of
+			// a command behind if its first line were commented out.
+			let lines = match plan_lines(format, &action, &plan) {
+				Some(l) => l,
+				None => {
+					writeln!(
+						writer,
+						"# Skipped, as the group has a path that can't be written safely in a script."
+					)?;
+					continue;
+				}
+			};
+
+			for line in lines {
+				writeln!(writer, "{}", line)?;
+			}
+		}
+
+		Ok(())
+	}
+}
+
+/// Returns the script lines for an action plan, starting with a comment
+/// naming the file kept, or `None` if any of the plan's paths can't be
+/// quoted.
+fn plan_lines(format: ScriptFormat, action: &Action, plan: &ActionPlan) -> Option<Vec<String>> {
+	let keep = quote(format, &absolute(plan.keep()))?;
+	let mut lines = vec![format!("# Keeping {}", comment(&keep))];
+
+	for path in plan.remove() {
+		let file = quote(format, &absolute(path))?;
+		let target = quote(format, &plan.link_target(path))?;
+		let line = match (format, action) {
+			(ScriptFormat::Sh, Action::Delete) => format!("rm -- {}", file),
+			(ScriptFormat::Sh, Action::Hardlink) => format!("ln -f -- {} {}", keep, file),
+			(ScriptFormat::Sh, Action::Symlink | Action::RelativeSymlink) => {
+				format!("ln -sf -- {} {}", target, file)
+			}
+			(ScriptFormat::Sh, Action::Reflink) => {
+				format!("cp --reflink=always -- {} {}", keep, file)
+			}
+			(ScriptFormat::PowerShell, Action::Reflink) => {
+				format!(
+					"# Reflinks aren't supported in PowerShell scripts: {}",
+					comment(&file)
+				)
+			}
+			(ScriptFormat::Sh, Action::MoveTo(dir)) => {
+				let to = quarantine_path(dir, path);
+				let to_dir = quote(format, to.parent().unwrap_or(dir))?;
+				let to = quote(format, &to)?;
+				format!("mkdir -p -- {} && mv -n -- {} {}", to_dir, file, to)
+			}
+			(ScriptFormat::PowerShell, Action::MoveTo(dir)) => {
+				let to = quarantine_path(dir, path);
+				let to_dir = quote(format, to.parent().unwrap_or(dir))?;
+				let to = quote(format, &to)?;
+				format!(
+					"New-Item -ItemType Directory -Force -Path {} | Out-Null; Move-Item -LiteralPath {} -Destination {}",
+					to_dir, file, to
+				)
+			}
+			(ScriptFormat::Sh, Action::Trash) => format!("gio trash -- {}", file),
+			(ScriptFormat::PowerShell, Action::Trash) => format!(
+				"[Microsoft.VisualBasic.FileIO.FileSystem]::DeleteFile({}, 'OnlyErrorDialogs', 'SendToRecycleBin')",
+				file
+			),
+			(ScriptFormat::PowerShell, Action::Delete) => {
+				format!("Remove-Item -LiteralPath {}", file)
+			}
+			(ScriptFormat::PowerShell, Action::Hardlink) => format!(
+				"Remove-Item -LiteralPath {}; New-Item -ItemType HardLink -Path {} -Value {} | Out-Null",
+				file, file, keep
+			),
+			(ScriptFormat::PowerShell, Action::Symlink | Action::RelativeSymlink) => format!(
+				"Remove-Item -LiteralPath {}; New-Item -ItemType SymbolicLink -Path {} -Value {} | Out-Null",
+				file, file, target
+			),
+		};
+		lines.push(line);
+	}
+
+	Some(lines)
+}
+
+/// Returns text made safe to write in a script comment, with line breaks
+/// and other control characters escaped so the text can't end the comment
+/// and run as a command.
+fn comment(text: &str) -> String {
+	let mut escaped = String::new();
+
+	for c in text.chars() {
+		match c.is_control() || matches!(c, '\u{2028}' | '\u{2029}') {
+			true => escaped.extend(c.escape_default()),
+			false => escaped.push(c),
+		};
+	}
+
+	escaped
+}
+
+/// Returns a path quoted for use in a script of the given format, or `None`
+/// if the path isn't valid UTF-8 or contains control characters.
+pub(crate) fn quote(format: ScriptFormat, path: &Path) -> Option<String> {
+	let path = path.to_str()?;
+
+	if path
+		.chars()
+		.any(|c| c.is_control() || matches!(c, '\u{2028}' | '\u{2029}'))
+	{
+		return None;
+	}
+
+	Some(match format {
+		ScriptFormat::Sh => format!("'{}'", path.replace('\'', "'\\''")),
+		ScriptFormat::PowerShell => {
+			// PowerShell also treats typographic single quotes as quotes.
+			let mut quoted = String::from("'");
+
+			for c in path.chars() {
+				if matches!(c, '\'' | '\u{2018}' | '\u{2019}' | '\u{201a}' | '\u{201b}') {
+					quoted.push(c);
+				}
+
+				quoted.push(c);
+			}
+
+			quoted.push('\'');
+			quoted
+		}
+	})
+}
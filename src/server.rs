@@ -0,0 +1,481 @@
+use crate::cache::HashCache;
+use crate::handle::ScanHandle;
+use crate::json;
+use crate::metrics::Metrics;
+use crate::progress::{Progress, ScanPhase};
+use crate::{DupOptions, DupResults};
+use std::io;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread;
+use std::time::Duration;
+
+/// The largest request body that will be read, in bytes.
+const MAX_BODY_LEN: usize = 1 << 20;
+
+/// The largest request line and headers that will be read, in bytes.
+const MAX_HEAD_LEN: u64 = 16 * 1024;
+
+/// How long a connection can wait to send or receive data before it's
+/// closed.
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The most connections that are handled at once.  Connections beyond this
+/// are closed without a response.
+const MAX_CONNECTIONS: usize = 64;
+
+/// The most finished scans that are kept, after which the oldest finished
+/// scan is removed as each scan finishes.
+const MAX_FINISHED_SCANS: usize = 32;
+
+/// The most scans that can be running or paused at once.  Requests to start
+/// more are refused until one finishes.
+const MAX_RUNNING_SCANS: usize = 4;
+
+/// A scan started by a request to a `Server`.
+struct Scan {
+	/// The scan's ID.
+	id: usize,
+
+	/// The directories being checked.
+	dirs: Vec<PathBuf>,
+
+	/// The progress of the check.
+	progress: Progress,
+
+	/// The handle used to pause and resume the check.
+	handle: ScanHandle,
+
+	/// The results as JSON, or an error message if the check failed, once
+	/// the check has finished.
+	results: Option<Result<String, String>>,
+}
+
+/// An HTTP server that runs duplicate checks in the background and reports
+/// their progress and results as JSON, keeping a cache of file hashes
+/// between checks.
+///
+/// The endpoints are:
+///
+/// - `POST /scans` starts checking the directories listed, one per line, in
+///   the request body, and responds with the scan's `id`.
+/// - `GET /scans` responds with the status of every scan.
+/// - `GET /scans/<id>` responds with a scan's status: its `directories`,
+///   its `state` of `running`, `paused`, `finished` or `failed`, its
+///   `phase` as described by `ScanPhase`, the numbers of `files_found`,
+///   `files_hashed`, `files_total`, `bytes_hashed`, `bytes_total` and
+///   `errors` so far, the `current_path` being read, and the `throughput`
+///   in bytes per second and `eta_seconds` while comparing files, any of
+///   which may be `null`.
+/// - `POST /scans/<id>/pause` pauses a scan before it next reads a file or
+///   directory, and `POST /scans/<id>/resume` resumes it.  Both respond
+///   with the scan's status.
+/// - `GET /scans/<id>/results` responds with a finished scan's results in
+///   the format written by `DupResults::write_json()`.
+/// - `GET /metrics` responds with metrics in the Prometheus text format:
+///   counters of the checks finished and the files found and hashed, and
+///   bytes hashed, by them, and gauges of the duplicate groups, redundant
+///   files and reclaimable bytes found by the latest check of each set of
+///   directories.
+///
+/// Only 4 scans can run at once, and starting another responds with a 503
+/// status until one finishes.  Only the 32 most recently finished scans are
+/// kept, so older scans are no longer found.
+///
+/// Failed requests are responded to with `{"error":"<message>"}`.
+#[derive(Clone)]
+pub struct Server {
+	/// The options used for each check, including the shared hash cache.
+	options: DupOptions,
+
+	/// The scans started and not yet removed, in order.
+	scans: Arc<Mutex<Vec<Scan>>>,
+
+	/// The ID given to the last scan started.
+	last_id: Arc<AtomicUsize>,
+
+	/// Totals recorded over all finished checks.
+	metrics: Arc<Mutex<Metrics>>,
+}
+
+impl Server {
+	/// Creates a new `Server` that runs checks with the given options.  If
+	/// the options don't have a hash cache, a new one is used.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// let server = dupcheck::Server::new(dupcheck::DupOptions::new());
+	/// let (status, content_type, body) = server.handle("POST", "/scans", "foo\nbar");
+	/// ```
+	pub fn new(mut options: DupOptions) -> Server {
+		if options.hash_cache().is_none() {
+			options.set_hash_cache(Some(HashCache::new()));
+		}
+
+		Server {
+			options,
+			scans: Arc::new(Mutex::new(vec![])),
+			last_id: Arc::new(AtomicUsize::new(0)),
+			metrics: Arc::new(Mutex::new(Metrics::default())),
+		}
+	}
+
+	/// Returns a reference to the options used for each check.
+	pub fn options(&self) -> &DupOptions {
+		&self.options
+	}
+
+	/// Handles a single request with the given method, path and body,
+	/// returning the HTTP status code, the response's content type and the
+	/// response body.
+	pub fn handle(&self, method: &str, path: &str, body: &str) -> (u16, &'static str, String) {
+		let path = path.split('?').next().unwrap_or_default();
+		let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+
+		if segments == ["metrics"] {
+			return match method {
+				"GET" => (
+					200,
+					"text/plain; version=0.0.4",
+					self.lock_metrics().prometheus(),
+				),
+				_ => (405, "application/json", json::error("method not allowed")),
+			};
+		}
+
+		let (status, body) = match (method, segments.as_slice()) {
+			("POST", ["scans"]) => self.start_scan(body),
+			("GET", ["scans"]) => {
+				let scans = self.lock();
+				let statuses: Vec<String> = scans.iter().map(status).collect();
+
+				(200, format!("{{\"scans\":[{}]}}", statuses.join(",")))
+			}
+			("GET", ["scans", id]) => match self.find(id) {
+				Some((i, scans)) => (200, status(&scans[i])),
+				None => (404, json::error("scan not found")),
+			},
+			("POST", ["scans", id, action @ ("pause" | "resume")]) => match self.find(id) {
+				Some((i, scans)) => {
+					let scan = &scans[i];
+
+					match *action {
+						"pause" => scan.handle.pause(),
+						_ => scan.handle.resume(),
+					};
+
+					(200, status(scan))
+				}
+				None => (404, json::error("scan not found")),
+			},
+			("GET", ["scans", id, "results"]) => match self.find(id) {
+				Some((i, scans)) => match &scans[i].results {
+					Some(Ok(results)) => (200, results.clone()),
+					Some(Err(e)) => (500, json::error(e)),
+					None => (409, json::error("scan is still running")),
+				},
+				None => (404, json::error("scan not found")),
+			},
+			(_, ["scans"]) | (_, ["scans", _]) | (_, ["scans", _, "results" | "pause" | "resume"]) => {
+				(405, json::error("method not allowed"))
+			}
+			_ => (404, json::error("not found")),
+		};
+
+		(status, "application/json", body)
+	}
+
+	/// Listens for HTTP requests at the given address, handling each
+	/// connection on its own thread, up to 64 at once.  This only returns if
+	/// listening fails.
+	///
+	/// # Errors
+	///
+	/// Returns an error if the address can't be listened on.
+	///
+	/// # Examples
+	///
+	/// ```no_run
+	/// let server = dupcheck::Server::new(dupcheck::DupOptions::new());
+	///
+	/// if let Err(server_error) = server.listen("127.0.0.1:8080") {
+	///     // Error handling
+	/// }
+	/// ```
+	pub fn listen<A: ToSocketAddrs>(&self, address: A) -> io::Result<()> {
+		let listener = TcpListener::bind(address)?;
+		let connections = Arc::new(AtomicUsize::new(0));
+
+		for stream in listener.incoming() {
+			let stream = match stream {
+				Ok(s) => s,
+				Err(_) => continue,
+			};
+
+			if connections.fetch_add(1, Ordering::SeqCst) >= MAX_CONNECTIONS {
+				connections.fetch_sub(1, Ordering::SeqCst);
+				continue;
+			}
+
+			let server = self.clone();
+			let connection = Connection(Arc::clone(&connections));
+
+			thread::spawn(move || {
+				let _ = server.respond(stream);
+				drop(connection);
+			});
+		}
+
+		Ok(())
+	}
+
+	/// Reads a request from a connection and writes the response.
+	fn respond(&self, mut stream: TcpStream) -> io::Result<()> {
+		stream.set_read_timeout(Some(TIMEOUT))?;
+		stream.set_write_timeout(Some(TIMEOUT))?;
+
+		// The request line and headers are read through a limit, so a client
+		// can't send an endless line.  The limit is then raised to the length
+		// of the body.
+		let mut reader = BufReader::new(stream.try_clone()?.take(MAX_HEAD_LEN));
+		let mut request_line = String::new();
+
+		if reader.read_line(&mut request_line)? == 0 {
+			return Ok(());
+		}
+
+		// A line is only cut short by the limit if the limit was reached, as
+		// the client may also stop sending after the last line.
+		let mut head_complete = request_line.ends_with('\n') || reader.get_ref().limit() > 0;
+
+		let mut parts = request_line.split_whitespace();
+		let method = parts.next().unwrap_or_default().to_string();
+		let path = parts.next().unwrap_or_default().to_string();
+		let mut content_len = 0;
+
+		while head_complete {
+			let mut header = String::new();
+			reader.read_line(&mut header)?;
+
+			if !header.ends_with('\n') && reader.get_ref().limit() == 0 {
+				head_complete = false;
+				break;
+			}
+
+			if header.trim().is_empty() {
+				break;
+			}
+
+			if let Some((name, value)) = header.split_once(':') {
+				if name.trim().eq_ignore_ascii_case("content-length") {
+					content_len = value.trim().parse().unwrap_or(0);
+				}
+			}
+		}
+
+		let (status, content_type, body) = match content_len {
+			_ if !head_complete => (
+				431,
+				"application/json",
+				json::error("request headers are too large"),
+			),
+			len if len > MAX_BODY_LEN => (
+				413,
+				"application/json",
+				json::error("request body is too large"),
+			),
+			len => {
+				let mut body = vec![0; len];
+				reader.get_mut().set_limit(len as u64);
+				reader.read_exact(&mut body)?;
+				self.handle(&method, &path, &String::from_utf8_lossy(&body))
+			}
+		};
+
+		write!(
+			stream,
+			"HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+			status,
+			reason(status),
+			content_type,
+			body.len(),
+			body,
+		)
+	}
+
+	/// Starts checking the directories listed in a request body on a new
+	/// thread.
+	fn start_scan(&self, body: &str) -> (u16, String) {
+		let dirs: Vec<PathBuf> = body
+			.lines()
+			.map(|l| l.trim())
+			.filter(|l| !l.is_empty())
+			.map(PathBuf::from)
+			.collect();
+
+		if dirs.is_empty() {
+			return (400, json::error("no directories given"));
+		}
+
+		let progress = Progress::new();
+		let scan_progress = progress.clone();
+		let handle = ScanHandle::new();
+		let mut options = self.options.clone();
+		options.set_progress(Some(progress.clone()));
+		options.set_scan_handle(Some(handle.clone()));
+
+		// The scans stay locked from counting them until the new scan is
+		// added, so concurrent requests can't start more than the limit.
+		let mut scans = self.lock();
+
+		if scans.iter().filter(|s| s.results.is_none()).count() >= MAX_RUNNING_SCANS {
+			return (503, json::error("too many scans are running"));
+		}
+
+		let id = self.last_id.fetch_add(1, Ordering::SeqCst) + 1;
+		scans.push(Scan {
+			id,
+			dirs: dirs.clone(),
+			progress,
+			handle,
+			results: None,
+		});
+		drop(scans);
+		let server = self.clone();
+
+		thread::spawn(move || {
+			let mut dup_results = DupResults::with_options(options);
+			let mut results = vec![];
+			let checked = dup_results.within(&dirs);
+			server.lock_metrics().record(
+				&dirs,
+				&scan_progress,
+				checked.as_ref().ok().map(|_| &dup_results),
+			);
+
+			let written = checked.and_then(|_| dup_results.write_json(&mut results));
+
+			let mut scans = server.lock();
+
+			if let Some(scan) = scans.iter_mut().find(|s| s.id == id) {
+				scan.results = Some(match written {
+					Ok(_) => Ok(String::from_utf8_lossy(&results).trim_end().to_string()),
+					Err(e) => Err(e.to_string()),
+				});
+			}
+
+			let finished = scans.iter().filter(|s| s.results.is_some()).count();
+
+			if finished > MAX_FINISHED_SCANS {
+				if let Some(i) = scans.iter().position(|s| s.results.is_some()) {
+					scans.remove(i);
+				}
+			}
+		});
+
+		(202, format!("{{\"id\":{}}}", id))
+	}
+
+	/// Returns the index of the scan with the given ID, if it exists, along
+	/// with the locked scans.
+	fn find(&self, id: &str) -> Option<(usize, MutexGuard<'_, Vec<Scan>>)> {
+		let id = id.parse::<usize>().ok()?;
+		let scans = self.lock();
+		let i = scans.iter().position(|s| s.id == id)?;
+
+		Some((i, scans))
+	}
+
+	/// Locks the metrics, recovering them if another thread panicked while
+	/// holding the lock.
+	fn lock_metrics(&self) -> MutexGuard<'_, Metrics> {
+		self.metrics.lock().unwrap_or_else(|e| e.into_inner())
+	}
+
+	/// Locks the scans, recovering them if another thread panicked while
+	/// holding the lock.
+	fn lock(&self) -> MutexGuard<'_, Vec<Scan>> {
+		self.scans.lock().unwrap_or_else(|e| e.into_inner())
+	}
+}
+
+/// Counts a connection being handled by a `Server` until it's dropped.
+struct Connection(Arc<AtomicUsize>);
+
+impl Drop for Connection {
+	fn drop(&mut self) {
+		self.0.fetch_sub(1, Ordering::SeqCst);
+	}
+}
+
+/// Returns a scan's status as JSON.
+fn status(scan: &Scan) -> String {
+	let dirs: Vec<String> = scan
+		.dirs
+		.iter()
+		.map(|d| json::string(&d.to_string_lossy()))
+		.collect();
+	let state = match &scan.results {
+		None if scan.handle.is_paused() => "paused",
+		None => "running",
+		Some(Ok(_)) => "finished",
+		Some(Err(_)) => "failed",
+	};
+
+	let status = scan.progress.status();
+	let phase = match status.phase() {
+		ScanPhase::Waiting => "waiting",
+		ScanPhase::Discovering => "discovering",
+		ScanPhase::Comparing => "comparing",
+		ScanPhase::Finished => "finished",
+	};
+	let current_path = match status.current_path() {
+		Some(path) => json::string(&path.to_string_lossy()),
+		None => "null".to_string(),
+	};
+	let throughput = match status.throughput() {
+		Some(t) => t.to_string(),
+		None => "null".to_string(),
+	};
+	let eta = match status.eta() {
+		Some(eta) => eta.as_secs().to_string(),
+		None => "null".to_string(),
+	};
+
+	format!(
+		"{{\"id\":{},\"directories\":[{}],\"state\":\"{}\",\"phase\":\"{}\",\"files_found\":{},\"files_hashed\":{},\"files_total\":{},\"bytes_hashed\":{},\"bytes_total\":{},\"errors\":{},\"current_path\":{},\"throughput\":{},\"eta_seconds\":{}}}",
+		scan.id,
+		dirs.join(","),
+		state,
+		phase,
+		status.files_found(),
+		status.files_hashed(),
+		status.files_total(),
+		status.bytes_hashed(),
+		status.bytes_total(),
+		status.errors(),
+		current_path,
+		throughput,
+		eta,
+	)
+}
+
+/// Returns the reason phrase for an HTTP status code.
+fn reason(status: u16) -> &'static str {
+	match status {
+		200 => "OK",
+		202 => "Accepted",
+		400 => "Bad Request",
+		404 => "Not Found",
+		405 => "Method Not Allowed",
+		409 => "Conflict",
+		413 => "Payload Too Large",
+		431 => "Request Header Fields Too Large",
+		503 => "Service Unavailable",
+		_ => "Internal Server Error",
+	}
+}
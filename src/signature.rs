@@ -0,0 +1,96 @@
+use crate::events::SkipReason;
+use crate::{Candidate, DupResults};
+use std::fs::File;
+use std::io;
+use std::io::Read;
+use std::path::Path;
+use std::str::FromStr;
+
+/// The bytes a file starts with, used to skip files of certain formats
+/// whatever their names, such as SQLite databases.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Signature {
+	/// The bytes files with the signature start with.
+	bytes: Vec<u8>,
+}
+
+impl FromStr for Signature {
+	type Err = String;
+
+	/// Parses a signature given as hexadecimal bytes, such as
+	/// `53514c69746520666f726d6174203300` for SQLite databases.
+	fn from_str(s: &str) -> Result<Signature, String> {
+		let invalid = || format!("invalid hexadecimal signature {}", s);
+
+		if s.is_empty() || !s.len().is_multiple_of(2) || !s.bytes().all(|b| b.is_ascii_hexdigit()) {
+			return Err(invalid());
+		}
+
+		let bytes = (0..s.len())
+			.step_by(2)
+			.map(|i| u8::from_str_radix(&s[i..i + 2], 16))
+			.collect::<Result<Vec<u8>, _>>()
+			.map_err(|_| invalid())?;
+
+		Ok(Signature { bytes })
+	}
+}
+
+impl Signature {
+	/// Creates a new `Signature` of files starting with the given bytes.
+	pub fn new(bytes: Vec<u8>) -> Signature {
+		Signature { bytes }
+	}
+
+	/// Returns a reference to the bytes files with the signature start
+	/// with.
+	pub fn bytes(&self) -> &[u8] {
+		&self.bytes
+	}
+}
+
+impl DupResults {
+	/// Returns the given files without any that start with one of the
+	/// signatures given by `DupOptions::excluded_signatures()`, sending an
+	/// event for each file left out.  Existing groups' files, and files whose
+	/// start can't be read, are kept, so errors are found when the files are
+	/// compared.
+	pub(crate) fn without_excluded_signatures(&self, set: Vec<Candidate>) -> Vec<Candidate> {
+		let signatures = self.options.excluded_signatures();
+
+		if signatures.is_empty() {
+			return set;
+		}
+
+		let retry = self.options.retry_policy();
+		let len = signatures.iter().map(|s| s.bytes.len()).max().unwrap_or(0);
+
+		set
+			.into_iter()
+			.filter(|c| {
+				if c.group.is_some() {
+					return true;
+				}
+
+				let path = c.path.to_path_buf();
+				let excluded = retry
+					.run(|| leading_bytes(&path, len))
+					.is_ok_and(|bytes| signatures.iter().any(|s| bytes.starts_with(&s.bytes)));
+
+				if excluded {
+					self.options.skip(&path, SkipReason::Signature);
+				}
+
+				!excluded
+			})
+			.collect()
+	}
+}
+
+/// Returns up to the first `len` bytes of a file.
+fn leading_bytes(file: &Path, len: usize) -> io::Result<Vec<u8>> {
+	let mut bytes = vec![];
+	File::open(file)?.take(len as u64).read_to_end(&mut bytes)?;
+
+	Ok(bytes)
+}
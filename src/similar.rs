@@ -0,0 +1,430 @@
+use crate::category::Category;
+use crate::duperror::DupError;
+use crate::utilities::{long_path, PathUtilities};
+use crate::{DupOptions, DupResults};
+use std::collections::HashSet;
+use std::fs::File;
+use std::io;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// The points in each video, as fractions of its duration, at which frames
+/// are sampled.
+const SAMPLE_POINTS: [f64; 5] = [0.1, 0.3, 0.5, 0.7, 0.9];
+
+/// The largest file that's read to be fingerprinted as text, in bytes.
+const MAX_TEXT_LEN: u64 = 16 * 1024 * 1024;
+
+/// The number of bytes read from the start of a file to check that it's
+/// text before the rest is read.
+const TEXT_PREFIX_LEN: u64 = 8 * 1024;
+
+/// The categories of files that aren't read as text, even if they have no
+/// zero bytes.
+const NOT_TEXT: [Category; 4] = [
+	Category::Images,
+	Category::Video,
+	Category::Audio,
+	Category::Archives,
+];
+
+/// A group of files that appear to be near-duplicates of each other, such as
+/// re-encoded copies of a video or edited copies of a document.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SimilarGroup {
+	/// The paths to the similar files.
+	files: Vec<PathBuf>,
+
+	/// The lowest similarity between the first file and any other file in
+	/// the group, from 0 to 1.
+	similarity: f64,
+}
+
+impl SimilarGroup {
+	/// Returns a reference to the paths to the similar files.
+	pub fn get_files(&self) -> &[PathBuf] {
+		&self.files
+	}
+
+	/// Returns how similar the files are, from 0 to 1, where 1 means their
+	/// fingerprints were identical.  This is the lowest similarity between
+	/// the group's first file and any other file in the group.
+	pub fn similarity(&self) -> f64 {
+		self.similarity
+	}
+}
+
+/// The duration and sampled frame hashes of a video.
+struct Fingerprint {
+	/// The video's duration in seconds.
+	duration: f64,
+
+	/// The difference hashes of the sampled frames.
+	frames: Vec<u64>,
+}
+
+impl Fingerprint {
+	/// Returns the similarity of this video to another, from 0 to 1, or
+	/// `None` if their durations are too different for them to be copies.
+	fn similarity(&self, other: &Fingerprint) -> Option<f64> {
+		let tolerance = (self.duration.max(other.duration) * 0.01).max(1.0);
+
+		if (self.duration - other.duration).abs() > tolerance {
+			return None;
+		}
+
+		let distance: u32 = self
+			.frames
+			.iter()
+			.zip(&other.frames)
+			.map(|(a, b)| (a ^ b).count_ones())
+			.sum();
+
+		Some(1.0 - distance as f64 / (64 * self.frames.len()) as f64)
+	}
+}
+
+impl DupResults {
+	/// Checks the videos within the given directories for copies of the same
+	/// video that aren't byte-for-byte duplicates, such as re-encoded copies.
+	/// Any groups found can then be retrieved with `similar()`.
+	///
+	/// This is experimental.  Frames are sampled at fixed points through each
+	/// video and compared by their perceptual hashes, and videos are grouped
+	/// if their durations match and their similarity is at least
+	/// `min_similarity`, from 0 to 1.  `ffmpeg` and `ffprobe` must be
+	/// installed to read the videos.
+	///
+	/// # Errors
+	///
+	/// Returns an error if any `dirs` are not directories.  The `DupResults`
+	/// will contain errors for any videos that couldn't be read.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use std::path::PathBuf;
+	///
+	/// let dirs = vec![PathBuf::from("foo")];
+	/// let mut dup_result = dupcheck::DupResults::new();
+	///
+	/// if let Err(dup_error) = dup_result.similar_videos(&dirs, 0.9) {
+	///     // Error handling
+	/// }
+	///
+	/// for group in dup_result.similar() {
+	///     println!("{:.0}% similar", group.similarity() * 100.0);
+	/// }
+	/// ```
+	pub fn similar_videos<T: AsRef<Path>>(
+		&mut self,
+		dirs: &[T],
+		min_similarity: f64,
+	) -> io::Result<()> {
+		// Only video files are fingerprinted, as well as any other filters.
+		let mut options = self.options.clone();
+		options.set_categories(vec![Category::Video]);
+		let mut fingerprints = vec![];
+
+		for file in self.similar_candidates(dirs, &options)? {
+			options.checkpoint();
+			match fingerprint(&file) {
+				Ok(f) => fingerprints.push((file, f)),
+				Err(e) => self.push_error(DupError::new(file, e)),
+			};
+		}
+
+		self.add_similar_groups(fingerprints, Fingerprint::similarity, min_similarity);
+
+		Ok(())
+	}
+
+	/// Checks the text files within the given directories for documents that
+	/// are nearly the same, such as copies with small edits.  Any groups found
+	/// can then be retrieved with `similar()`.
+	///
+	/// Each file's words are fingerprinted with a SimHash of overlapping
+	/// three-word phrases, and files are grouped if the similarity of their
+	/// fingerprints is at least `min_similarity`, from 0 to 1.  Files
+	/// containing zero bytes aren't treated as text and are skipped, as are
+	/// files larger than 16 MiB and files with the extensions of images,
+	/// video, audio or archives.  Each file's first bytes are checked before
+	/// the rest of it is read.
+	///
+	/// # Errors
+	///
+	/// Returns an error if any `dirs` are not directories.  The `DupResults`
+	/// will contain errors for any files that couldn't be read.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use std::path::PathBuf;
+	///
+	/// let dirs = vec![PathBuf::from("foo")];
+	/// let mut dup_result = dupcheck::DupResults::new();
+	///
+	/// if let Err(dup_error) = dup_result.similar_texts(&dirs, 0.9) {
+	///     // Error handling
+	/// }
+	/// ```
+	pub fn similar_texts<T: AsRef<Path>>(
+		&mut self,
+		dirs: &[T],
+		min_similarity: f64,
+	) -> io::Result<()> {
+		let options = self.options.clone();
+		let retry = options.retry_policy();
+		let mut fingerprints = vec![];
+
+		for file in self.similar_candidates(dirs, &options)? {
+			options.checkpoint();
+			let extension = file
+				.extension()
+				.map(|e| e.to_string_lossy().to_lowercase())
+				.unwrap_or_default();
+
+			if NOT_TEXT.iter().any(|c| c.has_extension(&extension)) {
+				continue;
+			}
+
+			match retry.run(|| read_text(&file)) {
+				Ok(Some(bytes)) => {
+					if let Some(hash) = simhash(&String::from_utf8_lossy(&bytes)) {
+						fingerprints.push((file, hash));
+					}
+				}
+				Ok(None) => {}
+				Err(e) => self.push_error(DupError::new(file, e)),
+			};
+		}
+
+		let similarity = |a: &u64, b: &u64| Some(1.0 - (a ^ b).count_ones() as f64 / 64.0);
+		self.add_similar_groups(fingerprints, similarity, min_similarity);
+
+		Ok(())
+	}
+
+	/// Returns the files within the given directories that are allowed by
+	/// the given options.
+	///
+	/// # Errors
+	///
+	/// Returns an error if any `dirs` are not directories.  The `DupResults`
+	/// will contain errors for any files or directories that couldn't be
+	/// read.
+	fn similar_candidates<T: AsRef<Path>>(
+		&mut self,
+		dirs: &[T],
+		options: &DupOptions,
+	) -> io::Result<Vec<PathBuf>> {
+		self.check_valid_paths(None, Some(dirs))?;
+		let mut files = vec![];
+		let mut searched = HashSet::new();
+
+		for dir in self.convert_to_path_buf(dirs) {
+			let (mut dir_files, mut dir_errors) = dir.files_within(None, options, &mut searched);
+			files.append(&mut dir_files);
+			self.errors.append(&mut dir_errors);
+		}
+
+		Ok(files)
+	}
+
+	/// Groups files by the similarity of their fingerprints, adding each
+	/// file to the first group whose first file is at least `min_similarity`
+	/// similar to it, and adds groups of more than one file to the similar
+	/// groups.
+	fn add_similar_groups<F, S>(
+		&mut self,
+		fingerprints: Vec<(PathBuf, F)>,
+		similarity: S,
+		min_similarity: f64,
+	) where
+		S: Fn(&F, &F) -> Option<f64>,
+	{
+		let mut groups: Vec<(Vec<(PathBuf, F)>, f64)> = vec![];
+
+		for (file, fingerprint) in fingerprints {
+			let similar = groups.iter().enumerate().find_map(|(i, (members, _))| {
+				similarity(&members[0].1, &fingerprint)
+					.filter(|s| *s >= min_similarity)
+					.map(|s| (i, s))
+			});
+
+			match similar {
+				Some((i, s)) => {
+					groups[i].1 = groups[i].1.min(s);
+					groups[i].0.push((file, fingerprint));
+				}
+				None => groups.push((vec![(file, fingerprint)], 1.0)),
+			};
+		}
+
+		for (members, similarity) in groups {
+			if members.len() > 1 {
+				self.similar.push(SimilarGroup {
+					files: members.into_iter().map(|(file, _)| file).collect(),
+					similarity,
+				});
+			}
+		}
+	}
+
+	/// Returns a reference to the groups of similar files found by
+	/// `similar_videos()` and `similar_texts()`.
+	pub fn similar(&self) -> &[SimilarGroup] {
+		&self.similar
+	}
+}
+
+/// Returns the contents of a file to be fingerprinted as text, or `None` if
+/// it's larger than `MAX_TEXT_LEN` or contains a zero byte.  The start of
+/// the file is checked before the rest is read, so most binary files are
+/// only partly read.
+fn read_text(file: &Path) -> io::Result<Option<Vec<u8>>> {
+	let mut reader = File::open(file)?;
+
+	if reader.metadata()?.len() > MAX_TEXT_LEN {
+		return Ok(None);
+	}
+
+	let mut bytes = vec![];
+	(&mut reader)
+		.take(TEXT_PREFIX_LEN)
+		.read_to_end(&mut bytes)?;
+
+	if bytes.contains(&0) {
+		return Ok(None);
+	}
+
+	// The file may have grown since its length was read.
+	let prefix_len = bytes.len();
+	reader
+		.take(MAX_TEXT_LEN + 1 - prefix_len as u64)
+		.read_to_end(&mut bytes)?;
+
+	if bytes.len() as u64 > MAX_TEXT_LEN || bytes[prefix_len..].contains(&0) {
+		return Ok(None);
+	}
+
+	Ok(Some(bytes))
+}
+
+/// Returns the SimHash of a text's overlapping three-word phrases, or `None`
+/// if it has no words.
+fn simhash(text: &str) -> Option<u64> {
+	let words: Vec<String> = text
+		.split(|c: char| !c.is_alphanumeric())
+		.filter(|w| !w.is_empty())
+		.map(|w| w.to_lowercase())
+		.collect();
+
+	if words.is_empty() {
+		return None;
+	}
+
+	let mut weights = [0i64; 64];
+
+	for phrase in words.windows(3.min(words.len())) {
+		let hash = blake3::hash(phrase.join(" ").as_bytes());
+		let bits = u64::from_le_bytes(hash.as_bytes()[..8].try_into().unwrap_or_default());
+
+		for (i, weight) in weights.iter_mut().enumerate() {
+			match bits >> i & 1 {
+				1 => *weight += 1,
+				_ => *weight -= 1,
+			};
+		}
+	}
+
+	Some(
+		weights
+			.iter()
+			.enumerate()
+			.fold(0, |hash, (i, weight)| hash | ((*weight > 0) as u64) << i),
+	)
+}
+
+/// Returns the duration and sampled frame hashes of a video.
+fn fingerprint(file: &Path) -> io::Result<Fingerprint> {
+	let output = Command::new("ffprobe")
+		.args([
+			"-v",
+			"error",
+			"-show_entries",
+			"format=duration",
+			"-of",
+			"default=noprint_wrappers=1:nokey=1",
+		])
+		.arg(long_path(file))
+		.output()
+		.map_err(not_installed)?;
+	let duration: f64 = String::from_utf8_lossy(&output.stdout)
+		.trim()
+		.parse()
+		.map_err(|_| {
+			io::Error::new(
+				io::ErrorKind::InvalidData,
+				"couldn't read the video's duration",
+			)
+		})?;
+	let mut frames = vec![];
+
+	for point in SAMPLE_POINTS {
+		frames.push(frame_hash(file, duration * point)?);
+	}
+
+	Ok(Fingerprint { duration, frames })
+}
+
+/// Returns the difference hash of the frame at the given time in a video,
+/// made by shrinking it to 9 by 8 grey pixels and recording whether each
+/// pixel is darker than the one to its right.
+fn frame_hash(file: &Path, time: f64) -> io::Result<u64> {
+	let output = Command::new("ffmpeg")
+		.args(["-v", "error", "-ss", &format!("{:.3}", time), "-i"])
+		.arg(long_path(file))
+		.args([
+			"-frames:v",
+			"1",
+			"-vf",
+			"scale=9:8,format=gray",
+			"-f",
+			"rawvideo",
+			"-",
+		])
+		.output()
+		.map_err(not_installed)?;
+	let pixels = output.stdout;
+
+	if pixels.len() < 72 {
+		return Err(io::Error::new(
+			io::ErrorKind::InvalidData,
+			"couldn't read a frame from the video",
+		));
+	}
+
+	let mut hash = 0;
+
+	for row in pixels[..72].chunks(9) {
+		for x in 0..8 {
+			hash = (hash << 1) | (row[x] < row[x + 1]) as u64;
+		}
+	}
+
+	Ok(hash)
+}
+
+/// Returns a clearer error if running `ffmpeg` or `ffprobe` failed because
+/// it isn't installed.
+fn not_installed(error: io::Error) -> io::Error {
+	match error.kind() {
+		io::ErrorKind::NotFound => io::Error::new(
+			io::ErrorKind::NotFound,
+			"ffmpeg and ffprobe must be installed to check for similar videos",
+		),
+		_ => error,
+	}
+}
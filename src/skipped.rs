@@ -0,0 +1,67 @@
+use crate::events::SkipReason;
+use crate::DupResults;
+use std::mem;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+/// The files and directories skipped by a running check, which may be added
+/// to from any of its threads.
+///
+/// Copies of a `SkipLog` start empty, so checks run at the same time with
+/// copies of the same options don't take each other's skipped files.
+#[derive(Debug, Default)]
+pub(crate) struct SkipLog(Mutex<Vec<(PathBuf, SkipReason)>>);
+
+impl Clone for SkipLog {
+	fn clone(&self) -> SkipLog {
+		SkipLog::default()
+	}
+}
+
+impl SkipLog {
+	/// Records a skipped file or directory.
+	pub(crate) fn push(&self, path: PathBuf, reason: SkipReason) {
+		self
+			.0
+			.lock()
+			.unwrap_or_else(|e| e.into_inner())
+			.push((path, reason));
+	}
+
+	/// Returns the skipped files and directories, leaving the log empty.
+	pub(crate) fn take(&self) -> Vec<(PathBuf, SkipReason)> {
+		mem::take(&mut *self.0.lock().unwrap_or_else(|e| e.into_inner()))
+	}
+}
+
+impl DupResults {
+	/// Returns a reference to the paths of the files and directories skipped
+	/// by checks with these `DupResults`, with the reason each was skipped,
+	/// in the order they were skipped.  These are only recorded if
+	/// `DupOptions::record_skipped()` or `DupOptions::explainable()` is
+	/// enabled.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use std::path::PathBuf;
+	///
+	/// let dirs = vec![PathBuf::from("foo")];
+	/// let mut options = dupcheck::DupOptions::new();
+	/// options.set_skip_junk(true);
+	/// options.set_record_skipped(true);
+	///
+	/// let mut dup_result = dupcheck::DupResults::with_options(options);
+	///
+	/// if let Err(dup_error) = dup_result.within(&dirs) {
+	///     // Error handling
+	/// }
+	///
+	/// for (path, reason) in dup_result.skipped() {
+	///     println!("{} ({:?})", path.display(), reason);
+	/// }
+	/// ```
+	pub fn skipped(&self) -> &[(PathBuf, SkipReason)] {
+		&self.skipped
+	}
+}
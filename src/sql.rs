@@ -0,0 +1,171 @@
+use crate::metadata::FileMetadata;
+use crate::DupResults;
+use std::io;
+use std::io::Write;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The statements creating the tables that results are inserted into.
+const SCHEMA: &str = "CREATE TABLE IF NOT EXISTS scans (
+	id INTEGER PRIMARY KEY,
+	finished INTEGER NOT NULL,
+	duplicate_groups INTEGER NOT NULL,
+	duplicate_files INTEGER NOT NULL,
+	reclaimable_bytes INTEGER NOT NULL,
+	errors INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS groups (
+	id INTEGER PRIMARY KEY,
+	scan_id INTEGER NOT NULL REFERENCES scans (id),
+	hash TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS files (
+	id INTEGER PRIMARY KEY,
+	group_id INTEGER NOT NULL REFERENCES groups (id),
+	path TEXT NOT NULL,
+	size INTEGER,
+	baseline INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS tags (
+	id INTEGER PRIMARY KEY,
+	group_id INTEGER NOT NULL REFERENCES groups (id),
+	key TEXT NOT NULL,
+	value TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS errors (
+	id INTEGER PRIMARY KEY,
+	scan_id INTEGER NOT NULL REFERENCES scans (id),
+	message TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS groups_hash ON groups (hash);
+CREATE INDEX IF NOT EXISTS files_path ON files (path);";
+
+impl DupResults {
+	/// Exports the results to the given writer as SQL statements that add
+	/// them to a SQLite database, so the results of many checks can be kept
+	/// and queried together.
+	///
+	/// This is an export of results that are already held in memory, once a
+	/// check has finished, so it doesn't reduce the memory a check uses.
+	///
+	/// The statements create the `scans`, `groups`, `files`, `tags` and
+	/// `errors` tables if they don't exist, then add a row to `scans` for
+	/// this check, with the time it was written and totals of the results,
+	/// and rows for its duplicate groups, their files and tags, and any
+	/// errors.  Files are marked as `baseline` files if they were listed in
+	/// a manifest.  Their sizes are the ones recorded when they were added
+	/// to their groups, or null if none were, as for baseline files.  Paths
+	/// that aren't valid UTF-8 are written as blobs of their bytes rather
+	/// than as text.  The statements run in a single transaction.
+	///
+	/// # Errors
+	///
+	/// Returns an error if writing fails.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use std::path::PathBuf;
+	///
+	/// let dirs = vec![PathBuf::from("foo")];
+	/// let mut dup_result = dupcheck::DupResults::new();
+	///
+	/// if let Err(dup_error) = dup_result.within(&dirs) {
+	///     // Error handling
+	/// }
+	///
+	/// // The output can be run with `sqlite3 results.db < results.sql`.
+	/// if let Err(write_error) = dup_result.write_sql(&mut std::io::stdout()) {
+	///     // Error handling
+	/// }
+	/// ```
+	pub fn write_sql<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+		let finished = SystemTime::now()
+			.duration_since(UNIX_EPOCH)
+			.map_or(0, |d| d.as_secs());
+		let groups = self.duplicates.len();
+
+		writeln!(writer, "BEGIN;")?;
+		writeln!(writer, "{}", SCHEMA)?;
+		writeln!(
+			writer,
+			"INSERT INTO scans (finished, duplicate_groups, duplicate_files, reclaimable_bytes, errors) VALUES ({}, {}, {}, {}, {});",
+			finished,
+			groups,
+			self.file_count() - groups,
+			self.reclaimable_bytes(),
+			self.errors.len(),
+		)?;
+
+		for group in &self.duplicates {
+			writeln!(
+				writer,
+				"INSERT INTO groups (scan_id, hash) VALUES ((SELECT max(id) FROM scans), {});",
+				string(&group.hash),
+			)?;
+
+			let files = group
+				.files
+				.iter()
+				.enumerate()
+				.map(|(i, f)| (f, group.metadata_at(i), false));
+			let baseline = group.baseline.iter().map(|f| (f, None, true));
+
+			for (file, metadata, is_baseline) in files.chain(baseline) {
+				writeln!(
+					writer,
+					"INSERT INTO files (group_id, path, size, baseline) VALUES ((SELECT max(id) FROM groups), {}, {}, {});",
+					path(file),
+					size(metadata),
+					is_baseline as u8,
+				)?;
+			}
+
+			for (key, value) in &group.tags {
+				writeln!(
+					writer,
+					"INSERT INTO tags (group_id, key, value) VALUES ((SELECT max(id) FROM groups), {}, {});",
+					string(key),
+					string(value),
+				)?;
+			}
+		}
+
+		for dup_error in &self.errors {
+			writeln!(
+				writer,
+				"INSERT INTO errors (scan_id, message) VALUES ((SELECT max(id) FROM scans), {});",
+				string(&dup_error.to_string()),
+			)?;
+		}
+
+		writeln!(writer, "COMMIT;")
+	}
+}
+
+/// Returns a string as a quoted SQL string.
+fn string(s: &str) -> String {
+	format!("'{}'", s.replace('\'', "''"))
+}
+
+/// Returns a path as a quoted SQL string, or as a blob of its bytes if it
+/// isn't valid UTF-8.
+fn path(file: &Path) -> String {
+	match file.to_str() {
+		Some(s) => string(s),
+		None => {
+			let bytes = file.as_os_str().as_encoded_bytes();
+			let hex: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+			format!("X'{}'", hex)
+		}
+	}
+}
+
+/// Returns the size in a file's recorded metadata as an SQL value, or `NULL`
+/// if none was recorded.
+fn size(metadata: Option<&FileMetadata>) -> String {
+	match metadata {
+		Some(metadata) => metadata.size().to_string(),
+		None => String::from("NULL"),
+	}
+}
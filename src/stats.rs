@@ -0,0 +1,434 @@
+use crate::paths::FilePath;
+use crate::utilities::file_id;
+use crate::DupResults;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// A summary of the duplicate files with a particular extension.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ExtensionStats {
+	/// The lowercase file extension, without a leading `.`, or an empty
+	/// string for files without an extension.
+	extension: String,
+
+	/// The number of files in duplicate groups with this extension.
+	files: usize,
+
+	/// The number of redundant copies with this extension.
+	redundant_files: usize,
+
+	/// The total size of the redundant copies with this extension.
+	redundant_bytes: u64,
+}
+
+impl ExtensionStats {
+	/// Returns the lowercase file extension, without a leading `.`, or an
+	/// empty string for files without an extension.
+	pub fn extension(&self) -> &str {
+		&self.extension
+	}
+
+	/// Returns the number of files in duplicate groups with this extension.
+	pub fn files(&self) -> usize {
+		self.files
+	}
+
+	/// Returns the number of redundant copies with this extension, which is
+	/// every file in a group other than the first and any hard links to
+	/// files already counted.
+	pub fn redundant_files(&self) -> usize {
+		self.redundant_files
+	}
+
+	/// Returns the total size of the redundant copies with this extension,
+	/// which is the space that could be reclaimed by removing them.
+	pub fn redundant_bytes(&self) -> u64 {
+		self.redundant_bytes
+	}
+}
+
+/// A summary of the duplicate files owned by a particular user.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct OwnerStats {
+	/// The user ID.
+	uid: u32,
+
+	/// The number of files in duplicate groups owned by the user.
+	files: usize,
+
+	/// The number of redundant copies owned by the user.
+	redundant_files: usize,
+
+	/// The total size of the redundant copies owned by the user.
+	redundant_bytes: u64,
+}
+
+impl OwnerStats {
+	/// Returns the user ID.
+	pub fn uid(&self) -> u32 {
+		self.uid
+	}
+
+	/// Returns the number of files in duplicate groups owned by the user.
+	pub fn files(&self) -> usize {
+		self.files
+	}
+
+	/// Returns the number of redundant copies owned by the user, counted as
+	/// for `ExtensionStats::redundant_files()`.
+	pub fn redundant_files(&self) -> usize {
+		self.redundant_files
+	}
+
+	/// Returns the total size of the redundant copies owned by the user.
+	pub fn redundant_bytes(&self) -> u64 {
+		self.redundant_bytes
+	}
+}
+
+/// A summary of the files and duplicates within one of the directories
+/// checked by `DupResults::within()`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RootStats {
+	/// The directory.
+	root: PathBuf,
+
+	/// The number of files found in the directory.
+	files: usize,
+
+	/// The number of files in the directory that are in duplicate groups.
+	duplicate_files: usize,
+
+	/// The number of redundant copies in the directory.
+	redundant_files: usize,
+
+	/// The total size of the redundant copies in the directory.
+	redundant_bytes: u64,
+
+	/// The number of duplicate files in the directory that have a copy in
+	/// another of the directories.
+	cross_root_files: usize,
+}
+
+impl RootStats {
+	/// Returns the directory.
+	pub fn root(&self) -> &Path {
+		&self.root
+	}
+
+	/// Returns the number of files found in the directory, including files
+	/// without duplicates.
+	pub fn files(&self) -> usize {
+		self.files
+	}
+
+	/// Returns the number of files in the directory that are in duplicate
+	/// groups.
+	pub fn duplicate_files(&self) -> usize {
+		self.duplicate_files
+	}
+
+	/// Returns the number of redundant copies in the directory, counted as
+	/// for `ExtensionStats::redundant_files()`.
+	pub fn redundant_files(&self) -> usize {
+		self.redundant_files
+	}
+
+	/// Returns the total size of the redundant copies in the directory.
+	pub fn redundant_bytes(&self) -> u64 {
+		self.redundant_bytes
+	}
+
+	/// Returns the number of duplicate files in the directory that are in a
+	/// group with a file in another of the directories, which shows how much
+	/// the directories overlap.
+	pub fn cross_root_files(&self) -> usize {
+		self.cross_root_files
+	}
+}
+
+impl DupResults {
+	/// Returns a summary of the duplicate files by extension, ordered from
+	/// the most redundant bytes to the least.
+	///
+	/// The first file in each group is treated as the copy to keep, and
+	/// files that are hard links to a file already counted aren't counted as
+	/// redundant.  Files whose metadata can't be read are counted as files
+	/// but not as redundant copies.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use std::path::PathBuf;
+	///
+	/// let dirs = vec![PathBuf::from("foo")];
+	/// let mut dup_result = dupcheck::DupResults::new();
+	///
+	/// if let Err(dup_error) = dup_result.within(&dirs) {
+	///     // Error handling
+	/// }
+	///
+	/// for stats in dup_result.extension_stats() {
+	///     println!("{}: {} bytes", stats.extension(), stats.redundant_bytes());
+	/// }
+	/// ```
+	pub fn extension_stats(&self) -> Vec<ExtensionStats> {
+		let mut stats: HashMap<String, ExtensionStats> = HashMap::new();
+
+		for group in &self.duplicates {
+			let mut ids = vec![];
+			let mut kept = false;
+
+			for file in &group.files {
+				let extension = file
+					.extension()
+					.map(|e| e.to_string_lossy().to_lowercase())
+					.unwrap_or_default();
+				let entry = stats
+					.entry(extension.clone())
+					.or_insert_with(|| ExtensionStats {
+						extension,
+						..ExtensionStats::default()
+					});
+				entry.files += 1;
+
+				let metadata = match file.metadata() {
+					Ok(m) => m,
+					Err(_) => continue,
+				};
+
+				match file_id(&metadata) {
+					Some(id) if ids.contains(&id) => continue,
+					Some(id) => ids.push(id),
+					None => {}
+				};
+
+				if !kept {
+					kept = true;
+					continue;
+				}
+
+				entry.redundant_files += 1;
+				entry.redundant_bytes += metadata.len();
+			}
+		}
+
+		let mut stats: Vec<ExtensionStats> = stats.into_values().collect();
+		stats.sort_by(|a, b| {
+			b.redundant_bytes
+				.cmp(&a.redundant_bytes)
+				.then_with(|| a.extension.cmp(&b.extension))
+		});
+
+		stats
+	}
+
+	/// Returns a summary of the duplicate files by the user that owns them,
+	/// ordered from the most redundant bytes to the least.
+	///
+	/// This requires `DupOptions::record_ownership()` to have been enabled
+	/// for the checks; otherwise, it will be empty.  Redundant copies are
+	/// counted as for `extension_stats()`, using the metadata recorded when
+	/// the files were found.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use std::path::PathBuf;
+	///
+	/// let mut options = dupcheck::DupOptions::new();
+	/// options.set_record_ownership(true);
+	///
+	/// let mut dup_result = dupcheck::DupResults::with_options(options);
+	/// let dirs = vec![PathBuf::from("foo")];
+	///
+	/// if let Err(dup_error) = dup_result.within(&dirs) {
+	///     // Error handling
+	/// }
+	///
+	/// for stats in dup_result.owner_stats() {
+	///     println!("uid {}: {} bytes", stats.uid(), stats.redundant_bytes());
+	/// }
+	/// ```
+	pub fn owner_stats(&self) -> Vec<OwnerStats> {
+		let mut stats: HashMap<u32, OwnerStats> = HashMap::new();
+
+		for group in &self.duplicates {
+			let mut ids = vec![];
+			let mut kept = false;
+
+			for (i, file) in group.files.iter().enumerate() {
+				let metadata = match group.metadata_at(i) {
+					Some(m) => m,
+					None => continue,
+				};
+				let uid = match metadata.uid() {
+					Some(uid) => uid,
+					None => continue,
+				};
+				let entry = stats.entry(uid).or_insert_with(|| OwnerStats {
+					uid,
+					..OwnerStats::default()
+				});
+				entry.files += 1;
+
+				match file.metadata().ok().as_ref().and_then(file_id) {
+					Some(id) if ids.contains(&id) => continue,
+					Some(id) => ids.push(id),
+					None => {}
+				};
+
+				if !kept {
+					kept = true;
+					continue;
+				}
+
+				entry.redundant_files += 1;
+				entry.redundant_bytes += metadata.size();
+			}
+		}
+
+		let mut stats: Vec<OwnerStats> = stats.into_values().collect();
+		stats.sort_by(|a, b| {
+			b.redundant_bytes
+				.cmp(&a.redundant_bytes)
+				.then_with(|| a.uid.cmp(&b.uid))
+		});
+
+		stats
+	}
+
+	/// Returns a summary of the files and duplicates within each directory
+	/// checked by `within()`, in the order the directories were given.
+	///
+	/// Redundant copies are counted as for `extension_stats()`.  Files in
+	/// more than one of the directories, such as when one directory is
+	/// within another, are counted in the first of them.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use std::path::PathBuf;
+	///
+	/// let dirs = vec![PathBuf::from("foo"), PathBuf::from("bar")];
+	/// let mut dup_result = dupcheck::DupResults::new();
+	///
+	/// if let Err(dup_error) = dup_result.within(&dirs) {
+	///     // Error handling
+	/// }
+	///
+	/// for stats in dup_result.root_stats() {
+	///     println!(
+	///         "{}: {} files also in another directory",
+	///         stats.root().display(),
+	///         stats.cross_root_files()
+	///     );
+	/// }
+	/// ```
+	pub fn root_stats(&self) -> Vec<RootStats> {
+		let mut stats: Vec<RootStats> = self
+			.roots
+			.iter()
+			.map(|(root, files)| RootStats {
+				root: root.to_path_buf(),
+				files: *files,
+				..RootStats::default()
+			})
+			.collect();
+
+		for group in &self.duplicates {
+			let roots: Vec<Option<usize>> = group.files.iter().map(|f| self.root_of(f)).collect();
+			let cross_root = roots.iter().flatten().any(|r| Some(r) != roots[0].as_ref());
+			let mut ids = vec![];
+			let mut kept = false;
+
+			for (file, root) in group.files.iter().zip(&roots) {
+				if let Some(r) = root {
+					stats[*r].duplicate_files += 1;
+
+					if cross_root {
+						stats[*r].cross_root_files += 1;
+					}
+				}
+
+				let metadata = match file.metadata() {
+					Ok(m) => m,
+					Err(_) => continue,
+				};
+
+				match file_id(&metadata) {
+					Some(id) if ids.contains(&id) => continue,
+					Some(id) => ids.push(id),
+					None => {}
+				};
+
+				if !kept {
+					kept = true;
+					continue;
+				}
+
+				if let Some(r) = root {
+					stats[*r].redundant_files += 1;
+					stats[*r].redundant_bytes += metadata.len();
+				}
+			}
+		}
+
+		stats
+	}
+
+	/// Records the directories checked by `within()` and the number of the
+	/// given files found in each, replacing the counts for any directories
+	/// checked before.
+	pub(crate) fn add_roots(&mut self, dirs: Vec<PathBuf>, files: &[FilePath]) {
+		let mut indexes = vec![];
+
+		for dir in dirs {
+			let i = match self.roots.iter().position(|(root, _)| **root == *dir) {
+				Some(i) => i,
+				None => {
+					self.roots.push((Arc::from(dir), 0));
+					self.roots.len() - 1
+				}
+			};
+			self.roots[i].1 = 0;
+			indexes.push(i);
+		}
+
+		for file in files {
+			match self.root_of(&file.to_path_buf()) {
+				Some(i) if indexes.contains(&i) => self.roots[i].1 += 1,
+				_ => {}
+			};
+		}
+	}
+
+	/// Records the directory checked by `within()` that each file in the
+	/// group at the given index was found in, for files not already
+	/// recorded.
+	pub(crate) fn set_roots(&mut self, i: usize) {
+		let group = &self.duplicates[i];
+		let roots: Vec<(usize, Arc<Path>)> = (0..group.files.len())
+			.filter(|j| group.root_at(*j).is_none())
+			.filter_map(|j| {
+				self
+					.root_of(&group.files[j])
+					.map(|r| (j, Arc::clone(&self.roots[r].0)))
+			})
+			.collect();
+
+		for (j, root) in roots {
+			self.duplicates[i].set_root(j, root);
+		}
+	}
+
+	/// Returns the index of the first directory checked by `within()` that
+	/// contains the given file, if any.
+	fn root_of(&self, file: &Path) -> Option<usize> {
+		self
+			.roots
+			.iter()
+			.position(|(root, _)| file.starts_with(root))
+	}
+}
@@ -0,0 +1,62 @@
+use crate::DupGroup;
+use std::collections::BTreeMap;
+
+/// The tag that a group's note is kept in.
+const NOTE_TAG: &str = "note";
+
+impl DupGroup {
+	/// Returns a reference to the group's tags, ordered by key.
+	///
+	/// Tags are key/value pairs attached to a group, such as while reviewing
+	/// it, and are written along with the group by
+	/// `DupResults::write_json()` and `DupResults::write_sql()`.  The group's
+	/// note, if any, is the `note` tag.
+	pub fn tags(&self) -> &BTreeMap<String, String> {
+		&self.tags
+	}
+
+	/// Returns the value of the tag with the given key, if any.
+	pub fn tag(&self, key: &str) -> Option<&str> {
+		self.tags.get(key).map(String::as_str)
+	}
+
+	/// Sets the value of the tag with the given key, replacing any value it
+	/// already had.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use std::path::PathBuf;
+	///
+	/// let dirs = vec![PathBuf::from("foo")];
+	/// let mut dup_result = dupcheck::DupResults::new();
+	///
+	/// if let Err(dup_error) = dup_result.within(&dirs) {
+	///     // Error handling
+	/// }
+	///
+	/// if let Some(group) = dup_result.group_by_hash_mut("af1349b9f5f9a1a6a0404dea36dcc9499bcb25c9adc112b7cc9a93cae41f3262") {
+	///     group.set_tag("status", "needs owner review");
+	///     group.set_note("Ask the design team which copy they use.");
+	/// }
+	/// ```
+	pub fn set_tag<K: Into<String>, V: Into<String>>(&mut self, key: K, value: V) {
+		self.tags.insert(key.into(), value.into());
+	}
+
+	/// Removes the tag with the given key, returning its value if it had
+	/// one.
+	pub fn remove_tag(&mut self, key: &str) -> Option<String> {
+		self.tags.remove(key)
+	}
+
+	/// Returns the group's note, if any.
+	pub fn note(&self) -> Option<&str> {
+		self.tag(NOTE_TAG)
+	}
+
+	/// Sets the group's note, replacing any note it already had.
+	pub fn set_note<N: Into<String>>(&mut self, note: N) {
+		self.set_tag(NOTE_TAG, note);
+	}
+}
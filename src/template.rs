@@ -0,0 +1,211 @@
+use crate::utilities::format_timestamp;
+use crate::{DupGroup, DupResults};
+use std::io;
+use std::io::Write;
+use std::str::FromStr;
+
+/// A template for the line written for each duplicate file by
+/// `DupResults::write_template()`.
+///
+/// Fields in braces are replaced with the file's details:
+///
+/// - `{path}`: the file's path
+/// - `{hash}`: its group's hash, as given by `DupGroup::get_hash()`
+/// - `{size}`: its size in bytes
+/// - `{modified}`: its modification time, as an ISO 8601 date and time in
+///   UTC
+/// - `{root}`: the directory it was found in, as given by `DupGroup::root()`
+/// - `{group}`: the number of its group in the results, starting from 1
+/// - `{count}`: the number of files in its group
+///
+/// Fields that aren't known for a file are left empty.  `{{` and `}}` are
+/// written as braces, and `\t`, `\n` and `\\` as a tab, a newline and a
+/// backslash, so templates given on the command line can contain them.
+///
+/// # Examples
+///
+/// ```
+/// let template: dupcheck::Template = "{hash}\\t{size}\\t{path}".parse().unwrap();
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Template {
+	/// The text and fields of the template, in order.
+	parts: Vec<Part>,
+}
+
+/// A piece of a `Template`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Part {
+	/// Text written as it is.
+	Text(String),
+
+	/// A field replaced with a file's details.
+	Field(Field),
+}
+
+/// A file detail that can be used in a `Template`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Field {
+	Path,
+	Hash,
+	Size,
+	Modified,
+	Root,
+	Group,
+	Count,
+}
+
+impl FromStr for Field {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Field, String> {
+		match s {
+			"path" => Ok(Field::Path),
+			"hash" => Ok(Field::Hash),
+			"size" => Ok(Field::Size),
+			"modified" => Ok(Field::Modified),
+			"root" => Ok(Field::Root),
+			"group" => Ok(Field::Group),
+			"count" => Ok(Field::Count),
+			_ => Err(format!("unknown template field {}", s)),
+		}
+	}
+}
+
+impl FromStr for Template {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Template, String> {
+		let mut parts = vec![];
+		let mut text = String::new();
+		let mut chars = s.chars().peekable();
+
+		while let Some(c) = chars.next() {
+			match c {
+				'\\' => match chars.next() {
+					Some('t') => text.push('\t'),
+					Some('n') => text.push('\n'),
+					Some('\\') => text.push('\\'),
+					Some(other) => return Err(format!("unknown escape \\{} in template", other)),
+					None => return Err(String::from("template ends with \\")),
+				},
+				'{' if chars.peek() == Some(&'{') => {
+					chars.next();
+					text.push('{');
+				}
+				'{' => {
+					let mut name = String::new();
+
+					loop {
+						match chars.next() {
+							Some('}') => break,
+							Some(c) => name.push(c),
+							None => return Err(String::from("unmatched { in template; use {{ for a brace")),
+						};
+					}
+
+					if !text.is_empty() {
+						parts.push(Part::Text(std::mem::take(&mut text)));
+					}
+
+					parts.push(Part::Field(name.parse()?));
+				}
+				'}' => match chars.next() {
+					Some('}') => text.push('}'),
+					_ => return Err(String::from("unmatched } in template; use }} for a brace")),
+				},
+				c => text.push(c),
+			};
+		}
+
+		if !text.is_empty() {
+			parts.push(Part::Text(text));
+		}
+
+		Ok(Template { parts })
+	}
+}
+
+impl Template {
+	/// Returns the line for the file at the given index in a group, which is
+	/// the `number`th group in the results.
+	fn render(&self, group: &DupGroup, number: usize, i: usize) -> String {
+		let mut line = String::new();
+
+		for part in &self.parts {
+			match part {
+				Part::Text(text) => line.push_str(text),
+				Part::Field(field) => line.push_str(&field_value(*field, group, number, i)),
+			};
+		}
+
+		line
+	}
+}
+
+impl DupResults {
+	/// Writes a line for each file in each duplicate file group to the given
+	/// writer, formatted by the given template, so the results can be shaped
+	/// for other programs without being parsed first.
+	///
+	/// # Errors
+	///
+	/// Returns an error if writing fails.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use std::path::PathBuf;
+	///
+	/// let dirs = vec![PathBuf::from("foo")];
+	/// let mut dup_result = dupcheck::DupResults::new();
+	///
+	/// if let Err(dup_error) = dup_result.within(&dirs) {
+	///     // Error handling
+	/// }
+	///
+	/// let template = "{group}: {path} ({size} bytes)".parse().unwrap();
+	///
+	/// if let Err(write_error) = dup_result.write_template(&mut std::io::stdout(), &template) {
+	///     // Error handling
+	/// }
+	/// ```
+	pub fn write_template<W: Write>(&self, writer: &mut W, template: &Template) -> io::Result<()> {
+		for (i, group) in self.duplicates.iter().enumerate() {
+			for j in 0..group.files.len() {
+				writeln!(writer, "{}", template.render(group, i + 1, j))?;
+			}
+		}
+
+		Ok(())
+	}
+}
+
+/// Returns the value of a template field for the file at the given index in
+/// a group, or an empty string if it isn't known.
+fn field_value(field: Field, group: &DupGroup, number: usize, i: usize) -> String {
+	let file = &group.files[i];
+
+	// Files' metadata is read again for groups that don't have it, such as
+	// groups imported from other tools.
+	let size = || match group.metadata_at(i) {
+		Some(metadata) => Some(metadata.size()),
+		None => file.metadata().ok().map(|m| m.len()),
+	};
+	let modified = || match group.metadata_at(i) {
+		Some(metadata) => metadata.modified(),
+		None => file.metadata().and_then(|m| m.modified()).ok(),
+	};
+
+	match field {
+		Field::Path => file.to_string_lossy().into_owned(),
+		Field::Hash => group.hash.clone(),
+		Field::Size => size().map_or(String::new(), |s| s.to_string()),
+		Field::Modified => modified().map_or(String::new(), format_timestamp),
+		Field::Root => group
+			.root_at(i)
+			.map_or(String::new(), |r| r.to_string_lossy().into_owned()),
+		Field::Group => number.to_string(),
+		Field::Count => group.files.len().to_string(),
+	}
+}
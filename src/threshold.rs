@@ -0,0 +1,29 @@
+use crate::DupResults;
+
+impl DupResults {
+	/// Removes the group at the given index if it has fewer files, including
+	/// any queried files, than `DupOptions::min_group_size()`, or fewer
+	/// reclaimable bytes than `DupOptions::min_redundant_bytes()`.  Returns
+	/// whether the group is still in the results.
+	pub(crate) fn check_thresholds(&mut self, i: usize) -> bool {
+		let min_files = self.options.min_group_size();
+		let min_bytes = self.options.min_redundant_bytes();
+
+		if min_files == 0 && min_bytes == 0 {
+			return true;
+		}
+
+		let group = &self.duplicates[i];
+
+		// Reclaimable bytes are only read if the group has enough files, as
+		// reading them needs the metadata of each file.
+		if group.files.len() + group.queried.len() < min_files
+			|| (min_bytes > 0 && group.reclaimable_bytes() < min_bytes)
+		{
+			self.duplicates.remove(i);
+			return false;
+		}
+
+		true
+	}
+}
@@ -0,0 +1,144 @@
+use std::io;
+use std::path::Path;
+
+/// Moves a file to the trash in the user's home directory, as described by
+/// the freedesktop.org trash specification, recording where it came from so
+/// it can be restored by file managers.
+#[cfg(all(unix, not(any(target_os = "macos", target_os = "ios"))))]
+pub(crate) fn trash(file: &Path) -> io::Result<()> {
+	use crate::utilities::{absolute, format_timestamp, move_file};
+	use std::env;
+	use std::fs;
+	use std::io::Write;
+	use std::os::unix::ffi::OsStrExt;
+	use std::path::PathBuf;
+	use std::time::SystemTime;
+
+	let data_home = match env::var_os("XDG_DATA_HOME").filter(|d| !d.is_empty()) {
+		Some(dir) => PathBuf::from(dir),
+		None => match env::var_os("HOME") {
+			Some(home) => PathBuf::from(home).join(".local/share"),
+			None => return Err(io::Error::new(io::ErrorKind::NotFound, "no home directory")),
+		},
+	};
+	let trash_dir = data_home.join("Trash");
+	let files_dir = trash_dir.join("files");
+	let info_dir = trash_dir.join("info");
+	fs::create_dir_all(&files_dir)?;
+	fs::create_dir_all(&info_dir)?;
+
+	let path = absolute(file);
+	let name = path.file_name().unwrap_or_default().to_os_string();
+	let mut encoded = String::new();
+
+	for byte in path.as_os_str().as_bytes() {
+		match byte {
+			b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => {
+				encoded.push(*byte as char)
+			}
+			_ => encoded.push_str(&format!("%{:02X}", byte)),
+		};
+	}
+
+	// The info file is created first to claim a name in the trash that no
+	// other file is using.
+	let mut n = 1;
+
+	loop {
+		let mut trash_name = name.clone();
+
+		if n > 1 {
+			trash_name.push(format!(".{}", n));
+		}
+
+		let mut info_name = trash_name.clone();
+		info_name.push(".trashinfo");
+		let info_path = info_dir.join(info_name);
+		let mut info = match fs::OpenOptions::new()
+			.write(true)
+			.create_new(true)
+			.open(&info_path)
+		{
+			Ok(f) => f,
+			Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+				n += 1;
+				continue;
+			}
+			Err(e) => return Err(e),
+		};
+
+		let written = write!(
+			info,
+			"[Trash Info]\nPath={}\nDeletionDate={}\n",
+			encoded,
+			format_timestamp(SystemTime::now())
+		);
+		let moved = written.and_then(|_| move_file(&path, &files_dir.join(&trash_name)));
+
+		if moved.is_err() {
+			let _ = fs::remove_file(&info_path);
+		}
+
+		return moved;
+	}
+}
+
+/// Moves a file to the trash in the user's home directory.
+#[cfg(any(target_os = "macos", target_os = "ios"))]
+pub(crate) fn trash(file: &Path) -> io::Result<()> {
+	use crate::utilities::move_file;
+	use std::env;
+	use std::path::PathBuf;
+
+	let trash_dir = match env::var_os("HOME") {
+		Some(home) => PathBuf::from(home).join(".Trash"),
+		None => return Err(io::Error::new(io::ErrorKind::NotFound, "no home directory")),
+	};
+	let name = file.file_name().unwrap_or_default();
+	let mut to = trash_dir.join(name);
+	let mut n = 1;
+
+	while to.symlink_metadata().is_ok() {
+		n += 1;
+		let mut numbered = name.to_os_string();
+		numbered.push(format!(" {}", n));
+		to = trash_dir.join(numbered);
+	}
+
+	move_file(file, &to)
+}
+
+/// Moves a file to the recycle bin, using PowerShell.
+#[cfg(windows)]
+pub(crate) fn trash(file: &Path) -> io::Result<()> {
+	use crate::utilities::long_path;
+	use std::process::Command;
+
+	// The path is passed in an environment variable rather than in the
+	// command, so no file name can be read as part of the command.
+	let status = Command::new("powershell")
+		.args(["-NoProfile", "-NonInteractive", "-Command"])
+		.arg(
+			"Add-Type -AssemblyName Microsoft.VisualBasic; \
+			[Microsoft.VisualBasic.FileIO.FileSystem]::DeleteFile($env:DUPCHECK_TRASH_PATH, 'OnlyErrorDialogs', 'SendToRecycleBin')",
+		)
+		.env("DUPCHECK_TRASH_PATH", long_path(file))
+		.status()?;
+
+	if !status.success() {
+		return Err(io::Error::other(
+			"couldn't move the file to the recycle bin",
+		));
+	}
+
+	Ok(())
+}
+
+/// Moving files to the trash isn't supported on this platform.
+#[cfg(not(any(unix, windows)))]
+pub(crate) fn trash(_file: &Path) -> io::Result<()> {
+	Err(io::Error::new(
+		io::ErrorKind::Unsupported,
+		"moving files to the trash isn't supported on this platform",
+	))
+}
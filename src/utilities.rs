@@ -1,71 +1,302 @@
 use crate::duperror::DupError;
+use crate::hash::hash_file;
+use crate::options::DupOptions;
+use crate::walker::WalkVisitor;
+use std::collections::HashSet;
 use std::fs;
+use std::fs::{File, Metadata};
 use std::io;
-use std::path::PathBuf;
+use std::io::Read;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 pub(crate) trait PathUtilities {
 	/// Returns a file's BLAKE3 hash.
 	fn blake3(&self) -> io::Result<String>;
 
-	/// Returns all files within a directory, optionally of certain `sizes`.
-	fn files_within(&self, sizes: Option<&[u64]>) -> (Vec<PathBuf>, Vec<DupError>);
+	/// Returns the BLAKE3 hash of the first `len` bytes of a file.
+	fn blake3_prefix(&self, len: u64) -> io::Result<String>;
+
+	/// Returns whether a file's contents are the same as another file's,
+	/// reading them into the given buffers, which must be the same size.
+	fn same_contents(&self, other: &Path, buf: &mut [u8], other_buf: &mut [u8]) -> io::Result<bool>;
+
+	/// Returns all files within a directory, optionally of certain `sizes`,
+	/// retrying failed reads and skipping files according to `options`.
+	/// Directories whose device and inode numbers are in `searched` are
+	/// skipped, and those of the directories searched are added to it.
+	fn files_within(
+		&self,
+		sizes: Option<&[u64]>,
+		options: &DupOptions,
+		searched: &mut HashSet<(u64, u64)>,
+	) -> (Vec<PathBuf>, Vec<DupError>);
+
+	/// Passes each file within a directory to `found` as it's found by the
+	/// walker set in `options`, skipping files in the same way as
+	/// `files_within()`, and returns any errors encountered.
+	fn walk_files(
+		&self,
+		sizes: Option<&[u64]>,
+		options: &DupOptions,
+		searched: &mut HashSet<(u64, u64)>,
+		found: &(dyn Fn(PathBuf) + Sync),
+	) -> Vec<DupError>;
 }
 
 impl PathUtilities for PathBuf {
 	fn blake3(&self) -> io::Result<String> {
-		let bytes = fs::read(self.as_path())?;
+		hash_file(self)
+	}
+
+	fn blake3_prefix(&self, len: u64) -> io::Result<String> {
+		let mut bytes = vec![];
+		File::open(self.as_path())?
+			.take(len)
+			.read_to_end(&mut bytes)?;
 		Ok(format!("{}", blake3::hash(&bytes)))
 	}
 
-	fn files_within(&self, sizes: Option<&[u64]>) -> (Vec<PathBuf>, Vec<DupError>) {
-		let read_dir = match self.read_dir() {
-			Ok(entries) => entries,
-			Err(e) => return (vec![], vec![DupError::new(self.to_path_buf(), e)]),
-		};
+	fn same_contents(&self, other: &Path, buf: &mut [u8], other_buf: &mut [u8]) -> io::Result<bool> {
+		let mut file = File::open(self.as_path())?;
+		let mut other_file = File::open(other)?;
 
-		let mut files = vec![];
-		let mut errors = vec![];
-		let sizes_vec = match sizes {
-			Some(sizes_slice) => Vec::from(sizes_slice),
-			None => vec![],
-		};
+		loop {
+			let read = file.read(buf)?;
+
+			if read == 0 {
+				return Ok(other_file.read(other_buf)? == 0);
+			}
 
-		for entry in read_dir {
-			let entry_path = match entry {
-				Ok(ent) => ent.path(),
-				Err(e) => {
-					errors.push(DupError::new(self.to_path_buf(), e));
-					continue;
-				}
+			match other_file.read_exact(&mut other_buf[..read]) {
+				Ok(()) => {}
+				Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(false),
+				Err(e) => return Err(e),
 			};
 
-			if entry_path.is_file() {
-				let metadata = match entry_path.metadata() {
-					Ok(md) => md,
-					Err(e) => {
-						errors.push(DupError::new(entry_path, e));
-						continue;
-					}
-				};
-
-				let size = metadata.len();
-
-				if sizes.is_none() || sizes_vec.contains(&size) {
-					files.push(entry_path);
-				}
-			} else if entry_path.is_dir() {
-				let (mut sub_files, mut sub_errors) = entry_path.files_within(sizes);
-
-				if !sub_files.is_empty() {
-					files.append(&mut sub_files);
-				}
-
-				if !sub_errors.is_empty() {
-					errors.append(&mut sub_errors);
-				}
+			if buf[..read] != other_buf[..read] {
+				return Ok(false);
 			}
 		}
+	}
+
+	fn files_within(
+		&self,
+		sizes: Option<&[u64]>,
+		options: &DupOptions,
+		searched: &mut HashSet<(u64, u64)>,
+	) -> (Vec<PathBuf>, Vec<DupError>) {
+		let files = Mutex::new(vec![]);
+		let errors = self.walk_files(sizes, options, searched, &|f| {
+			files.lock().unwrap_or_else(|e| e.into_inner()).push(f)
+		});
+
+		(
+			files.into_inner().unwrap_or_else(|e| e.into_inner()),
+			errors,
+		)
+	}
+
+	fn walk_files(
+		&self,
+		sizes: Option<&[u64]>,
+		options: &DupOptions,
+		searched: &mut HashSet<(u64, u64)>,
+		found: &(dyn Fn(PathBuf) + Sync),
+	) -> Vec<DupError> {
+		let visitor = WalkVisitor::new(self, options, sizes, searched, found);
+		options.walker().walk(self, &visitor);
+
+		visitor.into_errors()
+	}
+}
+
+/// Returns whether a file is one created by an operating system or file
+/// manager to hold folder settings, thumbnails or extended attributes, such
+/// as `.DS_Store`, `Thumbs.db`, `desktop.ini` or an AppleDouble `._*` file.
+pub(crate) fn is_junk(file: &Path) -> bool {
+	let name = file.file_name().unwrap_or_default().to_string_lossy();
+
+	name.starts_with("._")
+		|| [".DS_Store", "Thumbs.db", "desktop.ini"]
+			.iter()
+			.any(|junk| name.eq_ignore_ascii_case(junk))
+}
+
+/// Returns the device and inode numbers of a file.
+#[cfg(unix)]
+pub(crate) fn file_id(metadata: &Metadata) -> Option<(u64, u64)> {
+	use std::os::unix::fs::MetadataExt;
+
+	Some((metadata.dev(), metadata.ino()))
+}
+
+/// Returns the device and inode numbers of a file, which aren't available on
+/// this platform.
+#[cfg(not(unix))]
+pub(crate) fn file_id(_metadata: &Metadata) -> Option<(u64, u64)> {
+	None
+}
+
+/// Returns a path made absolute without resolving symbolic links, or the
+/// path as it is if that isn't possible.
+pub(crate) fn absolute(path: &Path) -> PathBuf {
+	std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// Returns a path in a form that other programs can open on Windows if it's
+/// longer than the `MAX_PATH` limit: made absolute and, if it's too long,
+/// given the `\\?\` prefix that lifts the limit.  The standard library
+/// already does this for its own file operations, so this is only needed for
+/// paths given to other programs.
+#[cfg(windows)]
+pub(crate) fn long_path(path: &Path) -> PathBuf {
+	use std::ffi::OsString;
+	use std::os::windows::ffi::{OsStrExt, OsStringExt};
+	use std::path::Prefix;
 
-		(files, errors)
+	// Directories are limited to 12 fewer characters than files, to leave
+	// room for 8.3 file names within them.
+	const MAX_DIR_PATH: usize = 248;
+
+	let path = absolute(path);
+	let wide: Vec<u16> = path.as_os_str().encode_wide().collect();
+
+	if wide.len() < MAX_DIR_PATH {
+		return path;
 	}
+
+	let unc = match path.components().next() {
+		Some(Component::Prefix(prefix)) => match prefix.kind() {
+			Prefix::Disk(_) => false,
+			Prefix::UNC(..) => true,
+			_ => return path,
+		},
+		_ => return path,
+	};
+
+	// UNC paths keep one of their two leading backslashes after the prefix.
+	let mut long = match unc {
+		true => OsString::from(r"\\?\UNC"),
+		false => OsString::from(r"\\?\"),
+	};
+	long.push(match unc {
+		true => OsString::from_wide(&wide[1..]),
+		false => path.into_os_string(),
+	});
+
+	PathBuf::from(long)
+}
+
+/// Returns a path as it is, as paths aren't limited in length on this
+/// platform.
+#[cfg(not(windows))]
+pub(crate) fn long_path(path: &Path) -> PathBuf {
+	path.to_path_buf()
+}
+
+/// Returns the relative path from the directory `from` to `to`, both of
+/// which should be absolute.  This is worked out from the paths alone, so
+/// any symbolic links within `from` aren't accounted for.
+pub(crate) fn relative_path(from: &Path, to: &Path) -> PathBuf {
+	let from: Vec<_> = from.components().collect();
+	let to: Vec<_> = to.components().collect();
+	let common = from.iter().zip(&to).take_while(|(a, b)| a == b).count();
+	let mut path = PathBuf::new();
+
+	for _ in common..from.len() {
+		path.push("..");
+	}
+
+	for component in &to[common..] {
+		path.push(component);
+	}
+
+	path
+}
+
+/// Returns the path a file would be moved to within a quarantine directory,
+/// which is the file's absolute path with its root replaced by the
+/// directory.  Windows drive prefixes are kept as a directory, e.g.
+/// `C:\\foo.txt` becomes `<dir>\\C\\foo.txt`.
+pub(crate) fn quarantine_path(dir: &Path, file: &Path) -> PathBuf {
+	let mut path = dir.to_path_buf();
+
+	for component in absolute(file).components() {
+		match component {
+			Component::Prefix(prefix) => {
+				let prefix = prefix.as_os_str().to_string_lossy();
+				path.push(prefix.replace([':', '\\', '?'], ""));
+			}
+			Component::RootDir => {}
+			_ => path.push(component),
+		};
+	}
+
+	path
+}
+
+/// Moves a file to the given path, creating any missing parent directories
+/// and copying it if it can't be renamed across filesystems.
+///
+/// # Errors
+///
+/// Returns an error if a file already exists at the given path.
+pub(crate) fn move_file(file: &Path, to: &Path) -> io::Result<()> {
+	if to.symlink_metadata().is_ok() {
+		return Err(io::Error::new(
+			io::ErrorKind::AlreadyExists,
+			format!("{} already exists", to.display()),
+		));
+	}
+
+	if let Some(parent) = to.parent() {
+		fs::create_dir_all(parent)?;
+	}
+
+	if fs::rename(file, to).is_err() {
+		fs::copy(file, to)?;
+		fs::remove_file(file)?;
+	}
+
+	Ok(())
+}
+
+/// Returns a temporary path in the same directory as the given file.
+pub(crate) fn temp_path(file: &Path) -> PathBuf {
+	let mut name = std::ffi::OsString::from(".");
+	name.push(file.file_name().unwrap_or_default());
+	name.push(format!(".dupcheck-{}.tmp", std::process::id()));
+	file.with_file_name(name)
+}
+
+/// Returns a time formatted as an ISO 8601 date and time in UTC, e.g.
+/// `2022-05-31T09:30:00`.
+pub(crate) fn format_timestamp(time: SystemTime) -> String {
+	let secs = time.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+	let days = (secs / 86400) as i64;
+	let secs_of_day = secs % 86400;
+
+	// Converts days since the epoch to a civil date, using Howard Hinnant's
+	// `civil_from_days` algorithm.
+	let z = days + 719468;
+	let era = z.div_euclid(146097);
+	let doe = z.rem_euclid(146097);
+	let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+	let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+	let mp = (5 * doy + 2) / 153;
+	let day = doy - (153 * mp + 2) / 5 + 1;
+	let month = if mp < 10 { mp + 3 } else { mp - 9 };
+	let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+	format!(
+		"{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+		year,
+		month,
+		day,
+		secs_of_day / 3600,
+		secs_of_day % 3600 / 60,
+		secs_of_day % 60
+	)
 }
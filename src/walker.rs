@@ -0,0 +1,341 @@
+use crate::duperror::DupError;
+use crate::events::{ScanEvent, SkipReason};
+use crate::options::DupOptions;
+use crate::placeholder::is_placeholder;
+use crate::priority;
+use crate::progress::Progress;
+use crate::utilities::{file_id, is_junk};
+use std::collections::HashSet;
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Condvar, Mutex, MutexGuard};
+use std::thread;
+
+/// A way of finding the files within a directory and its subdirectories.
+///
+/// A walker only decides the order directories are searched in and which
+/// threads search them.  The `WalkVisitor` it's given reads directories and
+/// decides which files and directories are skipped, so every walker skips
+/// the same files and reports errors and events in the same way.
+///
+/// # Examples
+///
+/// ```
+/// use dupcheck::{WalkVisitor, Walker};
+/// use std::path::Path;
+///
+/// /// Searches directories breadth first.
+/// #[derive(Debug)]
+/// struct BreadthFirst;
+///
+/// impl Walker for BreadthFirst {
+///     fn walk(&self, dir: &Path, visitor: &WalkVisitor) {
+///         let mut dirs = vec![dir.to_path_buf()];
+///
+///         while !dirs.is_empty() {
+///             for dir in dirs.split_off(0) {
+///                 if visitor.enter(&dir) {
+///                     for entry in visitor.entries(&dir) {
+///                         if entry.is_file() {
+///                             visitor.file(entry);
+///                         } else if entry.is_dir() {
+///                             dirs.push(entry);
+///                         }
+///                     }
+///                 }
+///             }
+///         }
+///     }
+/// }
+///
+/// let mut options = dupcheck::DupOptions::new();
+/// options.set_walker(Some(std::sync::Arc::new(BreadthFirst)));
+/// ```
+pub trait Walker: fmt::Debug + Send + Sync {
+	/// Searches a directory and its subdirectories, passing each directory
+	/// to `visitor.enter()` before reading it with `visitor.entries()`, and
+	/// passing each file found to `visitor.file()`.  Directories that
+	/// `visitor.enter()` returns `false` for mustn't be read.
+	///
+	/// The visitor's methods may be called from any number of threads.
+	fn walk(&self, dir: &Path, visitor: &WalkVisitor);
+}
+
+/// The walkers built into dupcheck.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BuiltinWalker {
+	/// Searches each directory's subdirectories one at a time, in the order
+	/// the operating system lists them.  This is the default.
+	#[default]
+	Recursive,
+
+	/// Searches each directory's files and subdirectories one at a time in
+	/// order of their paths, so files are found in the same order on every
+	/// platform and filesystem.
+	Sorted,
+
+	/// Searches directories on as many threads as the system can run at
+	/// once, so files are found sooner on storage that handles many reads
+	/// at once, such as SSDs and network filesystems, but in no fixed order.
+	Parallel,
+}
+
+impl FromStr for BuiltinWalker {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<BuiltinWalker, String> {
+		match s {
+			"recursive" => Ok(BuiltinWalker::Recursive),
+			"sorted" => Ok(BuiltinWalker::Sorted),
+			"parallel" => Ok(BuiltinWalker::Parallel),
+			_ => Err(format!("unknown walker {}", s)),
+		}
+	}
+}
+
+impl Walker for BuiltinWalker {
+	fn walk(&self, dir: &Path, visitor: &WalkVisitor) {
+		match self {
+			BuiltinWalker::Recursive => walk_recursive(dir, visitor, false),
+			BuiltinWalker::Sorted => walk_recursive(dir, visitor, true),
+			BuiltinWalker::Parallel => walk_parallel(dir, visitor),
+		};
+	}
+}
+
+/// Reads directories for a `Walker` and decides which of their files and
+/// subdirectories are checked, according to the check's options.
+pub struct WalkVisitor<'a> {
+	/// The directory being searched, which is never skipped as excluded.
+	root: &'a Path,
+
+	/// The options of the check.
+	options: &'a DupOptions,
+
+	/// The sizes of files to find, or `None` to find files of any size.
+	sizes: Option<&'a [u64]>,
+
+	/// The device and inode numbers of the directories already searched.
+	searched: Mutex<&'a mut HashSet<(u64, u64)>>,
+
+	/// The errors found while searching.
+	errors: Mutex<Vec<DupError>>,
+
+	/// The function each file found is passed to.
+	found: &'a (dyn Fn(PathBuf) + Sync),
+}
+
+impl<'a> WalkVisitor<'a> {
+	/// Creates a new `WalkVisitor` for searching the given directory.
+	pub(crate) fn new(
+		root: &'a Path,
+		options: &'a DupOptions,
+		sizes: Option<&'a [u64]>,
+		searched: &'a mut HashSet<(u64, u64)>,
+		found: &'a (dyn Fn(PathBuf) + Sync),
+	) -> WalkVisitor<'a> {
+		WalkVisitor {
+			root,
+			options,
+			sizes,
+			searched: Mutex::new(searched),
+			errors: Mutex::new(vec![]),
+			found,
+		}
+	}
+
+	/// Returns whether a directory should be read, which it shouldn't if
+	/// it's excluded by the check's options or has already been searched,
+	/// such as through a bind mount or a symbolic link.
+	pub fn enter(&self, dir: &Path) -> bool {
+		if dir != self.root && self.options.is_excluded_dir(dir) {
+			self.skipped(dir, SkipReason::ExcludedDir);
+			return false;
+		}
+
+		// A directory reached again would otherwise have its files reported
+		// as duplicates of themselves.
+		let retry = self.options.retry_policy();
+		let id = retry.run(|| dir.metadata()).ok().as_ref().and_then(file_id);
+
+		if id.is_some_and(|id| !lock(&self.searched).insert(id)) {
+			self.skipped(dir, SkipReason::AlreadySearched);
+			return false;
+		}
+
+		true
+	}
+
+	/// Returns the paths of the entries in a directory, recording an error
+	/// for the directory if it can't be read.
+	pub fn entries(&self, dir: &Path) -> Vec<PathBuf> {
+		let retry = self.options.retry_policy();
+		let read_dir = match retry.run(|| dir.read_dir()) {
+			Ok(entries) => entries,
+			Err(e) => {
+				self.error(dir.to_path_buf(), e);
+				return vec![];
+			}
+		};
+
+		self
+			.options
+			.emit(|| ScanEvent::DirEntered(dir.to_path_buf()));
+		self.options.record(|p| p.set_current_path(Some(dir)));
+		let mut entries = vec![];
+
+		for entry in read_dir {
+			self.options.checkpoint();
+
+			match entry {
+				Ok(ent) => entries.push(ent.path()),
+				Err(e) => self.error(dir.to_path_buf(), e),
+			};
+		}
+
+		entries
+	}
+
+	/// Adds a file to the check, unless it's skipped by the check's options.
+	pub fn file(&self, file: PathBuf) {
+		let options = self.options;
+		let retry = options.retry_policy();
+
+		if options.skip_junk() && is_junk(&file) {
+			self.skipped(&file, SkipReason::Junk);
+			return;
+		}
+
+		let metadata = match retry.run(|| file.metadata()) {
+			Ok(md) => md,
+			Err(e) => return self.error(file, e),
+		};
+
+		if options.skip_placeholders() && is_placeholder(&metadata) {
+			self.skipped(&file, SkipReason::Placeholder);
+			return;
+		}
+
+		if self.sizes.is_some_and(|s| !s.contains(&metadata.len())) {
+			self.skipped(&file, SkipReason::Size);
+			return;
+		}
+
+		match retry.run(|| options.allows_file(&file)) {
+			Ok(true) => {}
+			Ok(false) => return self.skipped(&file, SkipReason::Filtered),
+			Err(e) => return self.error(file, e),
+		};
+
+		options.record(Progress::add_file_found);
+		(self.found)(file);
+	}
+
+	/// Records an error found while searching, and sends it as an event.
+	pub fn error(&self, path: PathBuf, error: io::Error) {
+		let dup_error = DupError::new(path, error);
+		self
+			.options
+			.emit(|| ScanEvent::Error(dup_error.to_string()));
+		self.options.record(Progress::add_error);
+		lock(&self.errors).push(dup_error);
+	}
+
+	/// Returns the errors found while searching.
+	pub(crate) fn into_errors(self) -> Vec<DupError> {
+		self.errors.into_inner().unwrap_or_else(|e| e.into_inner())
+	}
+
+	/// Records a skipped file or directory, and sends it as an event.
+	fn skipped(&self, path: &Path, reason: SkipReason) {
+		self.options.skip(path, reason);
+	}
+}
+
+/// Searches a directory and its subdirectories one at a time, optionally
+/// sorting each directory's entries by path.
+fn walk_recursive(dir: &Path, visitor: &WalkVisitor, sorted: bool) {
+	if !visitor.enter(dir) {
+		return;
+	}
+
+	let mut entries = visitor.entries(dir);
+
+	if sorted {
+		entries.sort_unstable();
+	}
+
+	for entry in entries {
+		if entry.is_file() {
+			visitor.file(entry);
+		} else if entry.is_dir() {
+			walk_recursive(&entry, visitor, sorted);
+		}
+	}
+}
+
+/// Searches a directory and its subdirectories on as many threads as the
+/// system can run at once, with each thread taking the next directory
+/// waiting to be searched.
+fn walk_parallel(dir: &Path, visitor: &WalkVisitor) {
+	let threads = thread::available_parallelism().map_or(1, |n| n.get());
+
+	// The directories waiting to be searched, and the number being searched,
+	// which may add more.
+	let state = Mutex::new((vec![dir.to_path_buf()], 0));
+	let changed = Condvar::new();
+
+	thread::scope(|scope| {
+		for _ in 0..threads {
+			scope.spawn(|| {
+				if visitor.options.low_priority() {
+					priority::lower_thread_priority();
+				}
+
+				loop {
+					let dir = {
+						let mut state = lock(&state);
+
+						loop {
+							if let Some(dir) = state.0.pop() {
+								state.1 += 1;
+								break dir;
+							}
+
+							if state.1 == 0 {
+								return;
+							}
+
+							state = changed.wait(state).unwrap_or_else(|e| e.into_inner());
+						}
+					};
+
+					let mut subdirs = vec![];
+
+					if visitor.enter(&dir) {
+						for entry in visitor.entries(&dir) {
+							if entry.is_file() {
+								visitor.file(entry);
+							} else if entry.is_dir() {
+								subdirs.push(entry);
+							}
+						}
+					}
+
+					let mut state = lock(&state);
+					state.0.append(&mut subdirs);
+					state.1 -= 1;
+					changed.notify_all();
+				}
+			});
+		}
+	});
+}
+
+/// Locks a mutex, recovering its data if another thread panicked while
+/// holding the lock.
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+	mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
@@ -0,0 +1,179 @@
+use crate::json;
+use crate::DupResults;
+use std::io;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
+/// How long to wait to connect to, or for a response from, a webhook.
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+impl DupResults {
+	/// Returns a JSON summary of the results, with the numbers of duplicate
+	/// groups, redundant duplicate files and errors, the bytes that could be
+	/// reclaimed, and a `text` description of them for chat services.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use std::path::PathBuf;
+	///
+	/// let dirs = vec![PathBuf::from("foo")];
+	/// let mut dup_result = dupcheck::DupResults::new();
+	///
+	/// if let Err(dup_error) = dup_result.within(&dirs) {
+	///     // Error handling
+	/// }
+	///
+	/// println!("{}", dup_result.summary_json());
+	/// ```
+	pub fn summary_json(&self) -> String {
+		let groups = self.duplicates.len();
+		let files = self.file_count() - groups;
+		let bytes = self.reclaimable_bytes();
+		let errors = self.errors.len();
+		let text = format!(
+			"dupcheck found {} duplicate group{} with {} redundant file{} ({} bytes reclaimable) and {} error{}.",
+			groups,
+			if groups != 1 { "s" } else { "" },
+			files,
+			if files != 1 { "s" } else { "" },
+			bytes,
+			errors,
+			if errors != 1 { "s" } else { "" },
+		);
+
+		format!(
+			"{{\"text\":{},\"duplicate_groups\":{},\"duplicate_files\":{},\"reclaimable_bytes\":{},\"errors\":{}}}",
+			json::string(&text),
+			groups,
+			files,
+			bytes,
+			errors,
+		)
+	}
+
+	/// Sends the JSON summary given by `summary_json()` to a webhook in a
+	/// POST request, for chat and ticketing services to be notified of the
+	/// results.
+	///
+	/// `http://` URLs are requested directly, and `https://` URLs are
+	/// requested with `curl`, which must be installed.
+	///
+	/// # Errors
+	///
+	/// Returns an error if the URL isn't an `http://` or `https://` URL, if
+	/// the request fails or if the response status isn't successful.
+	///
+	/// # Examples
+	///
+	/// ```no_run
+	/// use std::path::PathBuf;
+	///
+	/// let dirs = vec![PathBuf::from("foo")];
+	/// let mut dup_result = dupcheck::DupResults::new();
+	///
+	/// if let Err(dup_error) = dup_result.within(&dirs) {
+	///     // Error handling
+	/// }
+	///
+	/// if let Err(webhook_error) = dup_result.notify_webhook("http://localhost:8000/hooks/dupcheck") {
+	///     // Error handling
+	/// }
+	/// ```
+	pub fn notify_webhook(&self, url: &str) -> io::Result<()> {
+		post(url, &self.summary_json())
+	}
+}
+
+/// Sends a JSON body to a URL in a POST request.
+pub(crate) fn post(url: &str, body: &str) -> io::Result<()> {
+	if url.starts_with("https://") {
+		return post_with_curl(url, body);
+	}
+
+	let rest = url.strip_prefix("http://").ok_or_else(|| {
+		io::Error::new(
+			io::ErrorKind::InvalidInput,
+			"webhook URL must start with http:// or https://",
+		)
+	})?;
+	let (host, path) = match rest.find('/') {
+		Some(i) => (&rest[..i], &rest[i..]),
+		None => (rest, "/"),
+	};
+	let address = match host.contains(':') {
+		true => host.to_string(),
+		false => format!("{}:80", host),
+	};
+	let socket_address = address
+		.to_socket_addrs()?
+		.next()
+		.ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "webhook host couldn't be resolved"))?;
+
+	let mut stream = TcpStream::connect_timeout(&socket_address, TIMEOUT)?;
+	stream.set_read_timeout(Some(TIMEOUT))?;
+	write!(
+		stream,
+		"POST {} HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+		path,
+		host,
+		body.len(),
+		body,
+	)?;
+
+	let mut status_line = String::new();
+	BufReader::new(stream).read_line(&mut status_line)?;
+
+	match status_line.split_whitespace().nth(1) {
+		Some(status) if status.starts_with('2') => Ok(()),
+		_ => Err(io::Error::other(format!(
+			"webhook responded with {}",
+			status_line.trim()
+		))),
+	}
+}
+
+/// Sends a JSON body to a URL in a POST request using `curl`.
+fn post_with_curl(url: &str, body: &str) -> io::Result<()> {
+	let mut child = Command::new("curl")
+		.args([
+			"--silent",
+			"--show-error",
+			"--fail",
+			"--max-time",
+			&TIMEOUT.as_secs().to_string(),
+			"--header",
+			"Content-Type: application/json",
+			"--data-binary",
+			"@-",
+			"--output",
+			if cfg!(windows) { "NUL" } else { "/dev/null" },
+			url,
+		])
+		.stdin(Stdio::piped())
+		.stdout(Stdio::null())
+		.stderr(Stdio::piped())
+		.spawn()
+		.map_err(|e| match e.kind() {
+			io::ErrorKind::NotFound => io::Error::new(
+				io::ErrorKind::NotFound,
+				"curl must be installed to send webhooks to https:// URLs",
+			),
+			_ => e,
+		})?;
+
+	if let Some(mut stdin) = child.stdin.take() {
+		stdin.write_all(body.as_bytes())?;
+	}
+
+	let output = child.wait_with_output()?;
+
+	match output.status.success() {
+		true => Ok(()),
+		false => Err(io::Error::other(
+			String::from_utf8_lossy(&output.stderr).trim().to_string(),
+		)),
+	}
+}